@@ -0,0 +1,9 @@
+-- The Visual Pinball example table script ported to Lua.
+-- This behaves the same as src/pinball/scripts/example_table.rs and takes precedence over it.
+-- Like there, draining and releasing balls is left to the built-in drain logic.
+
+function Table1_Init()
+    -- Wall15 keeps the ball in the plunger lane but also blocks the plunger,
+    -- which we can't model yet, so we drop it.
+    Wall15.IsDropped = true
+end
@@ -0,0 +1,618 @@
+//! Input actions that decouple gameplay from physical keys, buttons and axes.
+//!
+//! Gameplay systems should read [`ActionState`] instead of `ButtonInput<KeyCode>` so that
+//! players can play with a keyboard, a gamepad or a cabinet controller.
+//! Systems in `FixedUpdate` should read [`FixedActionState`] instead, which is fed by a
+//! timestamped buffer so that no press or release is lost or seen twice.
+//!
+//! The [`InputBindings`] are stored in a [settings file](crate::pinball::settings_file) with the
+//! inputs of each action on a line:
+//!
+//! ```text
+//! vpinball2d-input 1
+//! left-flipper key:ShiftLeft button:LeftTrigger button:LeftTrigger2
+//! nudge-left key:KeyZ axis:RightStickX:-0.8
+//! plunger-axis LeftStickY:inverted
+//! ```
+
+use crate::pinball::settings_file::{self, StoredSettings};
+use bevy::input::InputSystems;
+use bevy::prelude::*;
+use bevy::reflect::{DynamicEnum, DynamicVariant, Enum, FromReflect};
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_plugins(settings_file::plugin::<InputBindings>);
+    app.init_resource::<ActionState>();
+    app.init_resource::<ActionBuffer>();
+    app.init_resource::<FixedActionState>();
+    // edges are recorded before the fixed steps of the frame run, so they see them in this frame
+    app.add_systems(
+        PreUpdate,
+        (update_action_state, record_action_edges)
+            .chain()
+            .after(InputSystems),
+    );
+    app.add_systems(FixedPreUpdate, consume_action_edges);
+}
+
+/// Everything a player can do, independent of the input device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputAction {
+    LeftFlipper,
+    RightFlipper,
+    Plunger,
+    NudgeLeft,
+    NudgeRight,
+    NudgeCenter,
+    Start,
+    Coin,
+    Pause,
+    ExtraBall,
+    /// Browse menus, like picking letters when entering high score initials.
+    MenuLeft,
+    MenuRight,
+    /// Drag the ball around with the mouse, for development purposes.
+    BallControl,
+}
+
+impl InputAction {
+    pub const ALL: [InputAction; 13] = [
+        InputAction::LeftFlipper,
+        InputAction::RightFlipper,
+        InputAction::Plunger,
+        InputAction::NudgeLeft,
+        InputAction::NudgeRight,
+        InputAction::NudgeCenter,
+        InputAction::Start,
+        InputAction::Coin,
+        InputAction::Pause,
+        InputAction::ExtraBall,
+        InputAction::MenuLeft,
+        InputAction::MenuRight,
+        InputAction::BallControl,
+    ];
+
+    /// The name in the input settings file.
+    fn name(self) -> &'static str {
+        match self {
+            InputAction::LeftFlipper => "left-flipper",
+            InputAction::RightFlipper => "right-flipper",
+            InputAction::Plunger => "plunger",
+            InputAction::NudgeLeft => "nudge-left",
+            InputAction::NudgeRight => "nudge-right",
+            InputAction::NudgeCenter => "nudge-center",
+            InputAction::Start => "start",
+            InputAction::Coin => "coin",
+            InputAction::Pause => "pause",
+            InputAction::ExtraBall => "extra-ball",
+            InputAction::MenuLeft => "menu-left",
+            InputAction::MenuRight => "menu-right",
+            InputAction::BallControl => "ball-control",
+        }
+    }
+}
+
+/// Continuous inputs, read as a value instead of a pressed state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AxisAction {
+    /// How far the plunger is pulled back.
+    Plunger,
+}
+
+impl AxisAction {
+    pub const ALL: [AxisAction; 1] = [AxisAction::Plunger];
+
+    /// The name in the input settings file.
+    fn name(self) -> &'static str {
+        match self {
+            AxisAction::Plunger => "plunger-axis",
+        }
+    }
+}
+
+/// A gamepad axis that drives an [`AxisAction`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AxisBinding {
+    pub axis: GamepadAxis,
+    /// Flip the sign of the raw axis value.
+    pub inverted: bool,
+}
+
+impl AxisBinding {
+    /// The binding as written in the input settings file, e.g. `LeftStickY:inverted`.
+    fn to_setting(self) -> String {
+        let axis = self.axis.variant_name();
+        if self.inverted {
+            format!("{axis}:inverted")
+        } else {
+            axis.to_string()
+        }
+    }
+
+    fn from_setting(setting: &str) -> Result<Self, String> {
+        let (axis, inverted) = match setting.split_once(':') {
+            Some((axis, "inverted")) => (axis, true),
+            Some(_) => {
+                return Err(format!(
+                    "expected an axis or an inverted axis, not '{setting}'"
+                ));
+            }
+            None => (setting, false),
+        };
+        Ok(Self {
+            axis: unit_variant(axis)?,
+            inverted,
+        })
+    }
+}
+
+/// A single physical input that can trigger an [`InputAction`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputBinding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    GamepadButton(GamepadButton),
+    /// A gamepad axis that counts as pressed once it goes past `threshold`.
+    /// A negative threshold triggers on the negative side of the axis.
+    GamepadAxis {
+        axis: GamepadAxis,
+        threshold: f32,
+    },
+}
+
+impl InputBinding {
+    /// The binding as written in the input settings file, e.g. `key:ShiftLeft`.
+    fn to_setting(self) -> String {
+        match self {
+            InputBinding::Key(key) => format!("key:{}", key.variant_name()),
+            InputBinding::Mouse(button) => format!("mouse:{}", button.variant_name()),
+            InputBinding::GamepadButton(button) => format!("button:{}", button.variant_name()),
+            InputBinding::GamepadAxis { axis, threshold } => {
+                format!("axis:{}:{threshold}", axis.variant_name())
+            }
+        }
+    }
+
+    fn from_setting(setting: &str) -> Result<Self, String> {
+        let (kind, name) = setting
+            .split_once(':')
+            .ok_or_else(|| format!("expected a kind of input and its name, not '{setting}'"))?;
+        match kind {
+            "key" => unit_variant(name).map(InputBinding::Key),
+            "mouse" => unit_variant(name).map(InputBinding::Mouse),
+            "button" => unit_variant(name).map(InputBinding::GamepadButton),
+            "axis" => {
+                let (axis, threshold) = name
+                    .split_once(':')
+                    .ok_or_else(|| format!("expected an axis and a threshold, not '{name}'"))?;
+                Ok(InputBinding::GamepadAxis {
+                    axis: unit_variant(axis)?,
+                    threshold: threshold
+                        .parse()
+                        .map_err(|_| format!("invalid threshold '{threshold}'"))?,
+                })
+            }
+            _ => Err(format!("unknown kind of input '{kind}'")),
+        }
+    }
+
+    /// Whether the input is pressed, and whether it was pressed during the last frame. A press
+    /// can be over before the frame ends, then it is just pressed without being pressed.
+    fn state(
+        &self,
+        keys: &ButtonInput<KeyCode>,
+        mouse_buttons: &ButtonInput<MouseButton>,
+        gamepads: &Query<&Gamepad>,
+    ) -> (bool, bool) {
+        match *self {
+            InputBinding::Key(key) => (keys.pressed(key), keys.just_pressed(key)),
+            InputBinding::Mouse(button) => (
+                mouse_buttons.pressed(button),
+                mouse_buttons.just_pressed(button),
+            ),
+            InputBinding::GamepadButton(button) => (
+                gamepads.iter().any(|gamepad| gamepad.pressed(button)),
+                gamepads.iter().any(|gamepad| gamepad.just_pressed(button)),
+            ),
+            InputBinding::GamepadAxis { axis, threshold } => {
+                let pressed = gamepads.iter().any(|gamepad| {
+                    let value = gamepad.get(axis).unwrap_or(0.0);
+                    if threshold < 0.0 {
+                        value <= threshold
+                    } else {
+                        value >= threshold
+                    }
+                });
+                (pressed, false)
+            }
+        }
+    }
+}
+
+/// Parses the name of an input without data like `ShiftLeft`, as given by [`Enum::variant_name`].
+fn unit_variant<T: FromReflect>(name: &str) -> Result<T, String> {
+    T::from_reflect(&DynamicEnum::new(name.to_string(), DynamicVariant::Unit))
+        .ok_or_else(|| format!("unknown input '{name}'"))
+}
+
+/// Maps each [`InputAction`] to the physical inputs that trigger it.
+///
+/// The defaults follow the Visual Pinball key layout, actions that are not in the settings file
+/// keep them.
+#[derive(Resource, Debug, Clone)]
+pub struct InputBindings {
+    bindings: HashMap<InputAction, Vec<InputBinding>>,
+    axes: HashMap<AxisAction, Vec<AxisBinding>>,
+}
+
+impl InputBindings {
+    pub fn bindings(&self, action: InputAction) -> &[InputBinding] {
+        self.bindings
+            .get(&action)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn axis_bindings(&self, action: AxisAction) -> &[AxisBinding] {
+        self.axes
+            .get(&action)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        let key = InputBinding::Key;
+        let button = InputBinding::GamepadButton;
+        let axis = |axis, threshold| InputBinding::GamepadAxis { axis, threshold };
+        let bindings = HashMap::from([
+            (
+                InputAction::LeftFlipper,
+                vec![
+                    key(KeyCode::ShiftLeft),
+                    button(GamepadButton::LeftTrigger),
+                    button(GamepadButton::LeftTrigger2),
+                ],
+            ),
+            (
+                InputAction::RightFlipper,
+                vec![
+                    key(KeyCode::ShiftRight),
+                    button(GamepadButton::RightTrigger),
+                    button(GamepadButton::RightTrigger2),
+                ],
+            ),
+            (
+                InputAction::Plunger,
+                vec![key(KeyCode::Enter), button(GamepadButton::South)],
+            ),
+            (
+                InputAction::NudgeLeft,
+                vec![key(KeyCode::KeyZ), axis(GamepadAxis::RightStickX, -0.8)],
+            ),
+            (
+                InputAction::NudgeRight,
+                vec![key(KeyCode::Slash), axis(GamepadAxis::RightStickX, 0.8)],
+            ),
+            (
+                InputAction::NudgeCenter,
+                vec![key(KeyCode::Space), axis(GamepadAxis::RightStickY, 0.8)],
+            ),
+            (
+                InputAction::Start,
+                vec![key(KeyCode::Digit1), button(GamepadButton::Start)],
+            ),
+            (
+                InputAction::Coin,
+                vec![key(KeyCode::Digit5), button(GamepadButton::Select)],
+            ),
+            (
+                InputAction::Pause,
+                vec![key(KeyCode::KeyP), button(GamepadButton::Mode)],
+            ),
+            (
+                InputAction::ExtraBall,
+                vec![key(KeyCode::KeyB), button(GamepadButton::North)],
+            ),
+            (
+                InputAction::MenuLeft,
+                vec![key(KeyCode::ArrowLeft), button(GamepadButton::DPadLeft)],
+            ),
+            (
+                InputAction::MenuRight,
+                vec![key(KeyCode::ArrowRight), button(GamepadButton::DPadRight)],
+            ),
+            (
+                InputAction::BallControl,
+                vec![InputBinding::Mouse(MouseButton::Left)],
+            ),
+        ]);
+        // Pulling the left stick down pulls the plunger.
+        let axes = HashMap::from([(
+            AxisAction::Plunger,
+            vec![AxisBinding {
+                axis: GamepadAxis::LeftStickY,
+                inverted: true,
+            }],
+        )]);
+        Self { bindings, axes }
+    }
+}
+
+impl StoredSettings for InputBindings {
+    const FILE_HEADER: &'static str = "vpinball2d-input";
+    const FILE_VERSION: u32 = 1;
+    const FILE_NAME: &'static str = "input.txt";
+
+    fn read(&mut self, key: &str, value: &str) -> Result<bool, String> {
+        if let Some(action) = InputAction::ALL.into_iter().find(|a| a.name() == key) {
+            let bindings = value
+                .split_whitespace()
+                .map(InputBinding::from_setting)
+                .collect::<Result<_, _>>()?;
+            self.bindings.insert(action, bindings);
+        } else if let Some(action) = AxisAction::ALL.into_iter().find(|a| a.name() == key) {
+            let bindings = value
+                .split_whitespace()
+                .map(AxisBinding::from_setting)
+                .collect::<Result<_, _>>()?;
+            self.axes.insert(action, bindings);
+        } else {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    fn write(&self) -> Vec<(&'static str, String)> {
+        let actions = InputAction::ALL.into_iter().map(|action| {
+            let bindings: Vec<_> = self
+                .bindings(action)
+                .iter()
+                .map(|b| b.to_setting())
+                .collect();
+            (action.name(), bindings.join(" "))
+        });
+        let axes = AxisAction::ALL.into_iter().map(|action| {
+            let bindings: Vec<_> = self
+                .axis_bindings(action)
+                .iter()
+                .map(|b| b.to_setting())
+                .collect();
+            (action.name(), bindings.join(" "))
+        });
+        actions.chain(axes).collect()
+    }
+}
+
+/// The state of every [`InputAction`] for the current frame.
+#[derive(Resource, Debug, Default)]
+pub struct ActionState {
+    actions: ButtonInput<InputAction>,
+    axes: HashMap<AxisAction, f32>,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: InputAction) -> bool {
+        self.actions.pressed(action)
+    }
+
+    pub fn just_pressed(&self, action: InputAction) -> bool {
+        self.actions.just_pressed(action)
+    }
+
+    pub fn just_released(&self, action: InputAction) -> bool {
+        self.actions.just_released(action)
+    }
+
+    /// The raw value of an [`AxisAction`], or `None` if no bound axis is connected.
+    pub fn axis(&self, action: AxisAction) -> Option<f32> {
+        self.axes.get(&action).copied()
+    }
+}
+
+fn update_action_state(
+    bindings: Res<InputBindings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    mut state: ResMut<ActionState>,
+) {
+    state.actions.clear();
+    for action in InputAction::ALL {
+        let (pressed, just_pressed) = bindings.bindings(action).iter().fold(
+            (false, false),
+            |(pressed, just_pressed), binding| {
+                let (binding_pressed, binding_just_pressed) =
+                    binding.state(&keys, &mouse_buttons, &gamepads);
+                (
+                    pressed || binding_pressed,
+                    just_pressed || binding_just_pressed,
+                )
+            },
+        );
+        // a press that is already released still counts as just pressed, and then released
+        if pressed || just_pressed {
+            state.actions.press(action);
+        }
+        if !pressed {
+            state.actions.release(action);
+        }
+    }
+    state.axes.clear();
+    for action in AxisAction::ALL {
+        // the axis that is furthest from its rest position wins
+        let value = bindings
+            .axis_bindings(action)
+            .iter()
+            .flat_map(|binding| {
+                gamepads.iter().filter_map(move |gamepad| {
+                    let value = gamepad.get(binding.axis)?;
+                    Some(if binding.inverted { -value } else { value })
+                })
+            })
+            .max_by(|a, b| a.abs().total_cmp(&b.abs()));
+        if let Some(value) = value {
+            state.axes.insert(action, value);
+        }
+    }
+}
+
+/// A press or release of an [`InputAction`] at a point in virtual time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ActionEdge {
+    action: InputAction,
+    pressed: bool,
+    time: Duration,
+}
+
+/// Press and release edges recorded every frame, waiting to be consumed by fixed steps.
+#[derive(Resource, Debug, Default)]
+struct ActionBuffer {
+    edges: VecDeque<ActionEdge>,
+}
+
+/// The state of every [`InputAction`] for the current fixed step.
+///
+/// `just_released` is true for exactly one fixed step, no matter how many frames or fixed steps
+/// pass in between.
+#[derive(Resource, Debug, Default)]
+pub struct FixedActionState {
+    actions: ButtonInput<InputAction>,
+}
+
+impl FixedActionState {
+    pub fn pressed(&self, action: InputAction) -> bool {
+        self.actions.pressed(action)
+    }
+
+    pub fn just_released(&self, action: InputAction) -> bool {
+        self.actions.just_released(action)
+    }
+}
+
+fn record_action_edges(
+    actions: Res<ActionState>,
+    time: Res<Time<Virtual>>,
+    mut buffer: ResMut<ActionBuffer>,
+) {
+    for action in InputAction::ALL {
+        if actions.just_pressed(action) {
+            buffer.edges.push_back(ActionEdge {
+                action,
+                pressed: true,
+                time: time.elapsed(),
+            });
+        }
+        if actions.just_released(action) {
+            buffer.edges.push_back(ActionEdge {
+                action,
+                pressed: false,
+                time: time.elapsed(),
+            });
+        }
+    }
+}
+
+/// Applies all edges that happened before the end of the current fixed step.
+///
+/// Fixed time never runs ahead of virtual time, so edges recorded in a frame are applied in the
+/// first fixed step that reaches their timestamp, or wait for a later frame if there is none.
+fn consume_action_edges(
+    time: Res<Time<Fixed>>,
+    mut buffer: ResMut<ActionBuffer>,
+    mut state: ResMut<FixedActionState>,
+) {
+    state.actions.clear();
+    while let Some(edge) = buffer.edges.front()
+        && edge.time <= time.elapsed()
+    {
+        if edge.pressed {
+            state.actions.press(edge.action);
+        } else {
+            state.actions.release(edge.action);
+        }
+        buffer.edges.pop_front();
+    }
+}
+
+/// Run condition that is active if the given [`InputAction`] was just pressed.
+pub fn action_just_pressed(action: InputAction) -> impl FnMut(Res<ActionState>) -> bool + Clone {
+    move |state: Res<ActionState>| state.just_pressed(action)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bindings_round_trip() {
+        let defaults = InputBindings::default();
+        let mut bindings = InputBindings {
+            bindings: HashMap::new(),
+            axes: HashMap::new(),
+        };
+        for (key, value) in defaults.write() {
+            assert!(bindings.read(key, &value).unwrap(), "unknown setting {key}");
+        }
+        for action in InputAction::ALL {
+            assert_eq!(bindings.bindings(action), defaults.bindings(action));
+        }
+        for action in AxisAction::ALL {
+            assert_eq!(
+                bindings.axis_bindings(action),
+                defaults.axis_bindings(action)
+            );
+        }
+    }
+
+    #[test]
+    fn reads_the_file_format() {
+        let mut bindings = InputBindings::default();
+        bindings
+            .read(
+                "left-flipper",
+                "key:KeyA button:LeftTrigger axis:LeftStickX:-0.5",
+            )
+            .unwrap();
+        bindings.read("pause", "").unwrap();
+        bindings
+            .read("plunger-axis", "RightStickY:inverted")
+            .unwrap();
+        assert_eq!(
+            bindings.bindings(InputAction::LeftFlipper),
+            [
+                InputBinding::Key(KeyCode::KeyA),
+                InputBinding::GamepadButton(GamepadButton::LeftTrigger),
+                InputBinding::GamepadAxis {
+                    axis: GamepadAxis::LeftStickX,
+                    threshold: -0.5,
+                },
+            ]
+        );
+        assert!(bindings.bindings(InputAction::Pause).is_empty());
+        assert_eq!(
+            bindings.axis_bindings(AxisAction::Plunger),
+            [AxisBinding {
+                axis: GamepadAxis::RightStickY,
+                inverted: true,
+            }]
+        );
+        // the other actions keep their defaults
+        assert_eq!(
+            bindings.bindings(InputAction::Start),
+            InputBindings::default().bindings(InputAction::Start)
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_inputs() {
+        let mut bindings = InputBindings::default();
+        assert!(bindings.read("start", "key:NoSuchKey").is_err());
+        assert!(bindings.read("start", "ShiftLeft").is_err());
+        assert!(bindings.read("start", "axis:LeftStickX").is_err());
+        assert!(!bindings.read("fly", "key:KeyF").unwrap());
+    }
+}
@@ -7,10 +7,12 @@ mod asset_tracking;
 mod audio;
 #[cfg(feature = "dev")]
 mod dev_tools;
+mod input;
 mod menus;
 mod pinball;
 mod screens;
 mod theme;
+mod vbscript;
 mod vpx;
 
 // mod diagnostics;
@@ -55,6 +57,8 @@ impl Plugin for AppPlugin {
                         ..default()
                     }
                     .into(),
+                    // the backglass window closes with the playfield
+                    exit_condition: bevy::window::ExitCondition::OnPrimaryClosed,
                     ..default()
                 })
                 .set(AudioPlugin {
@@ -64,7 +68,9 @@ impl Plugin for AppPlugin {
             // One unit in bevy is one meter
             // However I have the impression that this should be adjusted to the average object size
             // in the scene? So we set it to 0.1 to have more reasonable values for debug rendering
-            PhysicsPlugins::default().with_length_unit(0.1),
+            PhysicsPlugins::default()
+                .with_length_unit(0.1)
+                .with_collision_hooks::<pinball::height::HeightCollisionHooks>(),
             // crate::diagnostics::DiagnosticsPlugin,
         ));
         // gravity of approx. 9.81 m/s² but with a table at 7° angle
@@ -80,6 +86,7 @@ impl Plugin for AppPlugin {
             VpxPlugin,
             asset_tracking::plugin,
             audio::plugin,
+            input::plugin,
             pinball::plugin,
             #[cfg(feature = "dev")]
             dev_tools::plugin,
@@ -2,12 +2,26 @@
 //!
 //! Additional settings and accessibility options should go here.
 
-use bevy::{audio::Volume, input::common_conditions::input_just_pressed, prelude::*};
+use bevy::{
+    audio::Volume, ecs::spawn::SpawnableList, input::common_conditions::input_just_pressed,
+    prelude::*,
+};
 
-use crate::{menus::Menu, screens::Screen, theme::prelude::*};
+use crate::{
+    menus::Menu,
+    pinball::{
+        backglass_window::WindowSettings,
+        credits::CreditSettings,
+        plunger::{AnalogPlungerSettings, PlungerCalibration},
+        view::ViewSettings,
+    },
+    screens::Screen,
+    theme::prelude::*,
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(Menu::Settings), spawn_settings_menu);
+    app.add_systems(OnExit(Menu::Settings), stop_plunger_calibration);
     app.add_systems(
         Update,
         go_back.run_if(in_state(Menu::Settings).and(input_just_pressed(KeyCode::Escape))),
@@ -15,7 +29,17 @@ pub(super) fn plugin(app: &mut App) {
 
     app.add_systems(
         Update,
-        update_global_volume_label.run_if(in_state(Menu::Settings)),
+        (
+            update_global_volume_label,
+            update_coins_per_credit_label,
+            update_view_mode_label,
+            update_plunger_dead_zone_label,
+            update_toggle_labels::<CreditSettings>,
+            update_toggle_labels::<WindowSettings>,
+            update_toggle_labels::<ViewSettings>,
+            update_toggle_labels::<PlungerCalibration>,
+        )
+            .run_if(in_state(Menu::Settings)),
     );
 }
 
@@ -42,16 +66,70 @@ fn settings_grid() -> impl Bundle {
             grid_template_columns: RepeatedGridTrack::px(2, 400.0),
             ..default()
         },
-        children![
-            (
-                widget::label("Master Volume"),
-                Node {
-                    justify_self: JustifySelf::End,
-                    ..default()
-                }
+        // rows are nested, `children!` takes only so many entries
+        Children::spawn((
+            settings_row("Master Volume", global_volume_widget()),
+            settings_row(
+                "Free Play",
+                toggle_widget::<CreditSettings>(
+                    "Free Play",
+                    |settings| settings.free_play,
+                    |settings, on| settings.free_play = on,
+                ),
             ),
-            global_volume_widget(),
-        ],
+            settings_row("Coins per Credit", coins_per_credit_widget()),
+            // there is only the browser canvas on the web
+            #[cfg(not(target_family = "wasm"))]
+            settings_row(
+                "Backglass Window",
+                toggle_widget::<WindowSettings>(
+                    "Backglass Window",
+                    |settings| settings.backglass_window,
+                    |settings, on| settings.backglass_window = on,
+                ),
+            ),
+            settings_row("View Mode", view_mode_widget()),
+            settings_row(
+                "Follow Ball",
+                toggle_widget::<ViewSettings>(
+                    "Follow Ball",
+                    |settings| settings.follow_ball,
+                    |settings, on| settings.follow_ball = on,
+                ),
+            ),
+            settings_row(
+                "Drop Shadows",
+                toggle_widget::<ViewSettings>(
+                    "Drop Shadows",
+                    |settings| settings.drop_shadows,
+                    |settings, on| settings.drop_shadows = on,
+                ),
+            ),
+            // hold the plunger at rest, turn this on, pull the plunger all the way and turn it off
+            settings_row(
+                "Calibrate Plunger",
+                toggle_widget::<PlungerCalibration>(
+                    "Calibrate Plunger",
+                    |calibration| calibration.active,
+                    |calibration, on| calibration.active = on,
+                ),
+            ),
+            settings_row("Plunger Dead Zone", plunger_dead_zone_widget()),
+        )),
+    )
+}
+
+/// A label and its widget, in the two columns of the settings grid.
+fn settings_row(label: &'static str, control: impl Bundle) -> impl SpawnableList<ChildOf> {
+    (
+        Spawn((
+            widget::label(label),
+            Node {
+                justify_self: JustifySelf::End,
+                ..default()
+            },
+        )),
+        Spawn(control),
     )
 }
 
@@ -103,6 +181,189 @@ fn update_global_volume_label(
     label.0 = format!("{percent:3.0}%");
 }
 
+/// An on and off setting of the resource `R`, turned off with `-` and on with `+`.
+fn toggle_widget<R: Resource>(
+    name: &'static str,
+    get: fn(&R) -> bool,
+    set: fn(&mut R, bool),
+) -> impl Bundle {
+    (
+        Name::new(format!("{name} Widget")),
+        Node {
+            justify_self: JustifySelf::Start,
+            ..default()
+        },
+        children![
+            widget::button_small(
+                "-",
+                move |_: On<Pointer<Click>>, mut settings: ResMut<R>| set(&mut settings, false)
+            ),
+            (
+                Name::new(format!("Current {name}")),
+                Node {
+                    padding: UiRect::horizontal(px(10)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                children![(widget::label(""), ToggleLabel { get })],
+            ),
+            widget::button_small(
+                "+",
+                move |_: On<Pointer<Click>>, mut settings: ResMut<R>| set(&mut settings, true)
+            ),
+        ],
+    )
+}
+
+#[derive(Component)]
+struct ToggleLabel<R: Resource> {
+    get: fn(&R) -> bool,
+}
+
+fn update_toggle_labels<R: Resource>(
+    settings: Res<R>,
+    mut labels: Query<(&mut Text, &ToggleLabel<R>)>,
+) {
+    for (mut text, label) in &mut labels {
+        text.0 = if (label.get)(&settings) { "On" } else { "Off" }.to_string();
+    }
+}
+
+fn coins_per_credit_widget() -> impl Bundle {
+    (
+        Name::new("Coins per Credit Widget"),
+        Node {
+            justify_self: JustifySelf::Start,
+            ..default()
+        },
+        children![
+            widget::button_small("-", lower_coins_per_credit),
+            (
+                Name::new("Current Coins per Credit"),
+                Node {
+                    padding: UiRect::horizontal(px(10)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                children![(widget::label(""), CoinsPerCreditLabel)],
+            ),
+            widget::button_small("+", raise_coins_per_credit),
+        ],
+    )
+}
+
+const MAX_COINS_PER_CREDIT: u32 = 10;
+
+fn lower_coins_per_credit(_: On<Pointer<Click>>, mut settings: ResMut<CreditSettings>) {
+    settings.coins_per_credit = (settings.coins_per_credit - 1).max(1);
+}
+
+fn raise_coins_per_credit(_: On<Pointer<Click>>, mut settings: ResMut<CreditSettings>) {
+    settings.coins_per_credit = (settings.coins_per_credit + 1).min(MAX_COINS_PER_CREDIT);
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct CoinsPerCreditLabel;
+
+fn update_coins_per_credit_label(
+    settings: Res<CreditSettings>,
+    mut label: Single<&mut Text, With<CoinsPerCreditLabel>>,
+) {
+    label.0 = settings.coins_per_credit.to_string();
+}
+
+fn view_mode_widget() -> impl Bundle {
+    (
+        Name::new("View Mode Widget"),
+        Node {
+            justify_self: JustifySelf::Start,
+            ..default()
+        },
+        children![
+            widget::button_small("-", previous_view_mode),
+            (
+                Name::new("Current View Mode"),
+                Node {
+                    padding: UiRect::horizontal(px(10)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                children![(widget::label(""), ViewModeLabel)],
+            ),
+            widget::button_small("+", next_view_mode),
+        ],
+    )
+}
+
+fn previous_view_mode(_: On<Pointer<Click>>, mut settings: ResMut<ViewSettings>) {
+    settings.view_mode = settings.view_mode.previous();
+}
+
+fn next_view_mode(_: On<Pointer<Click>>, mut settings: ResMut<ViewSettings>) {
+    settings.view_mode = settings.view_mode.next();
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct ViewModeLabel;
+
+fn update_view_mode_label(
+    settings: Res<ViewSettings>,
+    mut label: Single<&mut Text, With<ViewModeLabel>>,
+) {
+    label.0 = settings.view_mode.label().to_string();
+}
+
+fn plunger_dead_zone_widget() -> impl Bundle {
+    (
+        Name::new("Plunger Dead Zone Widget"),
+        Node {
+            justify_self: JustifySelf::Start,
+            ..default()
+        },
+        children![
+            widget::button_small("-", lower_plunger_dead_zone),
+            (
+                Name::new("Current Plunger Dead Zone"),
+                Node {
+                    padding: UiRect::horizontal(px(10)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                children![(widget::label(""), PlungerDeadZoneLabel)],
+            ),
+            widget::button_small("+", raise_plunger_dead_zone),
+        ],
+    )
+}
+
+const MAX_PLUNGER_DEAD_ZONE: f32 = 0.5;
+
+fn lower_plunger_dead_zone(_: On<Pointer<Click>>, mut settings: ResMut<AnalogPlungerSettings>) {
+    settings.dead_zone = (settings.dead_zone - 0.05).max(0.0);
+}
+
+fn raise_plunger_dead_zone(_: On<Pointer<Click>>, mut settings: ResMut<AnalogPlungerSettings>) {
+    settings.dead_zone = (settings.dead_zone + 0.05).min(MAX_PLUNGER_DEAD_ZONE);
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct PlungerDeadZoneLabel;
+
+fn update_plunger_dead_zone_label(
+    settings: Res<AnalogPlungerSettings>,
+    mut label: Single<&mut Text, With<PlungerDeadZoneLabel>>,
+) {
+    let percent = 100.0 * settings.dead_zone;
+    label.0 = format!("{percent:3.0}%");
+}
+
+fn stop_plunger_calibration(mut calibration: ResMut<PlungerCalibration>) {
+    calibration.active = false;
+}
+
 fn go_back_on_click(
     _: On<Pointer<Click>>,
     screen: Res<State<Screen>>,
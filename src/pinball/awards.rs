@@ -0,0 +1,237 @@
+//! Replays, specials and the match, like on classic electro-mechanical tables.
+//!
+//! Every award raises an event: [`ReplayAwarded`] when a player passes a replay score level,
+//! [`SpecialAwarded`] when a script awards a special through
+//! [`ScriptCommand::AwardSpecial`](crate::pinball::scripts::api::ScriptCommand::AwardSpecial),
+//! and [`MatchAwarded`] when the last digits of a score match the draw at the end of the game.
+//! The knocker sounds for every award, and each one is worth a credit, see [`Credits`].
+//!
+//! [`Credits`]: crate::pinball::credits::Credits
+
+use crate::audio::sound_effect;
+use crate::pinball::game::{GameOver, GameStarted, GameState};
+use crate::pinball::table::TableAssets;
+use crate::screens::Screen;
+use crate::vpx::VpxAsset;
+use crate::{AppSystems, PausableSystems};
+use bevy::prelude::*;
+use rand::Rng;
+use std::collections::VecDeque;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<AwardSettings>();
+    app.init_resource::<ReplayProgress>();
+    app.init_resource::<ScoreHistory>();
+    app.add_observer(reset_replays);
+    app.add_observer(award_final_replays);
+    app.add_observer(draw_match);
+    app.add_observer(adjust_replay_levels);
+    app.add_observer(|_: On<ReplayAwarded>, knocker: Knocker| knocker.knock());
+    app.add_observer(|_: On<SpecialAwarded>, knocker: Knocker| knocker.knock());
+    app.add_observer(|_: On<MatchAwarded>, knocker: Knocker| knocker.knock());
+    app.add_systems(
+        Update,
+        award_replays
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay))
+            .run_if(resource_changed::<GameState>),
+    );
+}
+
+/// Sounds tables commonly use for the knocker.
+const KNOCKER_SOUNDS: [&str; 3] = ["knocker", "fx_knocker", "Knocker"];
+
+/// The number of recent final scores used to auto-adjust the replay levels, a game adds one
+/// score per player.
+const AUTO_ADJUST_SCORES: usize = 20;
+/// The fraction of final scores that should earn a replay when auto-adjusting.
+const AUTO_ADJUST_REPLAY_RATE: f32 = 0.1;
+/// Auto-adjusted replay levels are rounded to this.
+const AUTO_ADJUST_ROUNDING: u64 = 1000;
+
+#[derive(Resource, Debug, Clone)]
+pub struct AwardSettings {
+    /// The scores that award a replay, from low to high.
+    pub replay_levels: Vec<u64>,
+    /// Move the replay levels so that about one in ten final scores earns a replay.
+    pub auto_adjust_replays: bool,
+    pub match_enabled: bool,
+}
+
+impl Default for AwardSettings {
+    fn default() -> Self {
+        Self {
+            replay_levels: vec![50_000, 100_000],
+            auto_adjust_replays: false,
+            match_enabled: true,
+        }
+    }
+}
+
+/// A player reached a replay score level.
+#[derive(Event, Debug, Clone)]
+#[allow(dead_code)]
+pub struct ReplayAwarded {
+    /// Index of the player, starting at 0.
+    pub player: usize,
+    pub level: u64,
+}
+
+/// A script awarded a special to the current player.
+#[derive(Event, Debug, Clone)]
+#[allow(dead_code)]
+pub struct SpecialAwarded {
+    /// Index of the player, starting at 0.
+    pub player: usize,
+}
+
+/// The last two digits of a player's score matched the draw at the end of the game.
+#[derive(Event, Debug, Clone)]
+#[allow(dead_code)]
+pub struct MatchAwarded {
+    /// Index of the player, starting at 0.
+    pub player: usize,
+    /// The drawn number, one of 00, 10, ..., 90.
+    pub number: u64,
+}
+
+/// The number of replay levels each player passed this game.
+#[derive(Resource, Debug, Default)]
+struct ReplayProgress(Vec<usize>);
+
+/// The final scores of the players of recent games, for auto-adjusting the replay levels.
+#[derive(Resource, Debug, Default)]
+struct ScoreHistory(VecDeque<u64>);
+
+fn reset_replays(_: On<GameStarted>, mut progress: ResMut<ReplayProgress>) {
+    progress.0.clear();
+}
+
+fn award_replays(
+    mut commands: Commands,
+    game: Res<GameState>,
+    settings: Res<AwardSettings>,
+    mut progress: ResMut<ReplayProgress>,
+) {
+    if !game.in_progress() {
+        return;
+    }
+    award_passed_levels(&mut commands, &game.scores(), &settings, &mut progress);
+}
+
+/// Points scored on the last ball may only be seen after the game is over.
+fn award_final_replays(
+    game_over: On<GameOver>,
+    mut commands: Commands,
+    settings: Res<AwardSettings>,
+    mut progress: ResMut<ReplayProgress>,
+) {
+    award_passed_levels(&mut commands, &game_over.scores, &settings, &mut progress);
+}
+
+/// Awards a replay for every level a player passed since the last check.
+fn award_passed_levels(
+    commands: &mut Commands,
+    scores: &[u64],
+    settings: &AwardSettings,
+    progress: &mut ReplayProgress,
+) {
+    progress.0.resize(scores.len(), 0);
+    for (player, score) in scores.iter().enumerate() {
+        let passed = settings
+            .replay_levels
+            .iter()
+            .filter(|level| *score >= **level)
+            .count();
+        for &level in &settings.replay_levels[progress.0[player].min(passed)..passed] {
+            info!("Replay for player {} at {}", player + 1, level);
+            commands.trigger(ReplayAwarded { player, level });
+        }
+        progress.0[player] = progress.0[player].max(passed);
+    }
+}
+
+/// Awards a special to the current player, does nothing if no game is in progress.
+pub fn award_special(commands: &mut Commands, game: &GameState) {
+    if !game.in_progress() {
+        return;
+    }
+    let player = game.current_player;
+    info!("Special for player {}", player + 1);
+    commands.trigger(SpecialAwarded { player });
+}
+
+fn draw_match(game_over: On<GameOver>, mut commands: Commands, settings: Res<AwardSettings>) {
+    if !settings.match_enabled {
+        return;
+    }
+    let number = rand::rng().random_range(0..10) * 10;
+    info!("Match {:02}", number);
+    for (player, score) in game_over.scores.iter().enumerate() {
+        // a player that didn't score can't match 00
+        if *score > 0 && score % 100 == number {
+            info!("Match for player {}", player + 1);
+            commands.trigger(MatchAwarded { player, number });
+        }
+    }
+}
+
+/// Moves the replay levels so that about [`AUTO_ADJUST_REPLAY_RATE`] of the recent final scores
+/// earned a replay, keeping the ratio between the levels.
+fn adjust_replay_levels(
+    game_over: On<GameOver>,
+    mut history: ResMut<ScoreHistory>,
+    mut settings: ResMut<AwardSettings>,
+) {
+    history.0.extend(game_over.scores.iter().copied());
+    while history.0.len() > AUTO_ADJUST_SCORES {
+        history.0.pop_front();
+    }
+    if !settings.auto_adjust_replays || history.0.len() < AUTO_ADJUST_SCORES / 2 {
+        return;
+    }
+    // a first level of 0 has no ratio to scale by
+    let Some(&first_level) = settings.replay_levels.first().filter(|level| **level > 0) else {
+        return;
+    };
+    let mut scores: Vec<u64> = history.0.iter().copied().collect();
+    scores.sort_unstable();
+    let index = ((scores.len() as f32) * (1.0 - AUTO_ADJUST_REPLAY_RATE)) as usize;
+    let target = scores[index.min(scores.len() - 1)];
+    let round = |score: f64| {
+        ((score / AUTO_ADJUST_ROUNDING as f64).round() as u64)
+            .max(1)
+            .saturating_mul(AUTO_ADJUST_ROUNDING)
+    };
+    let scale = target as f64 / first_level as f64;
+    let levels: Vec<u64> = settings
+        .replay_levels
+        .iter()
+        .map(|level| round(*level as f64 * scale))
+        .collect();
+    if levels != settings.replay_levels {
+        info!("Replay levels adjusted to {:?}", levels);
+        settings.replay_levels = levels;
+    }
+}
+
+#[derive(bevy::ecs::system::SystemParam)]
+struct Knocker<'w, 's> {
+    commands: Commands<'w, 's>,
+    table_assets: Res<'w, TableAssets>,
+    assets_vpx: Res<'w, Assets<VpxAsset>>,
+}
+
+impl Knocker<'_, '_> {
+    fn knock(mut self) {
+        let sound = self
+            .assets_vpx
+            .get(&self.table_assets.vpx)
+            .and_then(|vpx_asset| KNOCKER_SOUNDS.iter().find_map(|name| vpx_asset.sound(name)))
+            .cloned();
+        if let Some(sound) = sound {
+            self.commands.spawn(sound_effect(sound));
+        }
+    }
+}
@@ -0,0 +1,447 @@
+//! The backglass beside the playfield, with the score reels of the table and, when the table
+//! ships a `<table>.directb2s` file, its backglass image, lights and score displays. The score
+//! displays of a directb2s replace the reels.
+//!
+//! A directb2s bulb is lit by the data a script sets for its B2S id through
+//! [`ScriptCommand::SetB2sData`](crate::pinball::scripts::api::ScriptCommand::SetB2sData), like
+//! `Controller.B2SSetData` in VPX. Bulbs without script data follow the table light with the same
+//! name, the others keep their initial state.
+//!
+//! While the [`BackglassWindow`] is open the backglass and the DMD are shown there, with the
+//! backglass image of the table when there is no directb2s file.
+
+use crate::pinball::backglass_window::BackglassWindow;
+use crate::pinball::dmd::DmdPanel;
+use crate::pinball::game::GameState;
+use crate::pinball::hud::{Hud, side_panel_width};
+use crate::pinball::light::{Light, LightState};
+use crate::pinball::reel::{Reel, spawn_reel};
+use crate::pinball::scripts::registry::sidecar_exists;
+use crate::pinball::table::{TableAssets, TableBackglass};
+use crate::pinball::view::{ViewMode, ViewSettings};
+use crate::screens::Screen;
+use crate::vpx::VpxAsset;
+use crate::vpx::directb2s::{B2sBulb, DirectB2sAsset};
+use crate::{AppSystems, PausableSystems};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use std::collections::HashMap;
+use std::path::Path;
+use vpin::vpx::gameitem::GameItemEnum;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<B2sData>();
+    app.add_systems(OnEnter(Screen::GameSetup), load_directb2s);
+    app.add_systems(
+        Update,
+        (show_directb2s, update_bulbs, update_b2s_scores)
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    app.add_systems(
+        Update,
+        (
+            follow_backglass_window,
+            show_table_backglass,
+            layout_backbox,
+            fit_b2s_score_text,
+        )
+            .in_set(AppSystems::Update)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    app.add_systems(OnExit(Screen::Gameplay), |mut data: ResMut<B2sData>| {
+        data.0.clear();
+    });
+}
+
+/// The size of the VPX backglass editor, which reel positions are relative to.
+pub(super) const BACKGLASS_WIDTH: f32 = 1000.0;
+pub(super) const BACKGLASS_HEIGHT: f32 = 750.0;
+/// Bulbs without an image light up their area in their color, this much see-through.
+const BULB_COLOR_ALPHA: f32 = 0.5;
+/// Score digits fill this much of the height of their display.
+const SCORE_TEXT_HEIGHT: f32 = 0.8;
+
+/// The data scripts set for B2S ids.
+#[derive(Resource, Debug, Default)]
+pub struct B2sData(pub HashMap<i32, i32>);
+
+#[derive(Resource)]
+struct DirectB2sHandle(Handle<DirectB2sAsset>);
+
+/// The area beside the playfield the backglass is placed in.
+#[derive(Component)]
+struct Backbox;
+
+#[derive(Component)]
+struct BackboxGlass;
+
+/// Marks the glass once the contents of the directb2s file are shown on it.
+#[derive(Component)]
+struct DirectB2sShown;
+
+#[derive(Component)]
+struct BulbNode(B2sBulb);
+
+/// The score display of a player, by index starting at 0.
+#[derive(Component)]
+struct ScoreNode {
+    player: usize,
+    digits: usize,
+}
+
+fn load_directb2s(
+    mut commands: Commands,
+    table_assets: Res<TableAssets>,
+    asset_server: Res<AssetServer>,
+) {
+    let table_path = Path::new(&table_assets.file_name);
+    if sidecar_exists(table_path, "directb2s") {
+        let handle = asset_server.load(table_path.with_extension("directb2s"));
+        commands.insert_resource(DirectB2sHandle(handle));
+    } else {
+        commands.remove_resource::<DirectB2sHandle>();
+    }
+}
+
+/// Spawns the backglass with the reels of the table. The directb2s contents are added once the
+/// file has loaded.
+pub(super) fn spawn_backbox(commands: &mut Commands, vpx_asset: &VpxAsset) {
+    commands
+        .spawn((
+            Name::new("Backbox"),
+            Backbox,
+            Node::default(),
+            Pickable::IGNORE,
+            DespawnOnExit(Screen::Gameplay),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Name::new("Backbox Glass"),
+                    BackboxGlass,
+                    Node {
+                        width: percent(90),
+                        aspect_ratio: Some(BACKGLASS_WIDTH / BACKGLASS_HEIGHT),
+                        ..default()
+                    },
+                ))
+                .with_children(|glass| {
+                    let reels = vpx_asset
+                        .raw
+                        .gameitems
+                        .iter()
+                        .filter_map(|item| match item {
+                            GameItemEnum::Reel(reel) => Some(reel),
+                            _ => None,
+                        });
+                    for (player, reel) in reels.enumerate() {
+                        spawn_reel(glass, vpx_asset, reel, player);
+                    }
+                });
+        });
+}
+
+/// Node placement in percent of the backglass, which is laid out in pixels of its image.
+fn placed(rect: Rect, size: Vec2) -> Node {
+    Node {
+        position_type: PositionType::Absolute,
+        left: percent(rect.min.x / size.x * 100.0),
+        top: percent(rect.min.y / size.y * 100.0),
+        width: percent(rect.width() / size.x * 100.0),
+        height: percent(rect.height() / size.y * 100.0),
+        ..default()
+    }
+}
+
+fn show_directb2s(
+    mut commands: Commands,
+    handle: Option<Res<DirectB2sHandle>>,
+    b2s_assets: Res<Assets<DirectB2sAsset>>,
+    mut glass_query: Query<(Entity, &mut Node), (With<BackboxGlass>, Without<DirectB2sShown>)>,
+    mut reel_query: Query<&mut Visibility, With<Reel>>,
+) {
+    let Some(b2s) = handle.and_then(|handle| b2s_assets.get(&handle.0)) else {
+        return;
+    };
+    for (glass, mut node) in &mut glass_query {
+        commands.entity(glass).insert(DirectB2sShown);
+        let (Some(backglass), true) = (&b2s.backglass, b2s.size.min_element() > 0.0) else {
+            warn!("The directb2s file has no backglass image, its lights can't be placed");
+            continue;
+        };
+        info!(
+            "Showing directb2s backglass {}",
+            b2s.name.as_deref().unwrap_or_default()
+        );
+        node.aspect_ratio = Some(b2s.size.x / b2s.size.y);
+        // the score displays of the directb2s take the place of the reels
+        if !b2s.scores.is_empty() {
+            for mut visibility in &mut reel_query {
+                *visibility = Visibility::Hidden;
+            }
+        }
+        commands
+            .entity(glass)
+            .insert(ImageNode::new(backglass.clone()))
+            .with_children(|glass| {
+                for bulb in &b2s.bulbs {
+                    let mut entity = glass.spawn((
+                        Name::from(format!("Bulb {}", bulb.name)),
+                        BulbNode(bulb.clone()),
+                        placed(bulb.rect, b2s.size),
+                        Visibility::Hidden,
+                    ));
+                    match &bulb.image {
+                        Some(image) => entity.insert(ImageNode::new(image.clone())),
+                        None => {
+                            entity.insert(BackgroundColor(bulb.color.with_alpha(BULB_COLOR_ALPHA)))
+                        }
+                    };
+                }
+                for score in &b2s.scores {
+                    glass.spawn((
+                        Name::from(format!("Score Player {}", score.player + 1)),
+                        ScoreNode {
+                            player: score.player,
+                            digits: score.digits,
+                        },
+                        placed(score.rect, b2s.size),
+                        Text::default(),
+                        TextColor(score.color),
+                        TextLayout::new_with_justify(Justify::Right),
+                    ));
+                }
+            });
+    }
+}
+
+fn update_bulbs(
+    time: Res<Time>,
+    data: Res<B2sData>,
+    light_query: Query<(&Light, &LightState)>,
+    mut bulb_query: Query<(&BulbNode, &mut Visibility)>,
+) {
+    let elapsed = time.elapsed_secs();
+    for (BulbNode(bulb), mut visibility) in &mut bulb_query {
+        let script_value = bulb.b2s_id.and_then(|id| data.0.get(&id));
+        let light_state = || {
+            light_query
+                .iter()
+                .find(|(light, _)| light.name.eq_ignore_ascii_case(&bulb.name))
+                .map(|(_, state)| *state)
+        };
+        let lit = match (script_value, light_state()) {
+            (Some(value), _) if bulb.b2s_value == 0 => *value != 0,
+            (Some(value), _) => *value == bulb.b2s_value,
+            (None, Some(state)) => state.is_lit(elapsed),
+            (None, None) => bulb.initially_on,
+        };
+        visibility.set_if_neq(if lit {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+}
+
+fn update_b2s_scores(game: Res<GameState>, mut score_query: Query<(&ScoreNode, &mut Text)>) {
+    for (score, mut text) in &mut score_query {
+        let value = game
+            .players
+            .get(score.player)
+            .map(|player| player.score.to_string())
+            .unwrap_or_default();
+        // like the real display, only the last digits fit
+        let value = &value[value.len().saturating_sub(score.digits)..];
+        text.set_if_neq(Text(value.to_string()));
+    }
+}
+
+/// Scales the score digits with the backglass.
+fn fit_b2s_score_text(mut score_query: Query<(&ComputedNode, &mut TextFont), With<ScoreNode>>) {
+    for (computed, mut font) in &mut score_query {
+        let size = computed.size().y * computed.inverse_scale_factor() * SCORE_TEXT_HEIGHT;
+        if size > 0.0 && (font.font_size - size).abs() > 0.5 {
+            font.font_size = size;
+        }
+    }
+}
+
+/// Moves the backbox and the DMD to the backglass window while it is open, and back beside the
+/// playfield once it is closed.
+fn follow_backglass_window(
+    mut commands: Commands,
+    backglass_window: Option<Res<BackglassWindow>>,
+    backbox: Single<(Entity, Option<&UiTargetCamera>), With<Backbox>>,
+    dmd: Single<(Entity, &ChildOf), With<DmdPanel>>,
+    hud: Single<Entity, With<Hud>>,
+) {
+    let (backbox, target) = *backbox;
+    let (dmd, dmd_parent) = *dmd;
+    match (&backglass_window, target) {
+        (Some(window), target) if target.map(UiTargetCamera::entity) != Some(window.camera) => {
+            commands
+                .entity(backbox)
+                .insert(UiTargetCamera(window.camera));
+        }
+        (None, Some(_)) => {
+            commands.entity(backbox).remove::<UiTargetCamera>();
+        }
+        _ => {}
+    }
+    match (&backglass_window, dmd_parent.parent()) {
+        (Some(_), parent) if parent != backbox => {
+            commands.entity(backbox).add_child(dmd);
+        }
+        (None, parent) if parent != *hud => {
+            commands.entity(*hud).insert_children(0, &[dmd]);
+        }
+        _ => {}
+    }
+}
+
+/// Shows the backglass image of the table on the glass when the backglass is in a window of its
+/// own or above the playfield, instead of behind the playfield. A directb2s backglass takes its
+/// place once loaded.
+fn show_table_backglass(
+    mut commands: Commands,
+    backglass_window: Option<Res<BackglassWindow>>,
+    settings: Res<ViewSettings>,
+    glass_query: Query<(Entity, Has<ImageNode>), (With<BackboxGlass>, Without<DirectB2sShown>)>,
+    mut table_backglass_query: Query<&mut Visibility, With<TableBackglass>>,
+    table_assets: Res<TableAssets>,
+    assets_vpx: Res<Assets<VpxAsset>>,
+) {
+    let on_glass = backglass_window.is_some() || settings.view_mode == ViewMode::FullSingleScreen;
+    // cabinets have the backglass on a monitor of its own
+    let behind_playfield = !on_glass && settings.view_mode == ViewMode::Desktop;
+    for mut visibility in &mut table_backglass_query {
+        visibility.set_if_neq(if behind_playfield {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+    for (glass, has_image) in &glass_query {
+        if on_glass && !has_image {
+            let image = assets_vpx.get(&table_assets.vpx).and_then(|vpx_asset| {
+                vpx_asset
+                    .image(&vpx_asset.raw.gamedata.backglass_image_full_desktop)
+                    .cloned()
+            });
+            if let Some(image) = image {
+                commands.entity(glass).insert(ImageNode::new(image));
+            }
+        } else if !on_glass && has_image {
+            commands.entity(glass).remove::<ImageNode>();
+        }
+    }
+}
+
+/// Puts the backbox beside the playfield, opposite the HUD, or in the top corner if there is no
+/// room for it. Above the playfield and in the backglass window the glass is made as large as
+/// fits, in the window with the DMD below it. Cabinets have the backglass on a monitor of its own,
+/// so there it is only shown in the backglass window.
+fn layout_backbox(
+    window: Single<&Window, With<PrimaryWindow>>,
+    windows: Query<&Window>,
+    backglass_window: Option<Res<BackglassWindow>>,
+    settings: Res<ViewSettings>,
+    mut backbox_query: Query<&mut Node, With<Backbox>>,
+    mut glass_query: Query<&mut Node, (With<BackboxGlass>, Without<Backbox>)>,
+    dmd_query: Query<&ComputedNode, With<DmdPanel>>,
+    table_assets: Res<TableAssets>,
+    assets_vpx: Res<Assets<VpxAsset>>,
+) {
+    let Some(vpx_asset) = assets_vpx.get(&table_assets.vpx) else {
+        return;
+    };
+    let gamedata = &vpx_asset.raw.gamedata;
+    let table_size = Vec2::new(
+        gamedata.right - gamedata.left,
+        gamedata.bottom - gamedata.top,
+    );
+    let view_mode = settings.view_mode;
+    let separate_window =
+        backglass_window.and_then(|backglass_window| windows.get(backglass_window.window).ok());
+    // the room for the glass when it is made as large as fits
+    let (node, glass_room) = if let Some(window) = separate_window {
+        let dmd_height = dmd_query.single().map_or(0.0, |computed| {
+            computed.size().y * computed.inverse_scale_factor()
+        });
+        let node = Node {
+            width: percent(100),
+            height: percent(100),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        };
+        let room = Vec2::new(window.width(), (window.height() - dmd_height).max(0.0));
+        (node, Some(room))
+    } else if view_mode.rotated() {
+        let node = Node {
+            display: Display::None,
+            ..default()
+        };
+        (node, None)
+    } else if let Some(area) = view_mode.backglass_area(window.size(), table_size) {
+        let node = Node {
+            position_type: PositionType::Absolute,
+            left: px(area.min.x),
+            top: px(area.min.y),
+            width: px(area.width()),
+            height: px(area.height()),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        };
+        (node, Some(area.size()))
+    } else {
+        let node = match side_panel_width(&window, vpx_asset, view_mode) {
+            Some(side_width) => Node {
+                position_type: PositionType::Absolute,
+                right: px(0),
+                top: px(0),
+                width: px(side_width),
+                height: percent(100),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            None => Node {
+                position_type: PositionType::Absolute,
+                right: px(10),
+                top: px(10),
+                width: percent(35),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::End,
+                ..default()
+            },
+        };
+        (node, None)
+    };
+    for mut glass in &mut glass_query {
+        let width = match glass_room {
+            Some(room) => {
+                // keeping the aspect ratio of the glass
+                let aspect_ratio = glass
+                    .aspect_ratio
+                    .unwrap_or(BACKGLASS_WIDTH / BACKGLASS_HEIGHT);
+                px(room.x.min(room.y * aspect_ratio))
+            }
+            None => percent(90),
+        };
+        if glass.width != width {
+            glass.width = width;
+        }
+    }
+    for mut backbox in &mut backbox_query {
+        backbox.set_if_neq(node.clone());
+    }
+}
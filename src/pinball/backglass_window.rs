@@ -0,0 +1,242 @@
+//! An optional second window with the backglass and the DMD, for cabinets and multiple monitors.
+//!
+//! The window has its own camera that only renders the UI targeting it, the
+//! [`backglass`](super::backglass) module moves the backbox and the DMD over while the window is
+//! open. Whether the window is used and where both windows were left are stored in a
+//! [settings file](super::settings_file):
+//!
+//! ```text
+//! vpinball2d-windows 1
+//! backglass-window on
+//! playfield 0 0 1280 720
+//! backglass 1280 0 1024 768
+//! ```
+
+use crate::AppSystems;
+use crate::pinball::settings_file::{self, StoredSettings, on_off, read_on_off};
+use bevy::prelude::*;
+use bevy::window::{PrimaryWindow, WindowClosed, WindowMoved, WindowResized};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_plugins(settings_file::plugin::<WindowSettings>);
+    // the canvas fits the browser page
+    #[cfg(not(target_family = "wasm"))]
+    app.add_systems(Startup, place_primary_window);
+    app.add_systems(
+        Update,
+        (
+            track_window_placement,
+            forget_closed_backglass_window,
+            // there is only the browser canvas on the web
+            #[cfg(not(target_family = "wasm"))]
+            open_or_close_backglass_window.run_if(resource_changed::<WindowSettings>),
+        )
+            .chain()
+            .in_set(AppSystems::Update),
+    );
+}
+
+/// The size of the backglass window the first time it opens, in logical pixels.
+#[cfg(not(target_family = "wasm"))]
+const DEFAULT_BACKGLASS_SIZE: Vec2 = Vec2::new(1024.0, 768.0);
+/// Nothing of the table is on this layer, the backglass camera only renders the UI.
+#[cfg(not(target_family = "wasm"))]
+const BACKGLASS_LAYER: usize = 1;
+
+/// Where a window was left, in logical pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct WindowPlacement {
+    position: IVec2,
+    size: Vec2,
+}
+
+impl WindowPlacement {
+    #[cfg(not(target_family = "wasm"))]
+    fn apply(self, window: &mut Window) {
+        window.position = WindowPosition::At(self.position);
+        window.resolution.set(self.size.x, self.size.y);
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct WindowSettings {
+    /// Whether the backglass and the DMD get a window of their own.
+    pub backglass_window: bool,
+    playfield: Option<WindowPlacement>,
+    backglass: Option<WindowPlacement>,
+}
+
+impl StoredSettings for WindowSettings {
+    const FILE_HEADER: &'static str = "vpinball2d-windows";
+    const FILE_VERSION: u32 = 1;
+    const FILE_NAME: &'static str = "windows.txt";
+
+    fn read(&mut self, key: &str, value: &str) -> Result<bool, String> {
+        match key {
+            "backglass-window" => self.backglass_window = read_on_off(value)?,
+            "playfield" | "backglass" => {
+                let numbers = value
+                    .split_whitespace()
+                    .map(str::parse::<i32>)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| "invalid number".to_string())?;
+                let [x, y, width, height] = numbers[..] else {
+                    return Err("expected a position and a size".to_string());
+                };
+                let placement = Some(WindowPlacement {
+                    position: IVec2::new(x, y),
+                    size: Vec2::new(width as f32, height as f32),
+                });
+                if key == "playfield" {
+                    self.playfield = placement;
+                } else {
+                    self.backglass = placement;
+                }
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn write(&self) -> Vec<(&'static str, String)> {
+        let mut settings = vec![("backglass-window", on_off(self.backglass_window))];
+        for (key, placement) in [("playfield", self.playfield), ("backglass", self.backglass)] {
+            if let Some(WindowPlacement { position, size }) = placement {
+                settings.push((
+                    key,
+                    format!(
+                        "{} {} {} {}",
+                        position.x, position.y, size.x as i32, size.y as i32
+                    ),
+                ));
+            }
+        }
+        settings
+    }
+}
+
+/// The backglass window with its camera, while it is open.
+#[derive(Resource, Debug)]
+pub struct BackglassWindow {
+    pub window: Entity,
+    pub camera: Entity,
+}
+
+/// Marks the camera of the backglass window, so that it isn't taken for the playfield camera.
+#[derive(Component, Debug)]
+pub struct BackglassCamera;
+
+#[cfg(not(target_family = "wasm"))]
+fn place_primary_window(
+    settings: Res<WindowSettings>,
+    mut window: Single<&mut Window, With<PrimaryWindow>>,
+) {
+    if let Some(placement) = settings.playfield {
+        placement.apply(&mut window);
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn open_or_close_backglass_window(
+    mut commands: Commands,
+    settings: Res<WindowSettings>,
+    backglass_window: Option<Res<BackglassWindow>>,
+) {
+    use bevy::camera::RenderTarget;
+    use bevy::camera::visibility::RenderLayers;
+    use bevy::window::WindowRef;
+
+    match (settings.backglass_window, backglass_window) {
+        (true, None) => {
+            let mut window = Window {
+                title: "VPinball2D Backglass".to_string(),
+                ..default()
+            };
+            match settings.backglass {
+                Some(placement) => placement.apply(&mut window),
+                None => window
+                    .resolution
+                    .set(DEFAULT_BACKGLASS_SIZE.x, DEFAULT_BACKGLASS_SIZE.y),
+            }
+            let window = commands.spawn((Name::new("Backglass Window"), window)).id();
+            let camera = commands
+                .spawn((
+                    Name::new("Backglass Camera"),
+                    BackglassCamera,
+                    Camera2d,
+                    Camera {
+                        order: 1,
+                        target: RenderTarget::Window(WindowRef::Entity(window)),
+                        ..default()
+                    },
+                    RenderLayers::layer(BACKGLASS_LAYER),
+                ))
+                .id();
+            info!("Opened the backglass window");
+            commands.insert_resource(BackglassWindow { window, camera });
+        }
+        (false, Some(backglass_window)) => {
+            commands.entity(backglass_window.camera).despawn();
+            commands.entity(backglass_window.window).despawn();
+            commands.remove_resource::<BackglassWindow>();
+        }
+        _ => {}
+    }
+}
+
+/// Closing the backglass window turns it off, the backglass goes back beside the playfield.
+fn forget_closed_backglass_window(
+    mut commands: Commands,
+    mut closed: MessageReader<WindowClosed>,
+    backglass_window: Option<Res<BackglassWindow>>,
+    mut settings: ResMut<WindowSettings>,
+) {
+    let Some(backglass_window) = backglass_window else {
+        closed.clear();
+        return;
+    };
+    if closed
+        .read()
+        .any(|closed| closed.window == backglass_window.window)
+    {
+        info!("The backglass window was closed");
+        commands.entity(backglass_window.camera).despawn();
+        commands.remove_resource::<BackglassWindow>();
+        settings.backglass_window = false;
+    }
+}
+
+fn track_window_placement(
+    mut moved: MessageReader<WindowMoved>,
+    mut resized: MessageReader<WindowResized>,
+    primary: Query<Entity, With<PrimaryWindow>>,
+    backglass_window: Option<Res<BackglassWindow>>,
+    windows: Query<&Window>,
+    mut settings: ResMut<WindowSettings>,
+) {
+    let changed = moved
+        .read()
+        .map(|moved| moved.window)
+        .chain(resized.read().map(|resized| resized.window))
+        .collect::<Vec<_>>();
+    for entity in changed {
+        let Ok(window) = windows.get(entity) else {
+            continue;
+        };
+        let WindowPosition::At(position) = window.position else {
+            continue;
+        };
+        let placement = Some(WindowPlacement {
+            position,
+            size: window.size(),
+        });
+        if primary.contains(entity) {
+            settings.playfield = placement;
+        } else if backglass_window
+            .as_ref()
+            .is_some_and(|backglass_window| backglass_window.window == entity)
+        {
+            settings.backglass = placement;
+        }
+    }
+}
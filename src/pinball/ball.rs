@@ -1,3 +1,5 @@
+use crate::pinball::events::PreSolveVelocity;
+use crate::pinball::height::{BallHeight, BallImage, item_z};
 use crate::pinball::table::TableAssets;
 use crate::screens::Screen;
 use crate::vpx::VpxAsset;
@@ -14,6 +16,7 @@ pub const BALL_RADIUS_M: f32 = 0.027 / 2.0;
 const BALL_MASS_KG: f32 = 0.08;
 
 #[derive(Component, Debug)]
+#[require(PreSolveVelocity)]
 pub struct Ball {
     #[allow(unused)]
     pub(crate) id: u32,
@@ -49,6 +52,7 @@ pub(crate) fn ball(
         ..default()
     });
     let ball_mesh = meshes.add(Mesh::from(Circle::new(BALL_RADIUS_M)));
+    let z = item_z(BALL_RADIUS_M * 2.0);
     // TODO add ball wall collision sound effects
     // We'll have to be a bit more creative here since ball sounds are actually handled by the script in vpinball.
     // Example / JPSalas => fx_ballrolling0
@@ -62,19 +66,30 @@ pub(crate) fn ball(
     (
         Name::from(format!("Ball {id}")),
         Ball { id },
-        Mesh2d::from(ball_mesh),
-        MeshMaterial2d::from(ball_material),
-        Transform::from_xyz(location.x, location.y, BALL_RADIUS_M),
-        // physics components
-        RigidBody::Dynamic,
-        Mass::from(BALL_MASS_KG),
-        Restitution::new(0.4),
-        Friction::from(0.2),
-        Collider::circle(BALL_RADIUS_M),
-        SleepingDisabled,
-        CollisionEventsEnabled,
-        // continuous collision detection to prevent tunneling at high speeds
-        SweptCcd::default(),
+        BallHeight::default(),
+        Transform::from_xyz(location.x, location.y, z),
+        Visibility::default(),
+        // the shadow is added by the height plugin
+        children![(
+            Name::new("Ball Image"),
+            BallImage,
+            Mesh2d::from(ball_mesh),
+            MeshMaterial2d::from(ball_material),
+        )],
+        // physics components, grouped as a bundle can't have more than 15 elements
+        (
+            RigidBody::Dynamic,
+            Mass::from(BALL_MASS_KG),
+            Restitution::new(0.4),
+            Friction::from(0.2),
+            Collider::circle(BALL_RADIUS_M),
+            SleepingDisabled,
+            CollisionEventsEnabled,
+            // balls in the air pass items below them
+            ActiveCollisionHooks::MODIFY_CONTACTS,
+            // continuous collision detection to prevent tunneling at high speeds
+            SweptCcd::default(),
+        ),
         // sound component
         AudioPlayer::new(sound_roll.clone()),
         PlaybackSettings::LOOP.with_spatial(true),
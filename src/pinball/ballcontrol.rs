@@ -1,5 +1,7 @@
 //! Mouse ball control for development purposes
 
+use crate::input::{ActionState, InputAction};
+use crate::pinball::backglass_window::BackglassCamera;
 use crate::pinball::ball::Ball;
 use crate::{AppSystems, PausableSystems};
 
@@ -8,7 +10,6 @@ use bevy::app::{App, Update};
 use bevy::camera::{Camera, Camera2d};
 use bevy::ecs::bundle::InsertMode;
 use bevy::ecs::system::entity_command::{insert, remove};
-use bevy::input::ButtonInput;
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 
@@ -26,13 +27,14 @@ pub(super) fn plugin(app: &mut App) {
 
 fn mouse_ball_control(
     mut commands: Commands,
-    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    actions: Res<ActionState>,
     window: Single<&Window, With<PrimaryWindow>>,
     gravity: Res<Gravity>,
     mut ball_query: Query<(Entity, &Transform, &Mass, &mut LinearVelocity), With<Ball>>,
-    camera_query: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    camera_query: Query<(&Camera, &GlobalTransform), (With<Camera2d>, Without<BackglassCamera>)>,
 ) {
-    if mouse_buttons.pressed(MouseButton::Left) {
+    if actions.pressed(InputAction::BallControl) {
+        // the camera transform includes the turn of the cabinet view modes
         if let Some((camera, camera_transform)) = camera_query.single().ok()
             && let Some(world_position) = window
                 .cursor_position()
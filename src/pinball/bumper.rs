@@ -1,36 +1,35 @@
-use crate::PausableSystems;
 use crate::pinball::ball::Ball;
+use crate::pinball::events::{GameItem, ItemHit, ItemKind};
+use crate::pinball::height::{ItemHeight, ShadowMaterial, drop_shadow, item_z};
+use crate::pinball::scripts::registry::{ActiveTableScript, TableScriptRegistry};
 use crate::pinball::table::TableAssets;
-use crate::vpx::VpxAsset;
+use crate::vpx::material2d::VpxMaterial2d;
+use crate::vpx::{MaterialKey, VpxAsset};
 use avian2d::math::Scalar;
 use avian2d::prelude::*;
 use bevy::ecs::relationship::RelatedSpawnerCommands;
 use bevy::prelude::*;
 
-use crate::screens::Screen;
-use bevy::sprite_render::AlphaMode2d;
 use rand::Rng;
 use vpin::vpx;
 use vpin::vpx::gameitem;
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_systems(
-        Update,
-        handle_bumper_collisions
-            .in_set(PausableSystems)
-            .run_if(in_state(Screen::Gameplay)),
-    );
+    app.add_observer(on_bumper_hit);
 }
 
 #[derive(Component)]
-struct Bumper {
+pub struct Bumper {
+    #[allow(dead_code)]
+    pub name: String,
     force: Scalar,
 }
 
 pub(super) fn spawn_bumper(
     parent: &mut RelatedSpawnerCommands<ChildOf>,
     meshes: &mut ResMut<Assets<Mesh>>,
-    materials: &mut ResMut<Assets<ColorMaterial>>,
+    shadow_material: &ShadowMaterial,
+    vpx_materials: &mut ResMut<Assets<VpxMaterial2d>>,
     vpx_asset: &VpxAsset,
     vpx_to_bevy_transform: Transform,
     bumper: &gameitem::bumper::Bumper,
@@ -42,79 +41,40 @@ pub(super) fn spawn_bumper(
     let mesh = Mesh::from(Circle {
         radius: vpx::vpu_to_m(bumper.radius),
     });
-    let vpx_cap_material_base_color = if bumper.cap_material.is_empty() {
-        Srgba::rgb_u8(200, 200, 200)
-    } else {
-        match vpx_asset
-            .raw
-            .gamedata
-            .materials
-            .iter()
-            .flatten()
-            .find(|m| m.name == bumper.cap_material)
-        {
-            None => {
-                warn!(
-                    "Bumper cap material '{}' not found, using default color",
-                    bumper.cap_material
-                );
-                Srgba::rgb_u8(200, 200, 200)
-            }
-            Some(m) => {
-                let base_color = m.base_color;
-                Srgba::rgb_u8(base_color.r, base_color.g, base_color.b)
-            }
-        }
-    };
-    let vpx_base_material_color = if bumper.base_material.is_empty() {
-        Srgba::rgb_u8(150, 150, 150)
-    } else {
-        match vpx_asset
-            .raw
-            .gamedata
-            .materials
-            .iter()
-            .flatten()
-            .find(|m| m.name == bumper.base_material)
-        {
-            None => {
-                warn!(
-                    "Bumper base material '{}' not found, using default color",
-                    bumper.base_material
-                );
-                Srgba::rgb_u8(150, 150, 150)
-            }
-            Some(m) => {
-                let base_color = m.base_color;
-                Srgba::rgb_u8(base_color.r, base_color.g, base_color.b)
-            }
-        }
-    };
-
-    let base_material = materials.add(ColorMaterial {
-        color: vpx_base_material_color.into(),
-        alpha_mode: AlphaMode2d::Opaque,
-        texture: None,
-        ..default()
-    });
-    let cap_material = materials.add(ColorMaterial {
-        color: vpx_cap_material_base_color.into(),
-        // TODO we want to create a proper transparent plastic material type
-        alpha_mode: AlphaMode2d::Blend,
-        texture: None,
-        ..default()
-    });
+    let cap_material = bumper_material(
+        vpx_materials,
+        vpx_asset,
+        &bumper.cap_material,
+        "cap",
+        Srgba::rgb_u8(200, 200, 200),
+    );
+    let base_material = bumper_material(
+        vpx_materials,
+        vpx_asset,
+        &bumper.base_material,
+        "base",
+        Srgba::rgb_u8(150, 150, 150),
+    );
 
+    // the cap is the top of the bumper, it casts the shadow
+    let top = vpx::vpu_to_m(bumper.height_scale);
+    let z = item_z(top);
+    let cap_mesh = meshes.add(Mesh::from(Circle { radius: cap_radius }));
+    let shadow = drop_shadow(cap_mesh.clone(), shadow_material, top, z);
     // use bumper.center to modify the transform
     let transform = Transform::from_xyz(
         vpx::vpu_to_m(bumper.center.x) + vpx_to_bevy_transform.translation.x,
         -vpx::vpu_to_m(bumper.center.y) + vpx_to_bevy_transform.translation.y,
-        0.1,
+        z,
     );
     // not sure what vpinball uses as force but we want newtons
     let force = bumper.force * 0.008;
     parent.spawn((
-        Bumper { force },
+        Bumper {
+            name: bumper.name.clone(),
+            force,
+        },
+        GameItem::new(&bumper.name, ItemKind::Bumper),
         Name::from(format!("Bumper{}", bumper.name)),
         Mesh2d(meshes.add(mesh)),
         MeshMaterial2d(base_material),
@@ -122,75 +82,109 @@ pub(super) fn spawn_bumper(
         CollisionEventsEnabled,
         RigidBody::Static,
         Collider::circle(base_radius),
-        children![(
-            Name::from(format!("Bumper Cap {}", bumper.name)),
-            Mesh2d(meshes.add(Mesh::from(Circle { radius: cap_radius }))),
-            MeshMaterial2d(cap_material),
-            Transform::from_xyz(0.0, 0.0, 0.01),
-        ),],
+        // a ball in the air flies over the bumper
+        ItemHeight { bottom: 0.0, top },
+        children![
+            (
+                Name::from(format!("Bumper Cap {}", bumper.name)),
+                Mesh2d(cap_mesh),
+                MeshMaterial2d(cap_material),
+                Transform::from_xyz(0.0, 0.0, 0.0001),
+            ),
+            shadow,
+        ],
     ));
 }
 
-fn handle_bumper_collisions(
-    bumper_query: Query<(Entity, &Bumper, &Transform)>,
+/// The shared material for a part of the bumper, or a plain color if it has none.
+fn bumper_material(
+    materials: &mut Assets<VpxMaterial2d>,
+    vpx_asset: &VpxAsset,
+    material: &str,
+    part: &str,
+    default_color: Srgba,
+) -> Handle<VpxMaterial2d> {
+    if let Some(handle) = vpx_asset.named_materials.get(&MaterialKey::round(material)) {
+        return handle.clone();
+    }
+    if !material.is_empty() {
+        warn!("Bumper {part} material '{material}' not found, using default color");
+    }
+    materials.add(VpxMaterial2d {
+        round: true,
+        ..VpxMaterial2d::from(Color::from(default_color))
+    })
+}
+
+fn on_bumper_hit(
+    hit: On<ItemHit>,
+    bumper_query: Query<(&Bumper, &Transform)>,
     mut ball_query: Query<(&Transform, Forces), With<Ball>>,
-    mut contact_events: MessageReader<CollisionStart>,
     mut commands: Commands,
     table_assets: Res<TableAssets>,
     assets_vpx: Res<Assets<VpxAsset>>,
+    registry: Res<TableScriptRegistry>,
+    active: Res<ActiveTableScript>,
 ) {
-    for contact_event in contact_events.read() {
-        for (bumper_entity, bumper, bumper_transform) in bumper_query.iter() {
-            if let (Some(h1), Some(h2)) = (contact_event.body1, contact_event.body2)
-                && (h1 == bumper_entity || h2 == bumper_entity)
-            {
-                let vpx_asset = assets_vpx.get(&table_assets.vpx).unwrap();
-
-                // jpsalas table
-                let bumper_sound = vpx_asset.named_sounds.get("fx_Bumper").or_else(|| {
-                    // vpx example tables use fx_bumper1 to fx_bumper4
+    let Ok((bumper, bumper_transform)) = bumper_query.get(hit.entity) else {
+        return;
+    };
+    let vpx_asset = assets_vpx.get(&table_assets.vpx).unwrap();
 
-                    // example table
-                    // random sound number between 1 and 4
-                    // TODO we might want to store these handles in a resource to avoid looking them up every time
-                    let sound_index = rand::rng().random_range(1..=4);
-                    let vpx_sound = vpx_asset
-                        .named_sounds
-                        .get(format!("fx_bumper{sound_index}").as_str());
+    // the table script plays its own bumper sound, see scripts::vbscript_sounds, unless the
+    // active script handles item events instead
+    let script_handles_events = active
+        .0
+        .is_some_and(|name| registry.handles_item_events(name));
+    let script_sound = !script_handles_events
+        && vpx_asset
+            .event_handlers
+            .sounds(&hit.item_name, "Hit")
+            .any(|sound| vpx_asset.sound(sound).is_some());
 
-                    // tna table
-                    // random sound number between 1 and 7
-                    // TODO we might want to store these handles in a resource to avoid looking them up every time
-                    let sound_index = rand::rng().random_range(1..=7);
-                    let tna_sound = vpx_asset
-                        .named_sounds
-                        .get(format!("SY_TNA_REV03_Pop_Bumper_{sound_index}").as_str());
+    if !script_sound {
+        // jpsalas table
+        let bumper_sound = vpx_asset.named_sounds.get("fx_Bumper").or_else(|| {
+            // vpx example tables use fx_bumper1 to fx_bumper4
 
-                    vpx_sound.or(tna_sound)
-                });
+            // example table
+            // random sound number between 1 and 4
+            // TODO we might want to store these handles in a resource to avoid looking them up every time
+            let sound_index = rand::rng().random_range(1..=4);
+            let vpx_sound = vpx_asset
+                .named_sounds
+                .get(format!("fx_bumper{sound_index}").as_str());
 
-                if let Some(sound_ball_collision) = bumper_sound {
-                    commands.spawn((
-                        AudioPlayer::new(sound_ball_collision.clone()),
-                        PlaybackSettings::ONCE.with_spatial(true),
-                        //.with_volume(Volume::Linear(volume)),
-                        Transform::from_translation(bumper_transform.translation),
-                    ));
-                } else {
-                    warn!("Bumper sound fx_bumper not found");
-                }
+            // tna table
+            // random sound number between 1 and 7
+            // TODO we might want to store these handles in a resource to avoid looking them up every time
+            let sound_index = rand::rng().random_range(1..=7);
+            let tna_sound = vpx_asset
+                .named_sounds
+                .get(format!("SY_TNA_REV03_Pop_Bumper_{sound_index}").as_str());
 
-                // Apply outward pulse to the ball
-                let ball_entity = if h1 == bumper_entity { h2 } else { h1 };
-                if let Ok((ball_transform, mut forces)) = ball_query.get_mut(ball_entity) {
-                    // Calculate direction from bumper center to ball
-                    let bumper_pos = bumper_transform.translation.truncate();
-                    let ball_pos = ball_transform.translation.truncate();
-                    let direction = (ball_pos - bumper_pos).normalize();
+            vpx_sound.or(tna_sound)
+        });
 
-                    forces.apply_linear_impulse(direction * bumper.force);
-                }
-            }
+        if let Some(sound_ball_collision) = bumper_sound {
+            commands.spawn((
+                AudioPlayer::new(sound_ball_collision.clone()),
+                PlaybackSettings::ONCE.with_spatial(true),
+                //.with_volume(Volume::Linear(volume)),
+                Transform::from_translation(bumper_transform.translation),
+            ));
+        } else {
+            warn!("Bumper sound fx_bumper not found");
         }
     }
+
+    // Apply outward pulse to the ball
+    if let Ok((ball_transform, mut forces)) = ball_query.get_mut(hit.ball) {
+        // Calculate direction from bumper center to ball
+        let bumper_pos = bumper_transform.translation.truncate();
+        let ball_pos = ball_transform.translation.truncate();
+        let direction = (ball_pos - bumper_pos).normalize();
+
+        forces.apply_linear_impulse(direction * bumper.force);
+    }
 }
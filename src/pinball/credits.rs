@@ -0,0 +1,110 @@
+//! Coins and credits. Starting a game or adding a player costs a credit unless free play is on.
+//! Replays, specials and matches are worth a credit each.
+
+use crate::audio::sound_effect;
+use crate::input::{InputAction, action_just_pressed};
+use crate::pinball::awards::{MatchAwarded, ReplayAwarded, SpecialAwarded};
+use crate::pinball::table::TableAssets;
+use crate::screens::Screen;
+use crate::vpx::VpxAsset;
+use crate::{AppSystems, PausableSystems};
+use bevy::prelude::*;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<CreditSettings>();
+    app.init_resource::<Credits>();
+    app.add_systems(
+        Update,
+        insert_coin
+            .run_if(action_just_pressed(InputAction::Coin))
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    app.add_observer(|_: On<ReplayAwarded>, credits: AwardCredit| credits.award());
+    app.add_observer(|_: On<SpecialAwarded>, credits: AwardCredit| credits.award());
+    app.add_observer(|_: On<MatchAwarded>, credits: AwardCredit| credits.award());
+}
+
+/// Sounds tables commonly use for a coin drop.
+const COIN_SOUNDS: [&str; 3] = ["coin", "fx_coin", "CoinIn"];
+
+#[derive(Resource, Debug, Clone)]
+pub struct CreditSettings {
+    pub coins_per_credit: u32,
+    pub free_play: bool,
+    /// Like on a real machine, credits stop counting up at some point.
+    pub max_credits: u32,
+}
+
+impl Default for CreditSettings {
+    fn default() -> Self {
+        Self {
+            coins_per_credit: 1,
+            free_play: false,
+            max_credits: 99,
+        }
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct Credits {
+    pub credits: u32,
+    /// Coins inserted that don't add up to a credit yet.
+    coins: u32,
+}
+
+impl Credits {
+    pub fn add_credits(&mut self, credits: u32, settings: &CreditSettings) {
+        self.credits = (self.credits + credits).min(settings.max_credits);
+    }
+
+    pub fn insert_coin(&mut self, settings: &CreditSettings) {
+        self.coins += 1;
+        if self.coins >= settings.coins_per_credit.max(1) {
+            self.coins = 0;
+            self.add_credits(1, settings);
+        }
+    }
+
+    /// Takes a credit to start a game or add a player, returns whether that is allowed.
+    pub fn use_credit(&mut self, settings: &CreditSettings) -> bool {
+        if settings.free_play {
+            return true;
+        }
+        if self.credits == 0 {
+            return false;
+        }
+        self.credits -= 1;
+        true
+    }
+}
+
+#[derive(bevy::ecs::system::SystemParam)]
+struct AwardCredit<'w> {
+    credits: ResMut<'w, Credits>,
+    settings: Res<'w, CreditSettings>,
+}
+
+impl AwardCredit<'_> {
+    fn award(mut self) {
+        self.credits.add_credits(1, &self.settings);
+    }
+}
+
+fn insert_coin(
+    mut commands: Commands,
+    mut credits: ResMut<Credits>,
+    settings: Res<CreditSettings>,
+    table_assets: Res<TableAssets>,
+    assets_vpx: Res<Assets<VpxAsset>>,
+) {
+    credits.insert_coin(&settings);
+    info!("Coin inserted, {} credits", credits.credits);
+    let sound = assets_vpx
+        .get(&table_assets.vpx)
+        .and_then(|vpx_asset| COIN_SOUNDS.iter().find_map(|name| vpx_asset.sound(name)));
+    if let Some(sound) = sound {
+        commands.spawn(sound_effect(sound.clone()));
+    }
+}
@@ -0,0 +1,123 @@
+//! The dots of the display.
+
+use crate::pinball::dmd::font::{GLYPH_HEIGHT, GLYPH_SPACING, GLYPH_WIDTH, glyph};
+use bevy::prelude::*;
+
+pub const DMD_WIDTH: usize = 128;
+pub const DMD_HEIGHT: usize = 32;
+/// The blank rows between two lines of text.
+pub const LINE_SPACING: usize = 3;
+
+/// The number of brightness levels of a dot. Older displays have 4, later ones 16.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShadeDepth {
+    Four,
+    #[default]
+    Sixteen,
+}
+
+impl ShadeDepth {
+    /// The depth with this number of brightness levels, as scripts set it.
+    pub fn from_levels(levels: u32) -> Option<Self> {
+        match levels {
+            4 => Some(ShadeDepth::Four),
+            16 => Some(ShadeDepth::Sixteen),
+            _ => None,
+        }
+    }
+
+    /// The brightest shade, 0 is off.
+    pub fn max(self) -> u8 {
+        match self {
+            ShadeDepth::Four => 3,
+            ShadeDepth::Sixteen => 15,
+        }
+    }
+}
+
+/// A frame of the display, one shade per dot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DmdFrame {
+    depth: ShadeDepth,
+    shades: Vec<u8>,
+}
+
+impl Default for DmdFrame {
+    fn default() -> Self {
+        Self::new(ShadeDepth::default())
+    }
+}
+
+impl DmdFrame {
+    pub fn new(depth: ShadeDepth) -> Self {
+        Self {
+            depth,
+            shades: vec![0; DMD_WIDTH * DMD_HEIGHT],
+        }
+    }
+
+    /// The shade of a dot, 0 outside the display.
+    pub fn get(&self, x: i32, y: i32) -> u8 {
+        Self::index(x, y).map_or(0, |i| self.shades[i])
+    }
+
+    /// Sets the shade of a dot, dots outside the display are ignored.
+    pub fn set(&mut self, x: i32, y: i32, shade: u8) {
+        if let Some(i) = Self::index(x, y) {
+            self.shades[i] = shade.min(self.depth.max());
+        }
+    }
+
+    /// The brightness of a dot, from 0 for off to 1 for the brightest shade.
+    pub fn brightness(&self, x: i32, y: i32) -> f32 {
+        self.get(x, y) as f32 / self.depth.max() as f32
+    }
+
+    /// Draws text with its top left corner at the given dot, clipped to the display.
+    pub fn draw_text(&mut self, text: &str, x: i32, y: i32, shade: u8) {
+        for (n, c) in text.chars().enumerate() {
+            let left = x + (n * (GLYPH_WIDTH + GLYPH_SPACING)) as i32;
+            for (row, bits) in glyph(c).into_iter().enumerate() {
+                for column in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0 {
+                        self.set(left + column as i32, y + row as i32, shade);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Scales an image to the display, using its luminance as the shade.
+    /// Images that can't be read, like compressed ones, come out dark.
+    pub fn from_image(image: &Image, depth: ShadeDepth) -> Self {
+        let mut frame = Self::new(depth);
+        let (width, height) = (image.width(), image.height());
+        if width == 0 || height == 0 {
+            return frame;
+        }
+        for y in 0..DMD_HEIGHT {
+            for x in 0..DMD_WIDTH {
+                let source_x = (x as u32 * width) / DMD_WIDTH as u32;
+                let source_y = (y as u32 * height) / DMD_HEIGHT as u32;
+                let Ok(color) = image.get_color_at(source_x, source_y) else {
+                    continue;
+                };
+                let color = color.to_srgba();
+                let luminance = 0.299 * color.red + 0.587 * color.green + 0.114 * color.blue;
+                let shade = (luminance * color.alpha * depth.max() as f32).round() as u8;
+                frame.set(x as i32, y as i32, shade);
+            }
+        }
+        frame
+    }
+
+    fn index(x: i32, y: i32) -> Option<usize> {
+        let (x, y) = (usize::try_from(x).ok()?, usize::try_from(y).ok()?);
+        (x < DMD_WIDTH && y < DMD_HEIGHT).then_some(y * DMD_WIDTH + x)
+    }
+}
+
+/// The height of a block of text lines in dots.
+pub fn text_height(lines: usize) -> usize {
+    (lines * (GLYPH_HEIGHT + LINE_SPACING)).saturating_sub(LINE_SPACING)
+}
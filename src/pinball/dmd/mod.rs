@@ -0,0 +1,315 @@
+//! An emulated 128x32 dot-matrix display (DMD), like on modern tables.
+//!
+//! Scenes are queued on the [`Dmd`] and shown one after the other: text that stands still, blinks
+//! or scrolls by, and frame animations made from table images. With nothing queued the display
+//! shows the score of the current player, or the table name in attract mode. Each frame is drawn
+//! into a dotted texture that the HUD shows, see [`dmd_panel`]. Scripts queue scenes through
+//! [`ScriptCommand::DmdText`](crate::pinball::scripts::api::ScriptCommand::DmdText) and
+//! [`ScriptCommand::DmdAnimation`](crate::pinball::scripts::api::ScriptCommand::DmdAnimation),
+//! and can switch to an older 4-shade display with
+//! [`ScriptCommand::DmdShadeDepth`](crate::pinball::scripts::api::ScriptCommand::DmdShadeDepth).
+
+mod font;
+mod frame;
+
+use crate::pinball::game::GameState;
+use crate::pinball::table::TableAssets;
+use crate::screens::Screen;
+use crate::vpx::VpxAsset;
+use crate::{AppSystems, PausableSystems};
+use bevy::asset::RenderAssetUsages;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use font::text_width;
+pub use frame::{DMD_HEIGHT, DMD_WIDTH, DmdFrame, ShadeDepth};
+use frame::{LINE_SPACING, text_height};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Dmd>();
+    app.add_systems(Startup, create_dmd_texture);
+    app.add_systems(
+        Update,
+        (play_dmd_scenes, render_dmd)
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    // the next table starts with the default display
+    app.add_systems(OnExit(Screen::Gameplay), |mut dmd: ResMut<Dmd>| {
+        *dmd = Dmd::default();
+    });
+}
+
+/// Each dot is drawn as a round spot of this many texels wide.
+const DOT_SIZE: usize = 4;
+/// The classic orange of a plasma display.
+const DOT_COLOR: Color = Color::srgb(1.0, 0.35, 0.05);
+/// Dots that are off still glow a little.
+const DOT_OFF_BRIGHTNESS: f32 = 0.08;
+const BLINK_INTERVAL: Duration = Duration::from_millis(250);
+/// How fast scrolling text moves, in dots per second.
+const SCROLL_SPEED: f32 = 64.0;
+
+/// How long scripts show text when they don't say.
+pub const DEFAULT_TEXT_DURATION_MS: u64 = 2000;
+/// How long scripts show an animation frame when they don't say.
+pub const DEFAULT_FRAME_DURATION_MS: u64 = 100;
+
+/// How text is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextEffect {
+    #[default]
+    Static,
+    Blink,
+    /// Scrolls in from the right and out to the left.
+    Scroll,
+}
+
+impl TextEffect {
+    /// Parses the effect names used by scripts, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "static" | "" => Some(TextEffect::Static),
+            "blink" => Some(TextEffect::Blink),
+            "scroll" => Some(TextEffect::Scroll),
+            _ => None,
+        }
+    }
+}
+
+/// Something to show on the display for a while.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DmdScene {
+    /// Lines of text, centered on the display.
+    Text {
+        lines: Vec<String>,
+        effect: TextEffect,
+        /// Scrolling text is shown until it has left the display instead.
+        duration: Duration,
+    },
+    /// Table images shown one after the other.
+    Animation {
+        frames: Vec<Handle<Image>>,
+        frame_duration: Duration,
+    },
+}
+
+impl DmdScene {
+    fn duration(&self) -> Duration {
+        match self {
+            DmdScene::Text {
+                lines,
+                effect: TextEffect::Scroll,
+                ..
+            } => {
+                let width = lines.iter().map(|line| text_width(line)).max().unwrap_or(0);
+                Duration::from_secs_f32((DMD_WIDTH + width) as f32 / SCROLL_SPEED)
+            }
+            DmdScene::Text { duration, .. } => *duration,
+            DmdScene::Animation {
+                frames,
+                frame_duration,
+            } => frame_duration
+                .checked_mul(frames.len() as u32)
+                .unwrap_or(Duration::MAX),
+        }
+    }
+}
+
+/// The display with its queue of scenes.
+#[derive(Resource, Debug, Default)]
+pub struct Dmd {
+    pub depth: ShadeDepth,
+    queue: VecDeque<DmdScene>,
+    /// The scene being shown, with how long it has been shown.
+    current: Option<(DmdScene, Duration)>,
+    frame: DmdFrame,
+}
+
+impl Dmd {
+    /// Queues a scene, it is shown after the ones already queued.
+    pub fn push(&mut self, scene: DmdScene) {
+        self.queue.push_back(scene);
+    }
+
+    /// Stops the current scene and drops the queued ones.
+    pub fn clear(&mut self) {
+        self.queue.clear();
+        self.current = None;
+    }
+}
+
+/// The texture the display is drawn into.
+#[derive(Resource, Debug)]
+pub struct DmdTexture {
+    pub image: Handle<Image>,
+    /// The frame that is in the texture.
+    shown: Option<DmdFrame>,
+}
+
+/// The display as a UI node, keeping the aspect ratio of the dots.
+pub fn dmd_panel(texture: &DmdTexture) -> impl Bundle {
+    (
+        Name::new("DMD"),
+        DmdPanel,
+        ImageNode::new(texture.image.clone()),
+        Node {
+            width: percent(90),
+            max_width: px((DMD_WIDTH * DOT_SIZE) as f32),
+            aspect_ratio: Some(DMD_WIDTH as f32 / DMD_HEIGHT as f32),
+            ..default()
+        },
+        BackgroundColor(Color::BLACK),
+    )
+}
+
+#[derive(Component, Debug)]
+pub struct DmdPanel;
+
+fn create_dmd_texture(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let image = Image::new_fill(
+        Extent3d {
+            width: (DMD_WIDTH * DOT_SIZE) as u32,
+            height: (DMD_HEIGHT * DOT_SIZE) as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    commands.insert_resource(DmdTexture {
+        image: images.add(image),
+        shown: None,
+    });
+}
+
+/// Moves on to the next scene when the current one is done and draws the frame.
+fn play_dmd_scenes(
+    time: Res<Time>,
+    mut dmd: ResMut<Dmd>,
+    game: Res<GameState>,
+    images: Res<Assets<Image>>,
+    table_assets: Res<TableAssets>,
+    assets_vpx: Res<Assets<VpxAsset>>,
+) {
+    let dmd = &mut *dmd;
+    if let Some((scene, elapsed)) = &mut dmd.current {
+        *elapsed += time.delta();
+        if *elapsed >= scene.duration() {
+            dmd.current = None;
+        }
+    }
+    if dmd.current.is_none() {
+        dmd.current = dmd.queue.pop_front().map(|scene| (scene, Duration::ZERO));
+    }
+
+    let frame = match &dmd.current {
+        Some((DmdScene::Text { lines, effect, .. }, elapsed)) => {
+            text_frame(lines, *effect, *elapsed, dmd.depth)
+        }
+        Some((
+            DmdScene::Animation {
+                frames,
+                frame_duration,
+            },
+            elapsed,
+        )) => {
+            let index =
+                (elapsed.as_secs_f32() / frame_duration.as_secs_f32().max(f32::EPSILON)) as usize;
+            frames
+                .get(index.min(frames.len().saturating_sub(1)))
+                .and_then(|handle| images.get(handle))
+                .map(|image| DmdFrame::from_image(image, dmd.depth))
+                .unwrap_or_else(|| DmdFrame::new(dmd.depth))
+        }
+        None => {
+            let lines = if game.in_progress() {
+                let score = game.current().map_or(0, |player| player.score);
+                vec![
+                    score.to_string(),
+                    format!("PLAYER {}  BALL {}", game.current_player + 1, game.ball),
+                ]
+            } else {
+                let name = assets_vpx
+                    .get(&table_assets.vpx)
+                    .and_then(|vpx_asset| vpx_asset.identity.name.clone())
+                    .unwrap_or_default();
+                vec![name, "PRESS START".to_string()]
+            };
+            text_frame(&lines, TextEffect::Static, Duration::ZERO, dmd.depth)
+        }
+    };
+    if dmd.frame != frame {
+        dmd.frame = frame;
+    }
+}
+
+/// Draws lines of text centered on the display.
+fn text_frame(
+    lines: &[String],
+    effect: TextEffect,
+    elapsed: Duration,
+    depth: ShadeDepth,
+) -> DmdFrame {
+    let mut frame = DmdFrame::new(depth);
+    let blink_off = (elapsed.as_millis() / BLINK_INTERVAL.as_millis()) % 2 == 1;
+    if effect == TextEffect::Blink && blink_off {
+        return frame;
+    }
+    let mut y = (DMD_HEIGHT as i32 - text_height(lines.len()) as i32) / 2;
+    for line in lines {
+        let width = text_width(line) as i32;
+        let x = match effect {
+            TextEffect::Scroll => DMD_WIDTH as i32 - (elapsed.as_secs_f32() * SCROLL_SPEED) as i32,
+            TextEffect::Static | TextEffect::Blink => (DMD_WIDTH as i32 - width) / 2,
+        };
+        frame.draw_text(line, x, y, depth.max());
+        y += (font::GLYPH_HEIGHT + LINE_SPACING) as i32;
+    }
+    frame
+}
+
+/// Draws the frame into the texture, each dot as a round spot.
+fn render_dmd(dmd: Res<Dmd>, mut texture: ResMut<DmdTexture>, mut images: ResMut<Assets<Image>>) {
+    if texture.shown.as_ref() == Some(&dmd.frame) {
+        return;
+    }
+    let Some(data) = images
+        .get_mut(&texture.image)
+        .and_then(|image| image.data.as_mut())
+    else {
+        return;
+    };
+    let [red, green, blue, _] = DOT_COLOR.to_srgba().to_u8_array();
+    let center = (DOT_SIZE as f32 - 1.0) / 2.0;
+    let radius = DOT_SIZE as f32 * 0.45;
+    let texture_width = DMD_WIDTH * DOT_SIZE;
+    for y in 0..DMD_HEIGHT * DOT_SIZE {
+        for x in 0..texture_width {
+            let (dx, dy) = (
+                (x % DOT_SIZE) as f32 - center,
+                (y % DOT_SIZE) as f32 - center,
+            );
+            let brightness = if dx * dx + dy * dy <= radius * radius {
+                let dot = dmd
+                    .frame
+                    .brightness((x / DOT_SIZE) as i32, (y / DOT_SIZE) as i32);
+                DOT_OFF_BRIGHTNESS + (1.0 - DOT_OFF_BRIGHTNESS) * dot
+            } else {
+                0.0
+            };
+            let i = (y * texture_width + x) * 4;
+            data[i..i + 4].copy_from_slice(&[
+                (red as f32 * brightness) as u8,
+                (green as f32 * brightness) as u8,
+                (blue as f32 * brightness) as u8,
+                255,
+            ]);
+        }
+    }
+    texture.shown = Some(dmd.frame.clone());
+}
@@ -0,0 +1,293 @@
+//! Game-item events, modelled after the VPX item events like `Drain_Hit` or `Gate_Spin`.
+//!
+//! Raw collisions are turned into typed events after each physics step and triggered on the item
+//! entity, so sounds, rules and scripts can observe them instead of parsing collisions themselves:
+//!
+//! ```ignore
+//! app.add_observer(|hit: On<ItemHit>| info!("{} was hit", hit.item_name));
+//! ```
+
+use crate::pinball::ball::Ball;
+use crate::pinball::table::TableAssets;
+use crate::screens::Screen;
+use crate::vpx::VpxAsset;
+use crate::{AppSystems, PausableSystems};
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use std::time::Duration;
+use vpin::vpx::gameitem::GameItemEnum;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            add_item_timers.in_set(AppSystems::TickTimers),
+            tick_item_timers.in_set(AppSystems::TickTimers),
+        )
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    app.add_systems(
+        FixedUpdate,
+        record_pre_solve_velocity
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    // in the same fixed step as the collision, before the next step overwrites the velocity
+    app.add_systems(
+        FixedPostUpdate,
+        dispatch_item_collisions
+            .after(PhysicsSystems::StepSimulation)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// A table item that can send events, named like in the VPX table.
+#[derive(Component, Debug, Clone)]
+pub struct GameItem {
+    pub name: String,
+    pub kind: ItemKind,
+}
+
+impl GameItem {
+    pub fn new(name: impl Into<String>, kind: ItemKind) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemKind {
+    Wall,
+    Rubber,
+    Kicker,
+    Trigger,
+    Bumper,
+    Light,
+    /// A VPX timer, which only exists to send [`ItemTimer`] events.
+    Timer,
+    /// An electro-mechanical score reel on the backglass.
+    Reel,
+    Gate,
+    Spinner,
+}
+
+/// A ball started touching an item.
+#[derive(EntityEvent, Debug, Clone)]
+pub struct ItemHit {
+    /// The item that was hit.
+    pub entity: Entity,
+    pub item_name: String,
+    pub kind: ItemKind,
+    pub ball: Entity,
+    pub ball_id: u32,
+    /// The speed of the ball in meters per second just before the contact started.
+    pub impact_speed: f32,
+}
+
+/// The velocity of a ball before the last physics step. By the time a collision is reported the
+/// solver has already bounced the ball off the item, so its [`LinearVelocity`] is the one after
+/// the impact.
+#[derive(Component, Debug, Default)]
+pub struct PreSolveVelocity(pub Vec2);
+
+/// A ball stopped touching an item.
+#[derive(EntityEvent, Debug, Clone)]
+pub struct ItemUnhit {
+    /// The item that was left.
+    pub entity: Entity,
+    pub item_name: String,
+    pub kind: ItemKind,
+    pub ball: Entity,
+    pub ball_id: u32,
+}
+
+/// A ball passed through a gate or spinner, which turns it.
+#[derive(EntityEvent, Debug, Clone)]
+pub struct ItemSpin {
+    pub entity: Entity,
+    pub item_name: String,
+    pub kind: ItemKind,
+}
+
+/// The timer of an item elapsed, see the VPX `TimerEnabled` and `TimerInterval` properties.
+#[derive(EntityEvent, Debug, Clone)]
+pub struct ItemTimer {
+    pub entity: Entity,
+    pub item_name: String,
+    pub kind: ItemKind,
+}
+
+/// A repeating timer on an item that triggers [`ItemTimer`] while enabled.
+#[derive(Component, Debug)]
+pub struct GameItemTimer {
+    pub enabled: bool,
+    pub timer: Timer,
+}
+
+impl GameItemTimer {
+    pub fn new(enabled: bool, interval_ms: u64) -> Self {
+        // VPX treats intervals below 1 ms as 1 ms
+        Self {
+            enabled,
+            timer: Timer::new(
+                Duration::from_millis(interval_ms.max(1)),
+                TimerMode::Repeating,
+            ),
+        }
+    }
+}
+
+pub(super) fn spawn_timer(
+    parent: &mut RelatedSpawnerCommands<ChildOf>,
+    timer: &vpin::vpx::gameitem::timer::Timer,
+) {
+    parent.spawn((
+        Name::from(format!("Timer {}", timer.name)),
+        GameItem::new(&timer.name, ItemKind::Timer),
+    ));
+}
+
+fn dispatch_item_collisions(
+    mut collision_start_reader: MessageReader<CollisionStart>,
+    mut collision_end_reader: MessageReader<CollisionEnd>,
+    ball_query: Query<(&Ball, &PreSolveVelocity)>,
+    item_query: Query<&GameItem>,
+    mut commands: Commands,
+) {
+    // the ball is always one of the two colliders, the item the other one
+    let ball_item = |collider1: Entity, collider2: Entity| {
+        let (ball_entity, item_entity) = if ball_query.contains(collider1) {
+            (collider1, collider2)
+        } else {
+            (collider2, collider1)
+        };
+        let (ball, velocity) = ball_query.get(ball_entity).ok()?;
+        let item = item_query.get(item_entity).ok()?;
+        Some((ball_entity, ball, velocity, item_entity, item))
+    };
+
+    for collision in collision_start_reader.read() {
+        if let Some((ball_entity, ball, velocity, item_entity, item)) =
+            ball_item(collision.collider1, collision.collider2)
+        {
+            commands.trigger(ItemHit {
+                entity: item_entity,
+                item_name: item.name.clone(),
+                kind: item.kind,
+                ball: ball_entity,
+                ball_id: ball.id,
+                impact_speed: velocity.0.length(),
+            });
+            if matches!(item.kind, ItemKind::Gate | ItemKind::Spinner) {
+                commands.trigger(ItemSpin {
+                    entity: item_entity,
+                    item_name: item.name.clone(),
+                    kind: item.kind,
+                });
+            }
+        }
+    }
+    for collision in collision_end_reader.read() {
+        if let Some((ball_entity, ball, _, item_entity, item)) =
+            ball_item(collision.collider1, collision.collider2)
+        {
+            commands.trigger(ItemUnhit {
+                entity: item_entity,
+                item_name: item.name.clone(),
+                kind: item.kind,
+                ball: ball_entity,
+                ball_id: ball.id,
+            });
+        }
+    }
+}
+
+/// Physics runs after the fixed update, so this is the velocity the step starts with. It is read
+/// back by [`dispatch_item_collisions`] right after the same step.
+fn record_pre_solve_velocity(
+    mut ball_query: Query<(&LinearVelocity, &mut PreSolveVelocity), With<Ball>>,
+) {
+    for (velocity, mut pre_solve) in &mut ball_query {
+        pre_solve.0 = velocity.0;
+    }
+}
+
+/// Adds timers to items that have a timer configured in the VPX table.
+fn add_item_timers(
+    mut commands: Commands,
+    item_query: Query<(Entity, &GameItem), Added<GameItem>>,
+    table_assets: Res<TableAssets>,
+    assets_vpx: Res<Assets<VpxAsset>>,
+) {
+    if item_query.is_empty() {
+        return;
+    }
+    let Some(vpx_asset) = assets_vpx.get(&table_assets.vpx) else {
+        return;
+    };
+    for (entity, item) in item_query.iter() {
+        let timer = vpx_asset
+            .raw
+            .gameitems
+            .iter()
+            .filter_map(vpx_timer)
+            .find(|(name, _, _)| name.eq_ignore_ascii_case(&item.name));
+        if let Some((_, enabled, interval_ms)) = timer {
+            commands
+                .entity(entity)
+                .insert(GameItemTimer::new(enabled, interval_ms));
+        }
+    }
+}
+
+/// The name of an item with whether its timer is enabled and the timer interval.
+fn vpx_timer(item: &GameItemEnum) -> Option<(&str, bool, u64)> {
+    let (name, enabled, interval) = match item {
+        GameItemEnum::Wall(wall) => (&wall.name, wall.is_timer_enabled, wall.timer_interval),
+        GameItemEnum::Kicker(kicker) => {
+            (&kicker.name, kicker.is_timer_enabled, kicker.timer_interval)
+        }
+        GameItemEnum::Trigger(trigger) => (
+            &trigger.name,
+            trigger.is_timer_enabled,
+            trigger.timer_interval,
+        ),
+        GameItemEnum::Bumper(bumper) => {
+            (&bumper.name, bumper.is_timer_enabled, bumper.timer_interval)
+        }
+        GameItemEnum::Light(light) => (&light.name, light.is_timer_enabled, light.timer_interval),
+        GameItemEnum::Timer(timer) => (&timer.name, timer.is_timer_enabled, timer.timer_interval),
+        GameItemEnum::Gate(gate) => (&gate.name, gate.is_timer_enabled, gate.timer_interval),
+        GameItemEnum::Spinner(spinner) => (
+            &spinner.name,
+            spinner.is_timer_enabled,
+            spinner.timer_interval,
+        ),
+        _ => return None,
+    };
+    Some((name, enabled, interval.max(0) as u64))
+}
+
+fn tick_item_timers(
+    time: Res<Time>,
+    mut timer_query: Query<(Entity, &GameItem, &mut GameItemTimer)>,
+    mut commands: Commands,
+) {
+    for (entity, item, mut timer) in timer_query.iter_mut() {
+        if !timer.enabled {
+            continue;
+        }
+        timer.timer.tick(time.delta());
+        for _ in 0..timer.timer.times_finished_this_tick() {
+            commands.trigger(ItemTimer {
+                entity,
+                item_name: item.name.clone(),
+                kind: item.kind,
+            });
+        }
+    }
+}
@@ -0,0 +1,261 @@
+//! The flow of a game: players, balls per game, scores and extra balls.
+//!
+//! Pressing Start begins a game, pressing it again during the first ball adds a player. Both cost
+//! a credit, see [`Credits`], as does buying an extra ball during a game. A ball
+//! ends when the last ball on the table drains, after which the next player is up. The game ends
+//! after the last ball of the last player. Scripts observe [`GameStarted`], [`BallStarted`],
+//! [`BallEnded`] and [`GameOver`], and score points through [`GameState::add_score`].
+
+use crate::input::{InputAction, action_just_pressed};
+use crate::pinball::ball::Ball;
+use crate::pinball::credits::{CreditSettings, Credits};
+use crate::pinball::events::{ItemHit, ItemKind};
+use crate::pinball::highscores::entering_initials;
+use crate::pinball::scripts::default_table::DrainSettings;
+use crate::screens::Screen;
+use crate::{AppSystems, PausableSystems};
+use bevy::prelude::*;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<GameSettings>();
+    app.init_resource::<GameState>();
+    app.add_observer(note_drained_ball);
+    app.add_systems(
+        Update,
+        (
+            start_game
+                .run_if(action_just_pressed(InputAction::Start))
+                .run_if(not(entering_initials)),
+            buy_extra_ball.run_if(action_just_pressed(InputAction::ExtraBall)),
+            end_ball,
+        )
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    app.add_systems(OnExit(Screen::Gameplay), reset_game);
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct GameSettings {
+    pub balls_per_game: u32,
+    pub max_players: usize,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            balls_per_game: 3,
+            max_players: 4,
+        }
+    }
+}
+
+/// The current game. After the game is over the players are kept to show the final scores.
+#[derive(Resource, Debug, Default, Clone)]
+pub struct GameState {
+    pub players: Vec<PlayerState>,
+    /// Index into `players`.
+    pub current_player: usize,
+    /// The current ball number, starting at 1.
+    pub ball: u32,
+    in_progress: bool,
+    /// Balls that drained and may still be on the table, until the drain logic removes them.
+    drained_balls: Vec<Entity>,
+    /// Set when the last ball on the table drained, the turn moves on in [`end_ball`].
+    ball_ended: bool,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PlayerState {
+    pub score: u64,
+    pub extra_balls: u32,
+}
+
+impl GameState {
+    pub fn in_progress(&self) -> bool {
+        self.in_progress
+    }
+
+    /// Whether a game was played and is over, the final scores are still kept.
+    pub fn is_over(&self) -> bool {
+        !self.in_progress && !self.players.is_empty()
+    }
+
+    pub fn current(&self) -> Option<&PlayerState> {
+        self.players.get(self.current_player)
+    }
+
+    /// Adds points to the current player, does nothing if no game is in progress.
+    pub fn add_score(&mut self, points: i64) {
+        if !self.in_progress {
+            return;
+        }
+        if let Some(player) = self.players.get_mut(self.current_player) {
+            player.score = player.score.saturating_add_signed(points);
+        }
+    }
+
+    /// Gives the current player another ball once the current one ends.
+    pub fn add_extra_ball(&mut self) {
+        if !self.in_progress {
+            return;
+        }
+        if let Some(player) = self.players.get_mut(self.current_player) {
+            player.extra_balls += 1;
+        }
+    }
+
+    pub fn scores(&self) -> Vec<u64> {
+        self.players.iter().map(|player| player.score).collect()
+    }
+}
+
+/// A new game started with one player.
+#[derive(Event, Debug, Clone)]
+pub struct GameStarted;
+
+/// A player is up with a new ball.
+#[derive(Event, Debug, Clone)]
+#[allow(dead_code)]
+pub struct BallStarted {
+    /// Index of the player, starting at 0.
+    pub player: usize,
+    pub ball: u32,
+}
+
+/// The last ball of a player's turn left the table.
+#[derive(Event, Debug, Clone)]
+#[allow(dead_code)]
+pub struct BallEnded {
+    /// Index of the player, starting at 0.
+    pub player: usize,
+    pub ball: u32,
+}
+
+/// The last ball of the last player ended.
+#[derive(Event, Debug, Clone)]
+#[allow(dead_code)]
+pub struct GameOver {
+    /// The final score of each player.
+    pub scores: Vec<u64>,
+}
+
+fn start_game(
+    mut commands: Commands,
+    mut game: ResMut<GameState>,
+    settings: Res<GameSettings>,
+    mut credits: ResMut<Credits>,
+    credit_settings: Res<CreditSettings>,
+) {
+    let can_add_player = game.ball == 1 && game.players.len() < settings.max_players;
+    if game.in_progress && !can_add_player {
+        return;
+    }
+    if !credits.use_credit(&credit_settings) {
+        info!("Insert coin to play");
+        return;
+    }
+    if !game.in_progress {
+        *game = GameState {
+            players: vec![PlayerState::default()],
+            current_player: 0,
+            ball: 1,
+            in_progress: true,
+            ..default()
+        };
+        info!("Game started");
+        commands.trigger(GameStarted);
+        commands.trigger(BallStarted { player: 0, ball: 1 });
+    } else {
+        game.players.push(PlayerState::default());
+        info!("Player {} joined the game", game.players.len());
+    }
+}
+
+/// Buys an extra ball for the current player with a credit.
+fn buy_extra_ball(
+    mut game: ResMut<GameState>,
+    mut credits: ResMut<Credits>,
+    credit_settings: Res<CreditSettings>,
+) {
+    if !game.in_progress {
+        return;
+    }
+    if !credits.use_credit(&credit_settings) {
+        info!("Insert coin to buy an extra ball");
+        return;
+    }
+    game.add_extra_ball();
+    info!("Extra ball for player {}", game.current_player + 1);
+}
+
+/// Notes the end of the ball when the last ball on the table drains. A ball that is already on
+/// the table when the game starts counts as the first ball.
+fn note_drained_ball(
+    hit: On<ItemHit>,
+    mut game: ResMut<GameState>,
+    drain_settings: Res<DrainSettings>,
+    ball_query: Query<Entity, With<Ball>>,
+) {
+    if !game.in_progress
+        || hit.kind != ItemKind::Kicker
+        || !hit
+            .item_name
+            .eq_ignore_ascii_case(&drain_settings.drain_kicker)
+    {
+        return;
+    }
+    // scripts remove the drained ball and create the next one later, so the balls that are still
+    // on the table are only those that did not drain
+    game.drained_balls.retain(|ball| ball_query.contains(*ball));
+    if game.drained_balls.contains(&hit.ball) {
+        return;
+    }
+    game.drained_balls.push(hit.ball);
+    if ball_query
+        .iter()
+        .all(|ball| game.drained_balls.contains(&ball))
+    {
+        game.ball_ended = true;
+    }
+}
+
+/// Ends the turn after the last ball drained and moves on to the next player.
+fn end_ball(mut commands: Commands, mut game: ResMut<GameState>, settings: Res<GameSettings>) {
+    if !game.ball_ended {
+        return;
+    }
+    game.ball_ended = false;
+    let (player, ball) = (game.current_player, game.ball);
+    info!("Ball {} of player {} ended", ball, player + 1);
+    commands.trigger(BallEnded { player, ball });
+
+    let current = &mut game.players[player];
+    if current.extra_balls > 0 {
+        current.extra_balls -= 1;
+        info!("Shoot again!");
+    } else {
+        game.current_player = (player + 1) % game.players.len();
+        if game.current_player == 0 {
+            game.ball += 1;
+        }
+    }
+    if game.ball > settings.balls_per_game {
+        game.in_progress = false;
+        info!("Game over, scores {:?}", game.scores());
+        commands.trigger(GameOver {
+            scores: game.scores(),
+        });
+        return;
+    }
+    commands.trigger(BallStarted {
+        player: game.current_player,
+        ball: game.ball,
+    });
+}
+
+fn reset_game(mut game: ResMut<GameState>) {
+    *game = GameState::default();
+}
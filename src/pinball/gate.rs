@@ -0,0 +1,89 @@
+//! Gates and spinners, which balls pass through. A ball passing sends an
+//! [`ItemSpin`](crate::pinball::events::ItemSpin) besides the usual hit and unhit.
+
+use crate::pinball::events::{GameItem, ItemKind};
+use crate::pinball::height::Z_IN_PLAYFIELD;
+use avian2d::prelude::*;
+use bevy::color::palettes::css;
+use bevy::ecs::relationship::RelatedSpawnerCommands;
+use bevy::prelude::*;
+use vpin::vpx;
+use vpin::vpx::vpu_to_m;
+
+const GATE_COLOR: Srgba = css::SILVER;
+const SPINNER_COLOR: Srgba = css::GOLD;
+/// How thick the line of a gate or spinner is drawn, in meters.
+const LINE_WIDTH: f32 = 0.002;
+
+pub(super) fn spawn_gate(
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    vpx_to_bevy_transform: Transform,
+    parent: &mut RelatedSpawnerCommands<ChildOf>,
+    gate: &vpx::gameitem::gate::Gate,
+) {
+    parent.spawn((
+        GameItem::new(&gate.name, ItemKind::Gate),
+        Name::from(format!("Gate {}", gate.name)),
+        passage(
+            meshes,
+            materials,
+            vpx_to_bevy_transform,
+            Vec2::new(gate.center.x, gate.center.y),
+            gate.length,
+            gate.rotation,
+            GATE_COLOR,
+        ),
+    ));
+}
+
+pub(super) fn spawn_spinner(
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    vpx_to_bevy_transform: Transform,
+    parent: &mut RelatedSpawnerCommands<ChildOf>,
+    spinner: &vpx::gameitem::spinner::Spinner,
+) {
+    parent.spawn((
+        GameItem::new(&spinner.name, ItemKind::Spinner),
+        Name::from(format!("Spinner {}", spinner.name)),
+        passage(
+            meshes,
+            materials,
+            vpx_to_bevy_transform,
+            Vec2::new(spinner.center.x, spinner.center.y),
+            spinner.length,
+            spinner.rotation,
+            SPINNER_COLOR,
+        ),
+    ));
+}
+
+/// A line across the path of the ball that only reports when it is crossed.
+/// The rotation is in degrees, clockwise like in VPX, with 0 lying across the table.
+fn passage(
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    vpx_to_bevy_transform: Transform,
+    center: Vec2,
+    length: f32,
+    rotation: f32,
+    color: Srgba,
+) -> impl Bundle {
+    let length = vpu_to_m(length);
+    (
+        Transform::from_xyz(
+            vpx_to_bevy_transform.translation.x + vpu_to_m(center.x),
+            vpx_to_bevy_transform.translation.y - vpu_to_m(center.y),
+            Z_IN_PLAYFIELD,
+        )
+        .with_rotation(Quat::from_rotation_z(-rotation.to_radians())),
+        Mesh2d(meshes.add(Rectangle::new(length, LINE_WIDTH))),
+        MeshMaterial2d(materials.add(Color::from(color))),
+        // physics
+        CollisionEventsEnabled,
+        RigidBody::Static,
+        Collider::rectangle(length, LINE_WIDTH),
+        Sensor,
+    )
+}
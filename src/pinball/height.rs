@@ -0,0 +1,308 @@
+//! A simulated height above the playfield for the balls, the physics itself stays flat.
+//!
+//! A ball kicked with an inclination leaves the playfield and falls back under gravity. While it
+//! is in the air it only collides with items whose height range it overlaps, so it clears walls,
+//! rubbers and bumpers that are lower than it. The height shows as a larger ball with its shadow
+//! falling further away.
+//!
+//! Heights also decide the drawing order, see [`item_z`]. Balls always cast a shadow on the
+//! playfield, walls and bumpers can cast optional soft drop shadows, see [`drop_shadow`].
+
+use crate::PausableSystems;
+use crate::pinball::ball::{BALL_RADIUS_M, Ball};
+use crate::pinball::view::ViewSettings;
+use crate::screens::Screen;
+use avian2d::prelude::*;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ShadowMaterial>();
+    app.add_observer(add_ball_shadow);
+    app.add_systems(
+        FixedUpdate,
+        fly_balls
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    app.add_systems(
+        Update,
+        show_ball_height
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    app.add_systems(Update, show_drop_shadows.run_if(in_state(Screen::Gameplay)));
+}
+
+/// Gravity pulling a ball in the air back to the playfield, in m/s².
+const GRAVITY: f32 = 9.81;
+/// The part of the vertical speed a ball keeps when it bounces on the playfield.
+const BOUNCE: f32 = 0.3;
+/// A ball landing slower than this, in m/s, stays on the playfield.
+const MIN_BOUNCE_SPEED: f32 = 0.2;
+/// How much larger the ball looks per meter above the playfield.
+const SCALE_PER_M: f32 = 2.0;
+/// How far a shadow falls from its item per meter above the playfield, lit from the top left.
+const SHADOW_OFFSET_PER_M: Vec2 = Vec2::new(0.5, -0.5);
+/// How far the layers of a shadow spread to soften its edges, in meters.
+const SHADOW_BLUR_M: f32 = 0.002;
+/// The color of a single shadow layer, the layers add up where they overlap.
+const SHADOW_LAYER_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.08);
+
+/// Items lying in the playfield, like inserts, triggers and kickers, are drawn just above it.
+pub const Z_IN_PLAYFIELD: f32 = 0.0005;
+/// Shadows are drawn over the playfield and what lies in it, and under every item with a height.
+const Z_SHADOW: f32 = 0.001;
+/// Items with a height are drawn over the shadows.
+const Z_ABOVE_SHADOWS: f32 = 0.002;
+
+/// The z of an item whose top is this high above the playfield, in meters. Higher items cover
+/// lower ones and a ball covers everything it is above.
+pub fn item_z(top: f32) -> f32 {
+    top.max(0.0) + Z_ABOVE_SHADOWS
+}
+
+/// The height of the bottom of a ball above the playfield, in meters.
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct BallHeight {
+    pub height: f32,
+    /// Upwards is positive, in m/s.
+    pub vertical_velocity: f32,
+}
+
+impl BallHeight {
+    /// Whether two balls at these heights touch.
+    fn touches(self, other: BallHeight) -> bool {
+        (self.height - other.height).abs() < BALL_RADIUS_M * 2.0
+    }
+}
+
+/// The height range of an item above the playfield, in meters. Balls that are completely above
+/// or below it pass. Items without a height range always collide.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct ItemHeight {
+    pub bottom: f32,
+    pub top: f32,
+}
+
+impl ItemHeight {
+    fn collides(self, ball: BallHeight) -> bool {
+        ball.height < self.top && ball.height + BALL_RADIUS_M * 2.0 > self.bottom
+    }
+}
+
+/// The shadow of a ball, on the playfield below it. Unlike [`DropShadow`]s it is always shown.
+#[derive(Component, Debug)]
+pub(super) struct BallShadow;
+
+/// A soft shadow cast on the playfield, shown when [`ViewSettings::drop_shadows`] is on.
+#[derive(Component, Debug)]
+pub(super) struct DropShadow;
+
+/// The material of all shadow layers.
+#[derive(Resource, Debug)]
+pub(super) struct ShadowMaterial(Handle<ColorMaterial>);
+
+impl FromWorld for ShadowMaterial {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.resource_mut::<Assets<ColorMaterial>>();
+        Self(materials.add(ColorMaterial::from(SHADOW_LAYER_COLOR)))
+    }
+}
+
+/// The shadow of an item with the given mesh, whose top is `top` above the playfield. It is
+/// spawned as a child of the item, which is drawn at `item_z`.
+pub(super) fn drop_shadow(
+    mesh: Handle<Mesh>,
+    material: &ShadowMaterial,
+    top: f32,
+    item_z: f32,
+) -> impl Bundle {
+    (
+        Name::new("Drop Shadow"),
+        DropShadow,
+        shadow_transform(top, item_z),
+        Visibility::Hidden,
+        shadow_layers(mesh, material),
+    )
+}
+
+fn shadow_transform(top: f32, item_z: f32) -> Transform {
+    Transform::from_translation((SHADOW_OFFSET_PER_M * top).extend(Z_SHADOW - item_z))
+}
+
+fn shadow_layers(mesh: Handle<Mesh>, material: &ShadowMaterial) -> impl Bundle {
+    let material = material.0.clone();
+    let layer = move |offset: Vec2| {
+        (
+            Mesh2d(mesh.clone()),
+            MeshMaterial2d(material.clone()),
+            Transform::from_translation((offset * SHADOW_BLUR_M).extend(0.0)),
+        )
+    };
+    // overlapping layers are darkest in the middle and fade out at the edges
+    children![
+        layer(Vec2::ZERO),
+        layer(Vec2::new(1.0, 1.0)),
+        layer(Vec2::new(1.0, -1.0)),
+        layer(Vec2::new(-1.0, 1.0)),
+        layer(Vec2::new(-1.0, -1.0)),
+    ]
+}
+
+/// Gives every new ball its shadow, moved by [`show_ball_height`].
+fn add_ball_shadow(
+    add: On<Add, Ball>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    material: Res<ShadowMaterial>,
+) {
+    let top = BALL_RADIUS_M * 2.0;
+    let mesh = meshes.add(Circle::new(BALL_RADIUS_M));
+    commands.entity(add.entity).with_child((
+        Name::new("Ball Shadow"),
+        BallShadow,
+        shadow_transform(top, item_z(top)),
+        Visibility::default(),
+        shadow_layers(mesh, &material),
+    ));
+}
+
+/// The image of a ball, scaled with its height. The ball itself isn't scaled as that would
+/// scale its collider.
+#[derive(Component, Debug)]
+pub(super) struct BallImage;
+
+/// Drops the contacts between balls and items at different heights, see [`ItemHeight`].
+#[derive(SystemParam)]
+pub struct HeightCollisionHooks<'w, 's> {
+    balls: Query<'w, 's, &'static BallHeight>,
+    items: Query<'w, 's, &'static ItemHeight>,
+}
+
+impl CollisionHooks for HeightCollisionHooks<'_, '_> {
+    fn modify_contacts(&self, contacts: &mut ContactPair, _commands: &mut Commands) -> bool {
+        let (entity1, entity2) = (contacts.collider1, contacts.collider2);
+        match (self.balls.get(entity1), self.balls.get(entity2)) {
+            (Ok(ball1), Ok(ball2)) => ball1.touches(*ball2),
+            (Ok(ball), Err(_)) => self
+                .items
+                .get(entity2)
+                .ok()
+                .is_none_or(|item| item.collides(*ball)),
+            (Err(_), Ok(ball)) => self
+                .items
+                .get(entity1)
+                .ok()
+                .is_none_or(|item| item.collides(*ball)),
+            (Err(_), Err(_)) => true,
+        }
+    }
+}
+
+/// Moves balls in the air up and down, bouncing them on the playfield.
+fn fly_balls(
+    time: Res<Time>,
+    mut ball_query: Query<(&mut BallHeight, &mut Transform, &RigidBody), With<Ball>>,
+) {
+    let delta = time.delta_secs();
+    for (mut ball, mut transform, rigid_body) in &mut ball_query {
+        // balls held by a kicker wait on the playfield
+        if *rigid_body != RigidBody::Dynamic
+            || (ball.height <= 0.0 && ball.vertical_velocity <= 0.0)
+        {
+            continue;
+        }
+        ball.vertical_velocity -= GRAVITY * delta;
+        ball.height += ball.vertical_velocity * delta;
+        if ball.height <= 0.0 {
+            ball.height = 0.0;
+            ball.vertical_velocity = if -ball.vertical_velocity > MIN_BOUNCE_SPEED {
+                -ball.vertical_velocity * BOUNCE
+            } else {
+                0.0
+            };
+        }
+        transform.translation.z = item_z(ball.height + BALL_RADIUS_M * 2.0);
+    }
+}
+
+fn show_ball_height(
+    ball_query: Query<(&BallHeight, &Children), Changed<BallHeight>>,
+    mut image_query: Query<&mut Transform, (With<BallImage>, Without<BallShadow>)>,
+    mut shadow_query: Query<&mut Transform, (With<BallShadow>, Without<BallImage>)>,
+) {
+    for (ball, children) in &ball_query {
+        for child in children {
+            if let Ok(mut transform) = image_query.get_mut(*child) {
+                transform.scale = Vec3::splat(1.0 + ball.height * SCALE_PER_M);
+            }
+            if let Ok(mut transform) = shadow_query.get_mut(*child) {
+                let top = ball.height + BALL_RADIUS_M * 2.0;
+                let offset = SHADOW_OFFSET_PER_M * top;
+                transform.translation = offset.extend(Z_SHADOW - item_z(top));
+            }
+        }
+    }
+}
+
+fn show_drop_shadows(
+    settings: Res<ViewSettings>,
+    mut shadow_query: Query<&mut Visibility, With<DropShadow>>,
+) {
+    let visibility = if settings.drop_shadows {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    for mut shadow in &mut shadow_query {
+        shadow.set_if_neq(visibility);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ball_at(height: f32) -> BallHeight {
+        BallHeight {
+            height,
+            vertical_velocity: 0.0,
+        }
+    }
+
+    #[test]
+    fn balls_clear_lower_items() {
+        let wall = ItemHeight {
+            bottom: 0.0,
+            top: 0.05,
+        };
+        assert!(wall.collides(ball_at(0.0)));
+        assert!(wall.collides(ball_at(0.049)));
+        assert!(!wall.collides(ball_at(0.05)));
+        assert!(!wall.collides(ball_at(0.1)));
+    }
+
+    #[test]
+    fn balls_pass_under_raised_items() {
+        let ramp = ItemHeight {
+            bottom: 0.05,
+            top: 0.08,
+        };
+        let diameter = BALL_RADIUS_M * 2.0;
+        assert!(!ramp.collides(ball_at(0.0)));
+        assert!(!ramp.collides(ball_at(0.05 - diameter - 0.001)));
+        assert!(ramp.collides(ball_at(0.05 - diameter + 0.001)));
+        assert!(ramp.collides(ball_at(0.06)));
+    }
+
+    #[test]
+    fn balls_touch_when_their_heights_overlap() {
+        let diameter = BALL_RADIUS_M * 2.0;
+        assert!(ball_at(0.0).touches(ball_at(0.0)));
+        assert!(ball_at(0.0).touches(ball_at(diameter * 0.9)));
+        assert!(ball_at(diameter * 0.9).touches(ball_at(0.0)));
+        assert!(!ball_at(0.0).touches(ball_at(diameter)));
+        assert!(!ball_at(diameter * 2.0).touches(ball_at(0.0)));
+    }
+}
@@ -0,0 +1,433 @@
+//! Persistent high scores per table, with initials entry after a game.
+//!
+//! Tables are identified by their table info, or by the hash of their script if they have none,
+//! see [`table_key`]. The scores are stored in a small versioned text file next to the assets
+//! folder:
+//!
+//! ```text
+//! vpinball2d-highscores 1
+//! table North Pole 6.0
+//! 12500 FDB
+//! 9100 AAA
+//! ```
+//!
+//! The scores of the table are shown over the playfield while no game is running, in attract mode
+//! and after a game over. After a game over every player with a qualifying score first enters
+//! their initials, using the flippers or arrow keys to pick a letter and Start or the plunger to
+//! confirm it.
+
+use crate::input::{ActionState, InputAction};
+use crate::pinball::game::{GameOver, GameState};
+use crate::pinball::table::TableAssets;
+use crate::screens::Screen;
+use crate::theme::widget;
+use crate::vpx::{TableIdentity, VpxAsset};
+use crate::{AppSystems, PausableSystems};
+use bevy::prelude::*;
+use std::collections::{BTreeMap, VecDeque};
+use thiserror::Error;
+
+pub(super) fn plugin(app: &mut App) {
+    app.insert_resource(HighScores::load());
+    app.add_observer(start_initials_entry);
+    app.add_systems(OnEnter(Screen::Gameplay), spawn_high_score_panel);
+    app.add_systems(
+        Update,
+        (
+            enter_initials.run_if(resource_exists::<InitialsEntry>),
+            update_high_score_panel,
+        )
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    app.add_systems(OnExit(Screen::Gameplay), stop_initials_entry);
+}
+
+/// The number of scores kept per table.
+const MAX_ENTRIES: usize = 5;
+const FILE_HEADER: &str = "vpinball2d-highscores";
+const FILE_VERSION: u32 = 1;
+#[cfg(not(target_family = "wasm"))]
+const FILE_NAME: &str = "highscores.txt";
+
+const INITIALS_LEN: usize = 3;
+const INITIALS_LETTERS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HighScore {
+    pub initials: String,
+    pub score: u64,
+}
+
+#[derive(Error, Debug)]
+pub enum HighScoreError {
+    #[error("not a high score file")]
+    NotHighScores,
+    #[error("unsupported version {0}")]
+    UnsupportedVersion(String),
+    #[error("line {line}: {message}")]
+    Invalid { line: usize, message: String },
+    #[error("could not read or write the high scores: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// The high scores of all tables.
+#[derive(Resource, Debug, Default)]
+pub struct HighScores {
+    tables: BTreeMap<String, Vec<HighScore>>,
+    /// Set when the stored file could not be read, so that saving doesn't overwrite it.
+    read_only: bool,
+}
+
+/// The key of a table in the high score file.
+fn table_key(identity: &TableIdentity) -> String {
+    match (&identity.name, &identity.version) {
+        (Some(name), Some(version)) => format!("{name} {version}"),
+        (Some(name), None) => name.clone(),
+        _ => format!("{:016x}", identity.hash),
+    }
+}
+
+impl HighScores {
+    /// The high scores of a table, best first.
+    fn table(&self, key: &str) -> &[HighScore] {
+        self.tables.get(key).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Whether the score makes it onto the high score list of the table.
+    pub fn qualifies(&self, key: &str, score: u64) -> bool {
+        let scores = self.table(key);
+        score > 0
+            && (scores.len() < MAX_ENTRIES || scores.last().is_some_and(|last| score > last.score))
+    }
+
+    pub fn insert(&mut self, key: &str, high_score: HighScore) {
+        let scores = self.tables.entry(key.to_string()).or_default();
+        // equal scores keep their place, the older one stays ahead
+        let position = scores.partition_point(|s| s.score >= high_score.score);
+        scores.insert(position, high_score);
+        scores.truncate(MAX_ENTRIES);
+    }
+
+    fn parse(text: &str) -> Result<Self, HighScoreError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()));
+        let version = lines
+            .next()
+            .and_then(|(_, header)| header.strip_prefix(FILE_HEADER))
+            .ok_or(HighScoreError::NotHighScores)?
+            .trim();
+        if version != FILE_VERSION.to_string() {
+            return Err(HighScoreError::UnsupportedVersion(version.to_string()));
+        }
+        let mut high_scores = HighScores::default();
+        let mut table = None;
+        for (line, text) in lines.filter(|(_, text)| !text.is_empty()) {
+            if let Some(key) = text.strip_prefix("table ") {
+                table = Some(key.trim().to_string());
+                continue;
+            }
+            let invalid = |message: &str| HighScoreError::Invalid {
+                line,
+                message: message.to_string(),
+            };
+            let key = table
+                .as_ref()
+                .ok_or_else(|| invalid("score before the first table"))?;
+            let (score, initials) = text
+                .split_once(' ')
+                .ok_or_else(|| invalid("expected a score and initials"))?;
+            let score = score.parse().map_err(|_| invalid("invalid score"))?;
+            high_scores.insert(
+                key,
+                HighScore {
+                    initials: initials.trim().to_string(),
+                    score,
+                },
+            );
+        }
+        Ok(high_scores)
+    }
+
+    fn to_text(&self) -> String {
+        let mut text = format!("{FILE_HEADER} {FILE_VERSION}\n");
+        for (key, scores) in &self.tables {
+            text.push_str(&format!("table {key}\n"));
+            for high_score in scores {
+                text.push_str(&format!("{} {}\n", high_score.score, high_score.initials));
+            }
+        }
+        text
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    fn path() -> std::path::PathBuf {
+        bevy::asset::io::file::FileAssetReader::get_base_path().join(FILE_NAME)
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    fn load() -> Self {
+        let path = Self::path();
+        let result = match std::fs::read_to_string(&path) {
+            Ok(text) => Self::parse(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        };
+        result.unwrap_or_else(|e| {
+            warn!(
+                "Ignoring high scores in {}, they won't be updated: {}",
+                path.display(),
+                e
+            );
+            Self {
+                read_only: true,
+                ..default()
+            }
+        })
+    }
+
+    /// High scores are not stored on the web.
+    #[cfg(target_family = "wasm")]
+    fn load() -> Self {
+        Self::default()
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    fn save(&self) {
+        if self.read_only {
+            return;
+        }
+        let path = Self::path();
+        if let Err(e) = std::fs::write(&path, self.to_text()) {
+            warn!("Failed to save high scores to {}: {}", path.display(), e);
+        }
+    }
+
+    #[cfg(target_family = "wasm")]
+    fn save(&self) {}
+}
+
+/// The high score list as text, one score per line.
+fn format_high_scores(scores: &[HighScore]) -> String {
+    if scores.is_empty() {
+        return "No high scores yet".to_string();
+    }
+    scores
+        .iter()
+        .enumerate()
+        .map(|(i, s)| format!("{}. {:<3} {:>12}", i + 1, s.initials, s.score))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Players entering their initials after a game over.
+#[derive(Resource, Debug)]
+pub(super) struct InitialsEntry {
+    table_key: String,
+    /// The players with a qualifying score that still have to enter their initials.
+    waiting: VecDeque<(usize, u64)>,
+    /// The confirmed letters.
+    initials: String,
+    /// The letter being picked, an index into [`INITIALS_LETTERS`].
+    letter: usize,
+}
+
+/// Run condition that is active while players enter their initials.
+pub(super) fn entering_initials(entry: Option<Res<InitialsEntry>>) -> bool {
+    entry.is_some()
+}
+
+fn start_initials_entry(
+    game_over: On<GameOver>,
+    mut commands: Commands,
+    high_scores: Res<HighScores>,
+    table_assets: Res<TableAssets>,
+    assets_vpx: Res<Assets<VpxAsset>>,
+) {
+    let Some(vpx_asset) = assets_vpx.get(&table_assets.vpx) else {
+        return;
+    };
+    let table_key = table_key(&vpx_asset.identity);
+    let mut waiting: Vec<(usize, u64)> = game_over
+        .scores
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|(_, score)| high_scores.qualifies(&table_key, *score))
+        .collect();
+    if waiting.is_empty() {
+        return;
+    }
+    // the best score enters first, lower scores may not qualify anymore when it is their turn
+    waiting.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+    commands.insert_resource(InitialsEntry {
+        table_key,
+        waiting: waiting.into(),
+        initials: String::new(),
+        letter: 0,
+    });
+}
+
+fn enter_initials(
+    mut commands: Commands,
+    mut entry: ResMut<InitialsEntry>,
+    mut high_scores: ResMut<HighScores>,
+    actions: Res<ActionState>,
+) {
+    let Some(&(player, score)) = entry.waiting.front() else {
+        commands.remove_resource::<InitialsEntry>();
+        return;
+    };
+    if !high_scores.qualifies(&entry.table_key, score) {
+        entry.waiting.pop_front();
+        return;
+    }
+    let letters = INITIALS_LETTERS.len();
+    if actions.just_pressed(InputAction::LeftFlipper) || actions.just_pressed(InputAction::MenuLeft)
+    {
+        entry.letter = (entry.letter + letters - 1) % letters;
+    }
+    if actions.just_pressed(InputAction::RightFlipper)
+        || actions.just_pressed(InputAction::MenuRight)
+    {
+        entry.letter = (entry.letter + 1) % letters;
+    }
+    if actions.just_pressed(InputAction::Start) || actions.just_pressed(InputAction::Plunger) {
+        let letter = INITIALS_LETTERS[entry.letter] as char;
+        entry.initials.push(letter);
+        entry.letter = 0;
+        if entry.initials.len() == INITIALS_LEN {
+            let initials = std::mem::take(&mut entry.initials);
+            info!(
+                "Player {} entered high score {} as {}",
+                player + 1,
+                score,
+                initials
+            );
+            high_scores.insert(&entry.table_key, HighScore { initials, score });
+            high_scores.save();
+            entry.waiting.pop_front();
+        }
+    }
+}
+
+fn stop_initials_entry(mut commands: Commands) {
+    commands.remove_resource::<InitialsEntry>();
+}
+
+#[derive(Component)]
+struct HighScorePanel;
+
+fn spawn_high_score_panel(mut commands: Commands) {
+    commands.spawn((
+        widget::ui_root("High Scores"),
+        HighScorePanel,
+        Visibility::Hidden,
+        DespawnOnExit(Screen::Gameplay),
+        children![widget::label("")],
+    ));
+}
+
+/// Shows the high scores in attract mode, and the initials entry after a game over.
+fn update_high_score_panel(
+    mut panel_query: Query<(&mut Visibility, &Children), With<HighScorePanel>>,
+    mut text_query: Query<&mut Text>,
+    high_scores: Res<HighScores>,
+    entry: Option<Res<InitialsEntry>>,
+    game: Res<GameState>,
+    table_assets: Res<TableAssets>,
+    assets_vpx: Res<Assets<VpxAsset>>,
+) {
+    let Some(vpx_asset) = assets_vpx.get(&table_assets.vpx) else {
+        return;
+    };
+    let text = match entry.as_deref() {
+        Some(entry) => entry.waiting.front().map(|(player, score)| {
+            let letter = INITIALS_LETTERS[entry.letter] as char;
+            let blanks = "_".repeat(INITIALS_LEN - entry.initials.len() - 1);
+            format!(
+                "Player {} - {}\nEnter your initials\n{}[{}]{}",
+                player + 1,
+                score,
+                entry.initials,
+                letter,
+                blanks
+            )
+        }),
+        None if !game.in_progress() => {
+            let scores = high_scores.table(&table_key(&vpx_asset.identity));
+            Some(format!(
+                "HIGH SCORES\n{}\n\nPress Start",
+                format_high_scores(scores)
+            ))
+        }
+        None => None,
+    };
+    for (mut visibility, children) in &mut panel_query {
+        visibility.set_if_neq(if text.is_some() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+        if let Some(text) = &text {
+            let mut texts = text_query.iter_many_mut(children);
+            while let Some(mut label) = texts.fetch_next() {
+                if label.0 != *text {
+                    label.0 = text.clone();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn high_score(initials: &str, score: u64) -> HighScore {
+        HighScore {
+            initials: initials.to_string(),
+            score,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut high_scores = HighScores::default();
+        high_scores.insert("North Pole 6.0", high_score("AAA", 9100));
+        high_scores.insert("North Pole 6.0", high_score("FDB", 12500));
+        high_scores.insert("00000000deadbeef", high_score("ZZ9", 42));
+        let text = high_scores.to_text();
+        assert_eq!(
+            text,
+            "vpinball2d-highscores 1\n\
+             table 00000000deadbeef\n42 ZZ9\n\
+             table North Pole 6.0\n12500 FDB\n9100 AAA\n"
+        );
+        let parsed = HighScores::parse(&text).unwrap();
+        assert_eq!(
+            parsed.table("North Pole 6.0"),
+            high_scores.table("North Pole 6.0")
+        );
+        assert_eq!(parsed.to_text(), text);
+    }
+
+    #[test]
+    fn keeps_the_best_scores() {
+        let mut high_scores = HighScores::default();
+        for score in 1..=MAX_ENTRIES as u64 + 2 {
+            high_scores.insert("Table", high_score("AAA", score * 100));
+        }
+        let scores = high_scores.table("Table");
+        assert_eq!(scores.len(), MAX_ENTRIES);
+        assert_eq!(scores[0].score, (MAX_ENTRIES as u64 + 2) * 100);
+        assert!(!high_scores.qualifies("Table", 300));
+        assert!(high_scores.qualifies("Table", 301));
+        assert!(high_scores.qualifies("Other Table", 1));
+        assert!(!high_scores.qualifies("Other Table", 0));
+    }
+}
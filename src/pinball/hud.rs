@@ -0,0 +1,280 @@
+//! The heads-up display: player scores, the current ball, credits and status messages.
+//!
+//! The HUD sits in the space that the camera fit leaves beside the playfield, or over the bottom
+//! of the table when the window is too narrow for that. It is hidden while a menu is open.
+//! Status messages are shown by triggering a [`StatusMessage`], scripts show them through
+//! [`ScriptCommand::ShowStatus`](crate::pinball::scripts::api::ScriptCommand::ShowStatus). The dot-matrix display sits at
+//! the top of the HUD, unless the backglass has a window of its own.
+
+use crate::menus::Menu;
+use crate::pinball::credits::{CreditSettings, Credits};
+use crate::pinball::dmd::{DmdTexture, dmd_panel};
+use crate::pinball::game::{BallStarted, GameOver, GameSettings, GameStarted, GameState};
+use crate::pinball::table::TableAssets;
+use crate::pinball::view::{ViewMode, ViewSettings};
+use crate::screens::Screen;
+use crate::theme::{palette, widget};
+use crate::vpx::VpxAsset;
+use crate::{AppSystems, PausableSystems};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use std::time::Duration;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<HudStatus>();
+    app.add_observer(show_status_message);
+    app.add_observer(|_: On<GameOver>, mut commands: Commands| {
+        commands.trigger(StatusMessage::GameOver);
+    });
+    app.add_observer(clear_tilt);
+    app.add_observer(|_: On<GameStarted>, mut status: ResMut<HudStatus>| status.0 = None);
+    app.add_systems(OnEnter(Screen::Gameplay), spawn_hud);
+    app.add_systems(OnExit(Screen::Gameplay), |mut status: ResMut<HudStatus>| {
+        status.0 = None;
+    });
+    app.add_systems(
+        Update,
+        expire_status_message
+            .in_set(AppSystems::TickTimers)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    app.add_systems(
+        Update,
+        (
+            layout_hud,
+            show_hud,
+            update_player_labels,
+            update_ball_label,
+            update_credits_label,
+            update_status_label,
+        )
+            .in_set(AppSystems::Update)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// The HUD needs at least this much space beside the playfield, in logical pixels.
+const SIDE_PANEL_MIN_WIDTH: f32 = 220.0;
+/// How long a [`StatusMessage::BallSaved`] stays on screen.
+const BALL_SAVED_DURATION: Duration = Duration::from_secs(2);
+
+/// A status message to show on the HUD, replacing the current one.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusMessage {
+    /// Shown until the next ball starts.
+    Tilt,
+    BallSaved,
+    /// Shown until a new game starts.
+    GameOver,
+}
+
+impl StatusMessage {
+    /// Parses the message names used by scripts, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "tilt" => Some(StatusMessage::Tilt),
+            "ball saved" => Some(StatusMessage::BallSaved),
+            "game over" => Some(StatusMessage::GameOver),
+            _ => None,
+        }
+    }
+
+    fn text(self) -> &'static str {
+        match self {
+            StatusMessage::Tilt => "TILT",
+            StatusMessage::BallSaved => "BALL SAVED",
+            StatusMessage::GameOver => "GAME OVER",
+        }
+    }
+
+    /// How long the message is shown, `None` keeps it until it is cleared.
+    fn duration(self) -> Option<Duration> {
+        match self {
+            StatusMessage::BallSaved => Some(BALL_SAVED_DURATION),
+            StatusMessage::Tilt | StatusMessage::GameOver => None,
+        }
+    }
+}
+
+/// The status message on the HUD, with the time it has left.
+#[derive(Resource, Debug, Default)]
+struct HudStatus(Option<(StatusMessage, Option<Timer>)>);
+
+fn show_status_message(message: On<StatusMessage>, mut status: ResMut<HudStatus>) {
+    let message = *message;
+    let timer = message
+        .duration()
+        .map(|duration| Timer::new(duration, TimerMode::Once));
+    status.0 = Some((message, timer));
+}
+
+fn clear_tilt(_: On<BallStarted>, mut status: ResMut<HudStatus>) {
+    if matches!(status.0, Some((StatusMessage::Tilt, _))) {
+        status.0 = None;
+    }
+}
+
+fn expire_status_message(time: Res<Time>, mut status: ResMut<HudStatus>) {
+    let Some((_, Some(timer))) = &mut status.0 else {
+        return;
+    };
+    if timer.tick(time.delta()).is_finished() {
+        status.0 = None;
+    }
+}
+
+#[derive(Component)]
+pub(super) struct Hud;
+
+/// Index of the player, starting at 0.
+#[derive(Component)]
+struct PlayerScoreLabel(usize);
+
+#[derive(Component)]
+struct BallLabel;
+
+#[derive(Component)]
+struct CreditsLabel;
+
+#[derive(Component)]
+struct StatusLabel;
+
+fn spawn_hud(mut commands: Commands, settings: Res<GameSettings>, dmd: Res<DmdTexture>) {
+    commands
+        .spawn((
+            Name::new("HUD"),
+            Hud,
+            Node::default(),
+            Pickable::IGNORE,
+            DespawnOnExit(Screen::Gameplay),
+        ))
+        .with_children(|parent| {
+            parent.spawn(dmd_panel(&dmd));
+            for player in 0..settings.max_players {
+                parent.spawn((widget::label(""), PlayerScoreLabel(player)));
+            }
+            parent.spawn((widget::label(""), BallLabel));
+            parent.spawn((widget::label(""), CreditsLabel));
+            parent.spawn((widget::header(""), StatusLabel));
+        });
+}
+
+/// The width of the space beside the playfield, if there is enough room for a panel there.
+/// This uses the same fit as the camera.
+pub(super) fn side_panel_width(
+    window: &Window,
+    vpx_asset: &VpxAsset,
+    view_mode: ViewMode,
+) -> Option<f32> {
+    let gamedata = &vpx_asset.raw.gamedata;
+    let table_size = Vec2::new(
+        gamedata.right - gamedata.left,
+        gamedata.bottom - gamedata.top,
+    );
+    let side_width = view_mode.playfield_area(window.size(), table_size).min.x;
+    (side_width >= SIDE_PANEL_MIN_WIDTH).then_some(side_width)
+}
+
+/// Puts the HUD beside the playfield when there is room for it.
+fn layout_hud(
+    window: Single<&Window, With<PrimaryWindow>>,
+    mut hud: Single<&mut Node, With<Hud>>,
+    settings: Res<ViewSettings>,
+    table_assets: Res<TableAssets>,
+    assets_vpx: Res<Assets<VpxAsset>>,
+) {
+    let Some(vpx_asset) = assets_vpx.get(&table_assets.vpx) else {
+        return;
+    };
+    let node = if let Some(side_width) = side_panel_width(&window, vpx_asset, settings.view_mode) {
+        Node {
+            position_type: PositionType::Absolute,
+            left: px(0),
+            top: px(0),
+            width: px(side_width),
+            height: percent(100),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            row_gap: px(10),
+            ..default()
+        }
+    } else {
+        Node {
+            position_type: PositionType::Absolute,
+            left: px(0),
+            right: px(0),
+            bottom: px(20),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            row_gap: px(10),
+            ..default()
+        }
+    };
+    hud.set_if_neq(node);
+}
+
+fn show_hud(menu: Res<State<Menu>>, mut hud: Single<&mut Visibility, With<Hud>>) {
+    hud.set_if_neq(if *menu.get() == Menu::None {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    });
+}
+
+fn update_player_labels(
+    game: Res<GameState>,
+    mut label_query: Query<(&PlayerScoreLabel, &mut Text, &mut TextColor, &mut Node)>,
+) {
+    for (label, mut text, mut color, mut node) in &mut label_query {
+        let Some(player) = game.players.get(label.0) else {
+            if node.display != Display::None {
+                node.display = Display::None;
+            }
+            continue;
+        };
+        if node.display != Display::Flex {
+            node.display = Display::Flex;
+        }
+        text.set_if_neq(Text(format!("Player {}  {}", label.0 + 1, player.score)));
+        color.set_if_neq(TextColor(
+            if game.in_progress() && game.current_player == label.0 {
+                palette::HEADER_TEXT
+            } else {
+                palette::LABEL_TEXT
+            },
+        ));
+    }
+}
+
+fn update_ball_label(game: Res<GameState>, mut label: Single<&mut Text, With<BallLabel>>) {
+    let text = if game.in_progress() {
+        format!("Ball {}", game.ball)
+    } else {
+        String::new()
+    };
+    label.set_if_neq(Text(text));
+}
+
+fn update_credits_label(
+    credits: Res<Credits>,
+    settings: Res<CreditSettings>,
+    mut label: Single<&mut Text, With<CreditsLabel>>,
+) {
+    let text = if settings.free_play {
+        "Free Play".to_string()
+    } else {
+        format!("Credits {}", credits.credits)
+    };
+    label.set_if_neq(Text(text));
+}
+
+fn update_status_label(status: Res<HudStatus>, mut label: Single<&mut Text, With<StatusLabel>>) {
+    let text = status
+        .0
+        .as_ref()
+        .map(|(message, _)| message.text())
+        .unwrap_or_default();
+    label.set_if_neq(Text(text.to_string()));
+}
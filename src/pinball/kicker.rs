@@ -1,3 +1,5 @@
+use crate::pinball::events::{GameItem, ItemKind};
+use crate::pinball::height::Z_IN_PLAYFIELD;
 use avian2d::prelude::*;
 use bevy::asset::Assets;
 use bevy::color::Color;
@@ -13,6 +15,7 @@ use vpin::vpx::vpu_to_m;
 pub struct Kicker {
     #[allow(dead_code)]
     pub name: String,
+    pub radius: f32,
 }
 
 const KICKER_COLOR: Srgba = css::GREEN;
@@ -33,12 +36,14 @@ pub(super) fn spawn_kicker(
     parent.spawn((
         Kicker {
             name: kicker.name.clone(),
+            radius,
         },
+        GameItem::new(&kicker.name, ItemKind::Kicker),
         Name::from(format!("Kicker {}", kicker.name)),
         Transform::from_xyz(
             vpx_to_bevy_transform.translation.x + vpu_to_m(kicker.center.x),
             vpx_to_bevy_transform.translation.y - vpu_to_m(kicker.center.y),
-            10.0,
+            Z_IN_PLAYFIELD,
         ),
         Mesh2d(meshes.add(Annulus::new(radius - 0.001, radius))),
         MeshMaterial2d(materials.add(Color::from(KICKER_COLOR))),
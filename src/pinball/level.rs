@@ -1,14 +1,20 @@
 //! Spawn the main level.
 
+use crate::pinball::backglass::spawn_backbox;
+use crate::pinball::backglass_window::BackglassCamera;
 use crate::pinball::ball::ball;
 use crate::pinball::bumper::spawn_bumper;
+use crate::pinball::events::spawn_timer;
+use crate::pinball::gate::{spawn_gate, spawn_spinner};
+use crate::pinball::height::ShadowMaterial;
 use crate::pinball::kicker::spawn_kicker;
 use crate::pinball::light::spawn_light;
 use crate::pinball::plunger::spawn_plunger;
-use crate::pinball::rubber::spawn_rubber;
+use crate::pinball::rubber::{rubber_material, spawn_rubber};
 use crate::pinball::trigger::spawn_trigger;
 use crate::pinball::wall::spawn_wall;
 use crate::vpx::VpxAsset;
+use crate::vpx::material2d::VpxMaterial2d;
 use crate::{
     pinball::table::{TableAssets, table},
     screens::Screen,
@@ -42,14 +48,17 @@ pub fn spawn_level(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    mut vpx_materials: ResMut<Assets<VpxMaterial2d>>,
+    shadow_material: Res<ShadowMaterial>,
     table_assets: Res<TableAssets>,
     assets_vpx: Res<Assets<VpxAsset>>,
-    camera_q: Query<(&Camera, &Projection), With<Camera2d>>,
+    camera_q: Query<(&Camera, &Projection), (With<Camera2d>, Without<BackglassCamera>)>,
 ) {
     let vpx_asset = assets_vpx.get(&table_assets.vpx).unwrap();
     let table_width_m = vpu_to_m(vpx_asset.raw.gamedata.right - vpx_asset.raw.gamedata.left);
     let table_depth_m = vpu_to_m(vpx_asset.raw.gamedata.bottom - vpx_asset.raw.gamedata.top);
     let vpx_to_bevy_transform = Transform::from_xyz(-table_width_m / 2.0, table_depth_m / 2.0, 0.0);
+    let rubber_material = rubber_material(&mut materials);
 
     // TODO the walls should probably be children of the table
     commands
@@ -88,7 +97,7 @@ pub fn spawn_level(
                 GameItemEnum::Wall(wall) => spawn_wall(
                     parent,
                     &meshes,
-                    &mut materials,
+                    &shadow_material,
                     vpx_asset,
                     vpx_to_bevy_transform,
                     wall,
@@ -97,7 +106,8 @@ pub fn spawn_level(
                     spawn_bumper(
                         parent,
                         &mut meshes,
-                        &mut materials,
+                        &shadow_material,
+                        &mut vpx_materials,
                         vpx_asset,
                         vpx_to_bevy_transform,
                         bumper,
@@ -133,7 +143,7 @@ pub fn spawn_level(
                 }
                 GameItemEnum::Rubber(rubber) => spawn_rubber(
                     &mut meshes,
-                    &mut materials,
+                    &rubber_material,
                     vpx_to_bevy_transform,
                     parent,
                     rubber,
@@ -146,7 +156,24 @@ pub fn spawn_level(
                     parent,
                     plunger,
                 ),
+                GameItemEnum::Timer(timer) => spawn_timer(parent, timer),
+                GameItemEnum::Gate(gate) => spawn_gate(
+                    &mut meshes,
+                    &mut materials,
+                    vpx_to_bevy_transform,
+                    parent,
+                    gate,
+                ),
+                GameItemEnum::Spinner(spinner) => spawn_spinner(
+                    &mut meshes,
+                    &mut materials,
+                    vpx_to_bevy_transform,
+                    parent,
+                    spinner,
+                ),
                 _ => (),
             });
         });
+    // reels are on the backglass, outside the playfield
+    spawn_backbox(&mut commands, vpx_asset);
 }
@@ -1,3 +1,7 @@
+use crate::PausableSystems;
+use crate::pinball::events::{GameItem, ItemKind};
+use crate::pinball::height::{Z_IN_PLAYFIELD, item_z};
+use crate::screens::Screen;
 use bevy::asset::Assets;
 use bevy::color::{Color, Srgba};
 use bevy::ecs::children;
@@ -7,10 +11,51 @@ use bevy::prelude::*;
 use vpin::vpx;
 use vpin::vpx::vpu_to_m;
 
+/// Time a blinking light stays on or off.
+const BLINK_INTERVAL_SECS: f32 = 0.125;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        update_light_visuals
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
 #[derive(Component)]
 pub struct Light {
     #[allow(dead_code)]
     pub name: String,
+    color: Srgba,
+}
+
+/// The state of a light as set by the table script, using the VPX numbering.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LightState {
+    Off = 0,
+    #[default]
+    On = 1,
+    Blinking = 2,
+}
+
+impl LightState {
+    pub fn from_vpx(state: i64) -> Self {
+        match state {
+            0 => LightState::Off,
+            2 => LightState::Blinking,
+            _ => LightState::On,
+        }
+    }
+
+    /// Whether the light is on at this time, blinking lights toggle every [`BLINK_INTERVAL_SECS`].
+    pub fn is_lit(self, elapsed_secs: f32) -> bool {
+        match self {
+            LightState::Off => false,
+            LightState::On => true,
+            LightState::Blinking => (elapsed_secs / BLINK_INTERVAL_SECS) as u32 % 2 == 0,
+        }
+    }
 }
 
 pub(super) fn spawn_light(
@@ -24,24 +69,50 @@ pub(super) fn spawn_light(
     let falloff_radius = vpu_to_m(light.falloff_radius);
     let light_color = Srgba::rgb_u8(light.color.r, light.color.g, light.color.b).with_alpha(0.6);
     let light_falloff_color = light_color.clone().with_alpha(0.1);
-    // TODO check what the correct default is in vpinball
-    const DEFAULT_LIGHT_HEIGHT: f32 = 0.01;
+    // inserts lie in the playfield, bulbs stand above it
+    let z = match light.height {
+        Some(height) if height > 0.0 => item_z(vpu_to_m(height)),
+        _ => Z_IN_PLAYFIELD,
+    };
     parent.spawn((
         Light {
             name: light.name.clone(),
+            color: light_color,
         },
+        LightState::default(),
+        GameItem::new(&light.name, ItemKind::Light),
         Name::from(format!("Light {}", light.name)),
         Transform::from_xyz(
             vpx_to_bevy_transform.translation.x + vpu_to_m(light.center.x),
             vpx_to_bevy_transform.translation.y - vpu_to_m(light.center.y),
-            vpu_to_m(light.height.unwrap_or(DEFAULT_LIGHT_HEIGHT)),
+            z,
         ),
         Mesh2d(meshes.add(Circle::new(radius))),
         MeshMaterial2d(materials.add(Color::from(light_color).with_alpha(0.5))),
         children![(
             Mesh2d(meshes.add(Circle::new(falloff_radius))),
             MeshMaterial2d(materials.add(Color::from(light_falloff_color).with_alpha(0.1))),
-            Transform::from_xyz(0.0, 0.0, -0.001)
+            Transform::from_xyz(0.0, 0.0, -0.0001)
         )],
     ));
 }
+
+/// Dims lights that are off and toggles blinking lights.
+fn update_light_visuals(
+    time: Res<Time>,
+    light_query: Query<(&Light, &LightState, &MeshMaterial2d<ColorMaterial>)>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (light, state, material) in light_query.iter() {
+        let alpha = if state.is_lit(time.elapsed_secs()) {
+            0.5
+        } else {
+            0.05
+        };
+        if let Some(material) = materials.get_mut(&material.0)
+            && material.color.alpha() != alpha
+        {
+            material.color = Color::from(light.color).with_alpha(alpha);
+        }
+    }
+}
@@ -6,17 +6,32 @@
 use bevy::prelude::*;
 use std::path::{Path, PathBuf};
 
+mod awards;
+mod backglass;
+pub mod backglass_window;
 mod ball;
 mod ballcontrol;
 mod bumper;
+pub mod credits;
+pub mod dmd;
+mod events;
+mod game;
+mod gate;
+pub mod height;
+pub mod highscores;
+mod hud;
 mod kicker;
 pub mod level;
 mod light;
-mod plunger;
+mod nudge;
+pub mod plunger;
+mod reel;
 mod rubber;
 mod scripts;
+pub(crate) mod settings_file;
 pub mod table;
 mod trigger;
+pub mod view;
 mod wall;
 
 #[derive(Resource)]
@@ -43,5 +58,22 @@ pub(super) fn plugin(app: &mut App) {
         bumper::plugin,
         scripts::plugin,
         plunger::plugin,
+        nudge::plugin,
+        light::plugin,
+        events::plugin,
+        game::plugin,
+        credits::plugin,
+        awards::plugin,
+        highscores::plugin,
+        height::plugin,
+    ));
+    // the displays, kept apart as plugin tuples are limited in size
+    app.add_plugins((
+        backglass::plugin,
+        backglass_window::plugin,
+        dmd::plugin,
+        hud::plugin,
+        reel::plugin,
+        view::plugin,
     ));
 }
@@ -0,0 +1,40 @@
+//! Nudging the table by giving every ball a small push.
+
+use crate::PausableSystems;
+use crate::input::{ActionState, InputAction};
+use crate::pinball::ball::Ball;
+use crate::screens::Screen;
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+/// Impulse in Newton-seconds applied to a ball for a single nudge.
+const NUDGE_IMPULSE: f32 = 0.015;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        nudge
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+fn nudge(actions: Res<ActionState>, mut ball_query: Query<Forces, With<Ball>>) {
+    // Nudging the cabinet moves the table, so relative to the table the ball moves the other way.
+    let mut direction = Vec2::ZERO;
+    if actions.just_pressed(InputAction::NudgeLeft) {
+        direction.x -= 1.0;
+    }
+    if actions.just_pressed(InputAction::NudgeRight) {
+        direction.x += 1.0;
+    }
+    if actions.just_pressed(InputAction::NudgeCenter) {
+        direction.y -= 1.0;
+    }
+    if direction == Vec2::ZERO {
+        return;
+    }
+    for mut forces in ball_query.iter_mut() {
+        forces.apply_linear_impulse(direction.normalize() * NUDGE_IMPULSE);
+    }
+}
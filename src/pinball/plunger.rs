@@ -1,5 +1,7 @@
 use crate::PausableSystems;
 use crate::audio::spatial_sound_effect;
+use crate::input::{ActionState, AxisAction, FixedActionState, InputAction};
+use crate::pinball::settings_file::{self, StoredSettings};
 use crate::pinball::table::TableAssets;
 use crate::screens::Screen;
 use crate::vpx::VpxAsset;
@@ -11,6 +13,10 @@ use vpin::vpx;
 use vpin::vpx::vpu_to_m;
 
 pub(super) fn plugin(app: &mut App) {
+    app.add_plugins(settings_file::plugin::<AnalogPlungerSettings>);
+    app.init_resource::<PlungerCalibration>();
+    // the calibration is part of the settings menu, which is also reachable from the title screen
+    app.add_systems(Update, calibrate_analog_plunger);
     app.add_systems(
         FixedUpdate,
         plunger_movement
@@ -33,6 +39,98 @@ pub struct Plunger {
     stroke: f32,
 }
 
+/// Calibration for an analog plunger, e.g. a gamepad stick or a cabinet plunger.
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct AnalogPlungerSettings {
+    /// Raw axis value when the plunger is at rest.
+    pub rest: f32,
+    /// Raw axis value when the plunger is fully pulled back.
+    pub pulled: f32,
+    /// Fraction of the full pull that is ignored, to filter out noise around the rest position.
+    pub dead_zone: f32,
+    /// How fast the axis must move back towards rest, in full pulls per second, to count as a release.
+    pub release_speed: f32,
+}
+
+impl Default for AnalogPlungerSettings {
+    fn default() -> Self {
+        Self {
+            rest: 0.0,
+            pulled: 1.0,
+            dead_zone: 0.1,
+            release_speed: 5.0,
+        }
+    }
+}
+
+impl StoredSettings for AnalogPlungerSettings {
+    const FILE_HEADER: &'static str = "vpinball2d-plunger";
+    const FILE_VERSION: u32 = 1;
+    const FILE_NAME: &'static str = "plunger.txt";
+
+    fn read(&mut self, key: &str, value: &str) -> Result<bool, String> {
+        let setting = match key {
+            "rest" => &mut self.rest,
+            "pulled" => &mut self.pulled,
+            "dead-zone" => &mut self.dead_zone,
+            "release-speed" => &mut self.release_speed,
+            _ => return Ok(false),
+        };
+        *setting = value.parse().map_err(|_| "invalid number".to_string())?;
+        Ok(true)
+    }
+
+    fn write(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("rest", self.rest.to_string()),
+            ("pulled", self.pulled.to_string()),
+            ("dead-zone", self.dead_zone.to_string()),
+            ("release-speed", self.release_speed.to_string()),
+        ]
+    }
+}
+
+impl AnalogPlungerSettings {
+    /// Converts a raw axis value into a pull between 0 (rest) and 1 (fully pulled).
+    fn pull(&self, raw: f32) -> f32 {
+        let range = self.pulled - self.rest;
+        if range.abs() < f32::EPSILON {
+            return 0.0;
+        }
+        let pull = ((raw - self.rest) / range).clamp(0.0, 1.0);
+        if pull < self.dead_zone {
+            0.0
+        } else {
+            (pull - self.dead_zone) / (1.0 - self.dead_zone)
+        }
+    }
+}
+
+/// Calibrates the analog plunger while it is active. The axis position when it starts is taken
+/// as the rest position, the position furthest from it as the fully pulled position. The result
+/// is kept once the calibration stops.
+#[derive(Resource, Debug, Default)]
+pub struct PlungerCalibration {
+    pub active: bool,
+    /// The rest and pulled positions seen so far.
+    range: Option<(f32, f32)>,
+}
+
+/// The smallest axis range a calibration must see, smaller ranges are taken for noise.
+const MIN_CALIBRATION_RANGE: f32 = 0.2;
+
+/// Tracks the analog plunger axis between fixed steps.
+#[derive(Component, Debug, Default)]
+struct AnalogPlunger {
+    /// The pull during the previous fixed step.
+    last_pull: f32,
+    /// Whether the plunger follows the axis.
+    engaged: bool,
+    /// Set when the plunger is released, it only follows the axis again once that is at rest.
+    released: bool,
+}
+
 pub(super) fn spawn_plunger(
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<ColorMaterial>>,
@@ -87,6 +185,7 @@ pub(super) fn spawn_plunger(
             Collider::rectangle(shape_plunger.size().x, shape_plunger.size().y),
             Restitution::new(0.5), // rubber
             ConstantForce::new(0.0, 0.0),
+            AnalogPlunger::default(),
             LockedAxes::ROTATION_LOCKED.lock_translation_x(),
             Mass::from(0.2), // Light mass for responsive spring
             SweptCcd::default(),
@@ -155,42 +254,112 @@ pub(super) fn spawn_plunger(
 }
 
 fn plunger_movement(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut plungers: Query<(&Plunger, &Transform, &mut ConstantForce)>,
+    fixed_actions: Res<FixedActionState>,
+    actions: Res<ActionState>,
+    settings: Res<AnalogPlungerSettings>,
+    mut plungers: Query<(
+        &Plunger,
+        &Transform,
+        &mut ConstantForce,
+        &mut LinearVelocity,
+        &mut AnalogPlunger,
+    )>,
     time: Res<Time>,
 ) {
     // Newtons per second applied when pulling the plunger
     const PULL_FORCE_PER_SECOND: f32 = 20.0;
     const MAX_FORCE: f32 = 50.0;
+    // Fastest speed in m/s at which an analog plunger follows the axis
+    const MAX_FOLLOW_SPEED: f32 = 2.0;
+    // Fastest speed in m/s at which an analog plunger is released
+    const MAX_RELEASE_SPEED: f32 = 6.0;
 
     let dt = time.delta_secs();
     let delta_force = PULL_FORCE_PER_SECOND * dt;
+    let analog_pull = actions
+        .axis(AxisAction::Plunger)
+        .map(|raw| settings.pull(raw))
+        .unwrap_or(0.0);
 
-    for (plunger, transform, mut constant_force) in plungers.iter_mut() {
+    for (plunger, transform, mut constant_force, mut velocity, mut analog) in plungers.iter_mut() {
         let current_offset = transform.translation.y - plunger.start_point.y;
 
-        if keyboard_input.pressed(KeyCode::Enter) {
+        // the axis speed in full pulls per second, negative when moving back towards rest
+        let pull_speed = if dt > 0.0 {
+            (analog_pull - analog.last_pull) / dt
+        } else {
+            0.0
+        };
+        analog.last_pull = analog_pull;
+
+        if analog_pull == 0.0 {
+            analog.engaged = false;
+            analog.released = false;
+        } else if !analog.engaged && !analog.released {
+            analog.engaged = true;
+        }
+
+        if analog.engaged {
+            if pull_speed < -settings.release_speed {
+                // the axis snapped back, let the spring fire the plunger with the axis speed
+                analog.engaged = false;
+                analog.released = true;
+                constant_force.y = 0.0;
+                velocity.y = (-pull_speed * plunger.stroke).min(MAX_RELEASE_SPEED);
+                debug!("Releasing analog plunger: velocity.y = {}", velocity.y);
+            } else {
+                // the plunger head rests at one stroke above the anchor
+                let target_offset = plunger.stroke * (1.0 - analog_pull);
+                constant_force.y = 0.0;
+                velocity.y = ((target_offset - current_offset) / dt.max(f32::EPSILON))
+                    .clamp(-MAX_FOLLOW_SPEED, MAX_FOLLOW_SPEED);
+            }
+        } else if fixed_actions.pressed(InputAction::Plunger) {
             // Apply downward force if not at max stretch
             if current_offset > -plunger.stroke && constant_force.y > -MAX_FORCE {
                 constant_force.y -= delta_force;
                 debug!("Pulling plunger down: force.y = {}", constant_force.y);
             }
-        } else {
-            // We can't use keyboard_input.just_released because the system runs in FixedUpdate
-            // https://github.com/bevyengine/bevy/issues/6183
+        } else if fixed_actions.just_released(InputAction::Plunger) {
+            debug!("Releasing plunger: force.y = {}", constant_force.y);
             constant_force.y = 0.0;
         }
     }
 }
 
+fn calibrate_analog_plunger(
+    actions: Res<ActionState>,
+    mut calibration: ResMut<PlungerCalibration>,
+    mut settings: ResMut<AnalogPlungerSettings>,
+) {
+    if calibration.active {
+        let Some(raw) = actions.axis(AxisAction::Plunger) else {
+            return;
+        };
+        let (rest, pulled) = calibration.range.get_or_insert((raw, raw));
+        if (raw - *rest).abs() > (*pulled - *rest).abs() {
+            *pulled = raw;
+        }
+    } else if let Some((rest, pulled)) = calibration.range {
+        calibration.range = None;
+        if (pulled - rest).abs() < MIN_CALIBRATION_RANGE {
+            warn!("The plunger was not pulled while calibrating, keeping the previous calibration");
+            return;
+        }
+        info!("Calibrated the analog plunger: rest {rest}, pulled {pulled}");
+        settings.rest = rest;
+        settings.pulled = pulled;
+    }
+}
+
 fn plunger_sound(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    actions: Res<ActionState>,
     mut commands: Commands,
     table_assets: Res<TableAssets>,
     assets_vpx: Res<Assets<VpxAsset>>,
     plunger_query: Query<Entity, With<Plunger>>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Enter) {
+    if actions.just_pressed(InputAction::Plunger) {
         // play plunger pull sound
         let vpx_asset = assets_vpx.get(&table_assets.vpx).unwrap();
         let sound_name = "plungerpull";
@@ -212,7 +381,7 @@ fn plunger_sound(
             warn!("Plunger pull sound '{}' not found in VPX asset", sound_name);
         }
     }
-    if keyboard_input.just_released(KeyCode::Enter) {
+    if actions.just_released(InputAction::Plunger) {
         // play plunger release sound
         // TODO the jpsalas table have a different sound for a release without the ball
         let vpx_asset = assets_vpx.get(&table_assets.vpx).unwrap();
@@ -0,0 +1,282 @@
+//! Electro-mechanical score reels, shown on the backglass, see [`backglass`](super::backglass).
+//!
+//! A VPX reel item is a row of wheels drawn from an image strip, or a grid of images. When the
+//! value changes the wheels roll forward to the new digits one motor step at a time, playing the
+//! reel sound for every digit they pass. Reels follow the score of a player, the first reel of the
+//! table the first player and so on, until a script sets their value through
+//! [`ScriptCommand::SetReelValue`](crate::pinball::scripts::api::ScriptCommand::SetReelValue) or
+//! [`ScriptCommand::AddReelValue`](crate::pinball::scripts::api::ScriptCommand::AddReelValue).
+
+use crate::audio::sound_effect;
+use crate::pinball::backglass::{BACKGLASS_HEIGHT, BACKGLASS_WIDTH};
+use crate::pinball::events::{GameItem, ItemKind};
+use crate::pinball::game::GameState;
+use crate::pinball::table::TableAssets;
+use crate::screens::Screen;
+use crate::theme::widget;
+use crate::vpx::VpxAsset;
+use crate::{AppSystems, PausableSystems};
+use bevy::ecs::relationship::RelatedSpawnerCommands;
+use bevy::prelude::*;
+use vpin::vpx;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            follow_scores.run_if(resource_changed::<GameState>),
+            roll_reels,
+            show_reel_wheels,
+        )
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// VPX rolls a reel a step every 50 ms unless the table says otherwise.
+const DEFAULT_UPDATE_INTERVAL_MS: u64 = 50;
+
+#[derive(Component, Debug)]
+pub struct Reel {
+    /// The number shown once the wheels stop.
+    value: u64,
+    /// The position of each wheel in motor steps, the leftmost wheel first.
+    positions: Vec<u32>,
+    /// The number of images on a wheel, usually 10 for the digits 0 to 9.
+    digits: u32,
+    motor_steps: u32,
+    step_timer: Timer,
+    sound: String,
+    /// Images per row when the reel image is a grid, 1 for a vertical strip.
+    grid_columns: u32,
+    /// Index of the player whose score is shown, `None` once a script controls the reel.
+    player: Option<usize>,
+}
+
+impl Reel {
+    /// Sets the value, keeping only the digits that fit on the wheels.
+    pub fn set_value(&mut self, value: u64) {
+        self.value = value % self.modulus();
+    }
+
+    /// Adds to the value, wrapping around like the wheels do.
+    pub fn add_value(&mut self, delta: i64) {
+        let modulus = i128::from(self.modulus());
+        self.value = (i128::from(self.value) + i128::from(delta)).rem_euclid(modulus) as u64;
+    }
+
+    /// Hands the reel over to the table script, it stops following the player score.
+    pub fn take_control(&mut self) {
+        self.player = None;
+    }
+
+    fn modulus(&self) -> u64 {
+        u64::from(self.digits).saturating_pow(self.positions.len() as u32)
+    }
+
+    /// The position in motor steps where a wheel stops for the current value.
+    fn target(&self, wheel: usize) -> u32 {
+        let power = (self.positions.len() - 1 - wheel) as u32;
+        let digit =
+            self.value / u64::from(self.digits).saturating_pow(power) % u64::from(self.digits);
+        digit as u32 * self.motor_steps
+    }
+
+    fn rolling(&self) -> bool {
+        (0..self.positions.len()).any(|wheel| self.positions[wheel] != self.target(wheel))
+    }
+
+    /// The part of the reel image that shows a wheel. On a strip the wheel rolls smoothly into the
+    /// next image, except from the last image back to the first.
+    fn wheel_rect(&self, image_size: Vec2, position: u32) -> Rect {
+        let (digit, step) = (position / self.motor_steps, position % self.motor_steps);
+        let columns = self.grid_columns.max(1);
+        let rows = self.digits.div_ceil(columns);
+        let cell = image_size / Vec2::new(columns as f32, rows as f32);
+        let mut min = Vec2::new((digit % columns) as f32, (digit / columns) as f32) * cell;
+        if columns == 1 && digit + 1 < self.digits {
+            min.y += cell.y * step as f32 / self.motor_steps as f32;
+        }
+        Rect::from_corners(min, min + cell)
+    }
+}
+
+/// A wheel of a reel, by index from the left.
+#[derive(Component, Debug)]
+struct ReelWheel(usize);
+
+/// Spawns a reel on the backglass, following the score of a player until a script takes over.
+pub(super) fn spawn_reel(
+    parent: &mut RelatedSpawnerCommands<ChildOf>,
+    vpx_asset: &VpxAsset,
+    reel: &vpx::gameitem::reel::Reel,
+    player: usize,
+) {
+    let count = (reel.reel_count as usize).max(1);
+    let spacing = reel.reel_spacing;
+    // the reel box as laid out by VPX, the wheels with spacing around them
+    let box_width = count as f32 * (reel.width + spacing) + spacing;
+    let box_height = reel.height + 2.0 * spacing;
+    let image = vpx_asset.image(&reel.image).cloned();
+    if image.is_none() && !reel.image.is_empty() {
+        warn!("Reel image '{}' not found for '{}'", reel.image, reel.name);
+    }
+    let motor_steps = (reel.motor_steps as u32).max(1);
+    let update_interval_ms = match reel.update_interval as u64 {
+        0 => DEFAULT_UPDATE_INTERVAL_MS,
+        interval => interval,
+    };
+    let background = if reel.is_transparent {
+        Color::NONE
+    } else {
+        Color::srgb_u8(reel.back_color.r, reel.back_color.g, reel.back_color.b)
+    };
+    parent
+        .spawn((
+            Reel {
+                value: 0,
+                positions: vec![0; count],
+                digits: (reel.digit_range as u32).saturating_add(1),
+                motor_steps,
+                step_timer: Timer::from_seconds(
+                    update_interval_ms as f32 / 1000.0,
+                    TimerMode::Repeating,
+                ),
+                sound: reel.sound.clone(),
+                grid_columns: if reel.use_image_grid {
+                    reel.images_per_grid_row as u32
+                } else {
+                    1
+                },
+                player: Some(player),
+            },
+            GameItem::new(&reel.name, ItemKind::Reel),
+            Name::from(format!("Reel {}", reel.name)),
+            Node {
+                position_type: PositionType::Absolute,
+                left: percent(reel.ver1.x / BACKGLASS_WIDTH * 100.0),
+                top: percent(reel.ver1.y / BACKGLASS_HEIGHT * 100.0),
+                width: percent(box_width / BACKGLASS_WIDTH * 100.0),
+                height: percent(box_height / BACKGLASS_HEIGHT * 100.0),
+                ..default()
+            },
+            BackgroundColor(background),
+            if reel.is_visible {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            },
+        ))
+        .with_children(|parent| {
+            for wheel in 0..count {
+                let node = Node {
+                    position_type: PositionType::Absolute,
+                    left: percent(
+                        (spacing + wheel as f32 * (reel.width + spacing)) / box_width * 100.0,
+                    ),
+                    top: percent(spacing / box_height * 100.0),
+                    width: percent(reel.width / box_width * 100.0),
+                    height: percent(reel.height / box_height * 100.0),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                };
+                match &image {
+                    Some(image) => parent.spawn((
+                        Name::new("Wheel"),
+                        ReelWheel(wheel),
+                        node,
+                        ImageNode::new(image.clone()),
+                    )),
+                    // without an image the digits are shown as text
+                    None => parent.spawn((
+                        Name::new("Wheel"),
+                        ReelWheel(wheel),
+                        node,
+                        widget::label("0"),
+                    )),
+                };
+            }
+        });
+}
+
+fn follow_scores(game: Res<GameState>, mut reel_query: Query<&mut Reel>) {
+    for mut reel in &mut reel_query {
+        let Some(score) = reel
+            .player
+            .and_then(|player| game.players.get(player))
+            .map(|player| player.score % reel.modulus())
+        else {
+            continue;
+        };
+        if reel.value != score {
+            reel.set_value(score);
+        }
+    }
+}
+
+/// Moves rolling wheels a motor step forward, playing the reel sound for every digit passed.
+fn roll_reels(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut reel_query: Query<&mut Reel>,
+    table_assets: Res<TableAssets>,
+    assets_vpx: Res<Assets<VpxAsset>>,
+) {
+    let Some(vpx_asset) = assets_vpx.get(&table_assets.vpx) else {
+        return;
+    };
+    for mut reel in &mut reel_query {
+        if !reel.rolling() {
+            reel.step_timer.reset();
+            continue;
+        }
+        if !reel.step_timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+        let targets: Vec<u32> = (0..reel.positions.len())
+            .map(|wheel| reel.target(wheel))
+            .collect();
+        let steps_per_turn = reel.digits * reel.motor_steps;
+        let motor_steps = reel.motor_steps;
+        let mut digits_passed = 0;
+        for (position, target) in reel.positions.iter_mut().zip(targets) {
+            if *position != target {
+                *position = (*position + 1) % steps_per_turn;
+                if *position % motor_steps == 0 {
+                    digits_passed += 1;
+                }
+            }
+        }
+        if let Some(sound) = vpx_asset.sound(&reel.sound) {
+            for _ in 0..digits_passed {
+                commands.spawn(sound_effect(sound.clone()));
+            }
+        }
+    }
+}
+
+fn show_reel_wheels(
+    reel_query: Query<(&Reel, &Children)>,
+    mut wheel_query: Query<(&ReelWheel, Option<&mut ImageNode>, Option<&mut Text>)>,
+    images: Res<Assets<Image>>,
+) {
+    for (reel, children) in &reel_query {
+        let mut wheels = wheel_query.iter_many_mut(children);
+        while let Some((wheel, image_node, text)) = wheels.fetch_next() {
+            let position = reel.positions[wheel.0];
+            if let Some(mut text) = text {
+                text.set_if_neq(Text((position / reel.motor_steps).to_string()));
+            }
+            if let Some(mut image_node) = image_node
+                && let Some(image) = images.get(&image_node.image)
+            {
+                let rect = reel.wheel_rect(image.size_f32(), position);
+                if image_node.rect != Some(rect) {
+                    image_node.rect = Some(rect);
+                }
+            }
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use crate::pinball::events::{GameItem, ItemKind};
+use crate::pinball::height::{ItemHeight, item_z};
 use crate::vpx::VpxAsset;
 use avian2d::prelude::{CollisionEventsEnabled, Friction, Restitution, RigidBody};
 use bevy::asset::Assets;
@@ -7,6 +9,7 @@ use bevy::ecs::relationship::RelatedSpawnerCommands;
 use bevy::mesh::{Mesh, Mesh2d};
 use bevy::prelude::*;
 use vpin::vpx;
+use vpin::vpx::vpu_to_m;
 
 const RUBER_COLOR: Srgba = css::WHITE;
 
@@ -16,9 +19,14 @@ pub struct Rubber {
     pub name: String,
 }
 
+/// The material all rubbers share.
+pub(super) fn rubber_material(materials: &mut Assets<ColorMaterial>) -> Handle<ColorMaterial> {
+    materials.add(Color::from(RUBER_COLOR))
+}
+
 pub(super) fn spawn_rubber(
     meshes: &mut ResMut<Assets<Mesh>>,
-    materials: &mut ResMut<Assets<ColorMaterial>>,
+    material: &Handle<ColorMaterial>,
     vpx_to_bevy_transform: Transform,
     parent: &mut RelatedSpawnerCommands<ChildOf>,
     rubber: &vpx::gameitem::rubber::Rubber,
@@ -43,22 +51,28 @@ pub(super) fn spawn_rubber(
 
     let mesh = meshes.get(mesh_handle).unwrap();
     let collider = crate::pinball::wall::mesh_collider(mesh);
+    let top = vpu_to_m(rubber.height + rubber.thickness as f32 / 2.0);
 
     parent.spawn((
         Rubber {
             name: rubber.name.clone(),
         },
+        GameItem::new(&rubber.name, ItemKind::Rubber),
         Name::from(format!("Rubber {}", rubber.name)),
         Transform::from_xyz(
             vpx_to_bevy_transform.translation.x,
             vpx_to_bevy_transform.translation.y,
-            0.0, // height is set in the mesh, not sure we want to keep that
+            item_z(top),
         ),
         Mesh2d(mesh_handle.clone()),
-        MeshMaterial2d(materials.add(Color::from(RUBER_COLOR))),
+        MeshMaterial2d(material.clone()),
         // physics
         CollisionEventsEnabled,
         RigidBody::Static,
+        ItemHeight {
+            bottom: vpu_to_m(rubber.height - rubber.thickness as f32 / 2.0),
+            top,
+        },
         collider,
         Restitution::from(rubber.elasticity),
         Friction::from(rubber.friction),
@@ -0,0 +1,415 @@
+//! The game-item API that table scripts use to change the game world.
+//!
+//! Script runtimes don't access the ECS directly. Instead they send [`ScriptCommand`]s which are
+//! applied here, addressing table items by their VPX name like the VPX scripting API does.
+
+use crate::audio::{sound_effect, spatial_sound_effect};
+use crate::pinball::awards::award_special;
+use crate::pinball::backglass::B2sData;
+use crate::pinball::ball::{BALL_RADIUS_M, Ball, ball};
+use crate::pinball::dmd::{Dmd, DmdScene, ShadeDepth, TextEffect};
+use crate::pinball::events::{GameItem, GameItemTimer};
+use crate::pinball::game::GameState;
+use crate::pinball::height::BallHeight;
+use crate::pinball::hud::StatusMessage;
+use crate::pinball::kicker::Kicker;
+use crate::pinball::light::{Light, LightState};
+use crate::pinball::reel::Reel;
+use crate::pinball::table::TableAssets;
+use crate::pinball::wall::Wall;
+use crate::screens::Screen;
+use crate::vpx::VpxAsset;
+use crate::{AppSystems, PausableSystems};
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::time::Duration;
+use vpin::vpx::vpu_to_m;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_message::<ScriptCommand>();
+    app.add_systems(
+        Update,
+        apply_script_commands
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// VPX kick speeds are in VP units per 10 milliseconds.
+const KICK_SPEED_TO_M_PER_S: f32 = 100.0;
+
+/// A request from a table script to change the game world.
+#[derive(Message, Debug, Clone, PartialEq)]
+pub enum ScriptCommand {
+    /// Play a sound, at the location of a table item if one is given.
+    PlaySound {
+        sound: String,
+        item: Option<String>,
+    },
+    /// Create a new ball that is held by a kicker until it is kicked.
+    CreateBall {
+        kicker: String,
+    },
+    /// Destroy all balls in a kicker.
+    DestroyBall {
+        kicker: String,
+    },
+    /// Kick all balls out of a kicker.
+    /// The angle is in degrees with 0 pointing up and 90 pointing right, the speed in VPX units.
+    /// The inclination is in degrees above the playfield, a kick with an inclination lifts the ball.
+    Kick {
+        kicker: String,
+        angle: f32,
+        speed: f32,
+        inclination: f32,
+    },
+    SetLightState {
+        light: String,
+        state: LightState,
+    },
+    /// Drop a wall below the playfield, removing it visually and physically.
+    SetWallDropped {
+        wall: String,
+        dropped: bool,
+    },
+    /// Add points to the score of the current player.
+    AddScore {
+        points: i64,
+    },
+    /// Award a special to the current player.
+    AwardSpecial,
+    /// Change the timer of an item, see the VPX `TimerEnabled` and `TimerInterval` properties.
+    SetTimer {
+        item: String,
+        enabled: Option<bool>,
+        interval_ms: Option<u64>,
+    },
+    /// Queue text on the dot-matrix display, one line per line of the text.
+    DmdText {
+        text: String,
+        effect: TextEffect,
+        duration_ms: u64,
+    },
+    /// Queue an animation of table images on the dot-matrix display.
+    DmdAnimation {
+        images: Vec<String>,
+        frame_ms: u64,
+    },
+    /// Stop what the dot-matrix display shows and drop everything queued.
+    DmdClear,
+    /// Set the number of brightness levels of the dot-matrix display.
+    DmdShadeDepth(ShadeDepth),
+    /// Roll a score reel to a value, see the VPX `SetValue` and `ResetToZero` methods.
+    SetReelValue {
+        reel: String,
+        value: u64,
+    },
+    /// Roll a score reel forward or back by some amount, see the VPX `AddValue` method.
+    AddReelValue {
+        reel: String,
+        value: i64,
+    },
+    /// Set the data for a directb2s id, which switches the backglass bulbs with that id.
+    SetB2sData {
+        id: i32,
+        value: i32,
+    },
+    /// Show a status message on the HUD, e.g. when the script tilts the table or saves a ball.
+    ShowStatus(StatusMessage),
+}
+
+/// Marks a ball that is held in place by a kicker.
+#[derive(Component, Debug)]
+pub struct HeldByKicker(pub Entity);
+
+fn apply_script_commands(
+    mut script_commands: MessageReader<ScriptCommand>,
+    mut commands: Commands,
+    kicker_query: Query<(Entity, &Kicker, &Transform)>,
+    ball_query: Query<(Entity, &Transform, Option<&HeldByKicker>), With<Ball>>,
+    mut light_query: Query<(&Light, &mut LightState)>,
+    wall_query: Query<(Entity, &Wall)>,
+    item_query: Query<(Entity, &GameItem)>,
+    mut timer_query: Query<&mut GameItemTimer>,
+    mut reel_query: Query<&mut Reel>,
+    mut game: ResMut<GameState>,
+    mut dmd: ResMut<Dmd>,
+    mut b2s_data: ResMut<B2sData>,
+    table_assets: Res<TableAssets>,
+    assets_vpx: Res<Assets<VpxAsset>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let Some(vpx_asset) = assets_vpx.get(&table_assets.vpx) else {
+        return;
+    };
+    // balls created this frame are not visible to the ball query yet
+    let mut created_balls: HashMap<Entity, Vec<Entity>> = HashMap::new();
+
+    for command in script_commands.read() {
+        match command {
+            ScriptCommand::PlaySound { sound, item } => {
+                let Some(handle) = vpx_asset.sound(sound) else {
+                    warn!("Script sound '{}' not found", sound);
+                    continue;
+                };
+                let item_entity = item.as_ref().and_then(|item| {
+                    item_query
+                        .iter()
+                        .find(|(_, game_item)| game_item.name.eq_ignore_ascii_case(item))
+                        .map(|(entity, _)| entity)
+                });
+                match item_entity {
+                    Some(entity) => {
+                        commands
+                            .entity(entity)
+                            .with_child(spatial_sound_effect(handle.clone()));
+                    }
+                    None => {
+                        commands.spawn(sound_effect(handle.clone()));
+                    }
+                }
+            }
+            ScriptCommand::CreateBall { kicker } => {
+                // the table waits for the next game, like the default drain logic
+                if game.is_over() {
+                    debug!("Not creating a ball in {} after the game is over", kicker);
+                    continue;
+                }
+                let Some((kicker_entity, _, kicker_transform)) = find_kicker(&kicker_query, kicker)
+                else {
+                    continue;
+                };
+                let ball_entity = commands
+                    .spawn(ball(
+                        0,
+                        &table_assets,
+                        &mut meshes,
+                        &mut materials,
+                        &assets_vpx,
+                        kicker_transform.translation.truncate(),
+                    ))
+                    .insert((
+                        RigidBody::Kinematic,
+                        HeldByKicker(kicker_entity),
+                        DespawnOnExit(Screen::Gameplay),
+                    ))
+                    .id();
+                created_balls
+                    .entry(kicker_entity)
+                    .or_default()
+                    .push(ball_entity);
+            }
+            ScriptCommand::DestroyBall { kicker } => {
+                let Some((kicker_entity, kicker_component, kicker_transform)) =
+                    find_kicker(&kicker_query, kicker)
+                else {
+                    continue;
+                };
+                let mut balls = balls_in_kicker(
+                    &ball_query,
+                    kicker_entity,
+                    kicker_component,
+                    kicker_transform,
+                );
+                balls.extend(created_balls.remove(&kicker_entity).unwrap_or_default());
+                for ball_entity in balls {
+                    commands.entity(ball_entity).despawn();
+                }
+            }
+            ScriptCommand::Kick {
+                kicker,
+                angle,
+                speed,
+                inclination,
+            } => {
+                let Some((kicker_entity, kicker_component, kicker_transform)) =
+                    find_kicker(&kicker_query, kicker)
+                else {
+                    continue;
+                };
+                let mut balls = balls_in_kicker(
+                    &ball_query,
+                    kicker_entity,
+                    kicker_component,
+                    kicker_transform,
+                );
+                balls.extend(created_balls.remove(&kicker_entity).unwrap_or_default());
+                let direction = Vec2::new(angle.to_radians().sin(), angle.to_radians().cos());
+                let speed = vpu_to_m(*speed) * KICK_SPEED_TO_M_PER_S;
+                let (vertical, horizontal) = inclination.to_radians().sin_cos();
+                for ball_entity in balls {
+                    commands
+                        .entity(ball_entity)
+                        .remove::<HeldByKicker>()
+                        .insert((
+                            RigidBody::Dynamic,
+                            LinearVelocity(direction * speed * horizontal),
+                            BallHeight {
+                                height: 0.0,
+                                vertical_velocity: speed * vertical,
+                            },
+                        ));
+                }
+            }
+            ScriptCommand::SetLightState { light, state } => {
+                match light_query
+                    .iter_mut()
+                    .find(|(l, _)| l.name.eq_ignore_ascii_case(light))
+                {
+                    Some((_, mut light_state)) => *light_state = *state,
+                    None => warn!("Script refers to unknown light '{}'", light),
+                }
+            }
+            ScriptCommand::SetWallDropped { wall, dropped } => {
+                let Some((wall_entity, _)) = wall_query
+                    .iter()
+                    .find(|(_, w)| w.name.eq_ignore_ascii_case(wall))
+                else {
+                    warn!("Script refers to unknown wall '{}'", wall);
+                    continue;
+                };
+                if *dropped {
+                    commands
+                        .entity(wall_entity)
+                        .insert((ColliderDisabled, Visibility::Hidden));
+                } else {
+                    commands
+                        .entity(wall_entity)
+                        .remove::<ColliderDisabled>()
+                        .insert(Visibility::Inherited);
+                }
+            }
+            ScriptCommand::AddScore { points } => game.add_score(*points),
+            ScriptCommand::AwardSpecial => award_special(&mut commands, &game),
+            ScriptCommand::SetTimer {
+                item,
+                enabled,
+                interval_ms,
+            } => {
+                let Some((item_entity, _)) = item_query
+                    .iter()
+                    .find(|(_, game_item)| game_item.name.eq_ignore_ascii_case(item))
+                else {
+                    warn!("Script refers to unknown item '{}'", item);
+                    continue;
+                };
+                let Ok(mut timer) = timer_query.get_mut(item_entity) else {
+                    // VPX items without a configured timer default to 100 ms
+                    commands.entity(item_entity).insert(GameItemTimer::new(
+                        enabled.unwrap_or(false),
+                        interval_ms.unwrap_or(100),
+                    ));
+                    continue;
+                };
+                if let Some(interval_ms) = interval_ms {
+                    timer
+                        .timer
+                        .set_duration(Duration::from_millis((*interval_ms).max(1)));
+                    timer.timer.reset();
+                }
+                if let Some(enabled) = enabled {
+                    // enabling a timer restarts it
+                    if *enabled && !timer.enabled {
+                        timer.timer.reset();
+                    }
+                    timer.enabled = *enabled;
+                }
+            }
+            ScriptCommand::DmdText {
+                text,
+                effect,
+                duration_ms,
+            } => dmd.push(DmdScene::Text {
+                lines: text.lines().map(str::to_string).collect(),
+                effect: *effect,
+                duration: Duration::from_millis(*duration_ms),
+            }),
+            ScriptCommand::DmdAnimation { images, frame_ms } => {
+                let frames = images
+                    .iter()
+                    .filter_map(|image| {
+                        let handle = vpx_asset.image(image).cloned();
+                        if handle.is_none() {
+                            warn!("Script refers to unknown image '{}'", image);
+                        }
+                        handle
+                    })
+                    .collect();
+                dmd.push(DmdScene::Animation {
+                    frames,
+                    frame_duration: Duration::from_millis(*frame_ms),
+                });
+            }
+            ScriptCommand::DmdClear => dmd.clear(),
+            ScriptCommand::DmdShadeDepth(depth) => dmd.depth = *depth,
+            ScriptCommand::SetReelValue { reel, value } => {
+                if let Some(mut reel) = find_reel(&item_query, &mut reel_query, reel) {
+                    reel.take_control();
+                    reel.set_value(*value);
+                }
+            }
+            ScriptCommand::AddReelValue { reel, value } => {
+                if let Some(mut reel) = find_reel(&item_query, &mut reel_query, reel) {
+                    reel.take_control();
+                    reel.add_value(*value);
+                }
+            }
+            ScriptCommand::SetB2sData { id, value } => {
+                b2s_data.0.insert(*id, *value);
+            }
+            ScriptCommand::ShowStatus(message) => commands.trigger(*message),
+        }
+    }
+}
+
+fn find_reel<'a>(
+    item_query: &Query<(Entity, &GameItem)>,
+    reel_query: &'a mut Query<&mut Reel>,
+    name: &str,
+) -> Option<Mut<'a, Reel>> {
+    let reel = item_query
+        .iter()
+        .find(|(_, game_item)| game_item.name.eq_ignore_ascii_case(name))
+        .and_then(|(entity, _)| reel_query.get_mut(entity).ok());
+    if reel.is_none() {
+        warn!("Script refers to unknown reel '{}'", name);
+    }
+    reel
+}
+
+fn find_kicker<'a>(
+    kicker_query: &'a Query<(Entity, &Kicker, &Transform)>,
+    name: &str,
+) -> Option<(Entity, &'a Kicker, &'a Transform)> {
+    let kicker = kicker_query
+        .iter()
+        .find(|(_, kicker, _)| kicker.name.eq_ignore_ascii_case(name));
+    if kicker.is_none() {
+        warn!("Script refers to unknown kicker '{}'", name);
+    }
+    kicker
+}
+
+/// The balls held by a kicker or overlapping with it.
+fn balls_in_kicker(
+    ball_query: &Query<(Entity, &Transform, Option<&HeldByKicker>), With<Ball>>,
+    kicker_entity: Entity,
+    kicker: &Kicker,
+    kicker_transform: &Transform,
+) -> Vec<Entity> {
+    ball_query
+        .iter()
+        .filter(|(_, ball_transform, held)| {
+            held.is_some_and(|held| held.0 == kicker_entity)
+                || ball_transform
+                    .translation
+                    .truncate()
+                    .distance(kicker_transform.translation.truncate())
+                    <= kicker.radius + BALL_RADIUS_M
+        })
+        .map(|(ball_entity, _, _)| ball_entity)
+        .collect()
+}
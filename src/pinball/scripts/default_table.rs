@@ -0,0 +1,236 @@
+//! Built-in table logic that every table gets unless its script takes over.
+//!
+//! Most tables drain into a kicker named `Drain` and release a new ball from a kicker named
+//! `BallRelease`. A [`TableScript`](super::registry::TableScript) can change the names, delay and
+//! sounds through [`DrainSettings`].
+//!
+//! During a game a new ball is released when the next ball starts, see [`BallStarted`], also for
+//! tables whose script drains balls itself and left none on the table. Before the first game
+//! every drained ball is replaced right away, after a game is over the table waits for the next.
+//!
+//! The wall that keeps the ball centered in the plunger lane is removed for the tables whose
+//! script names it, see [`TableScript::plunger_wall`](super::registry::TableScript::plunger_wall).
+
+use crate::audio::spatial_sound_effect;
+use crate::pinball::ball::{Ball, ball};
+use crate::pinball::events::{ItemHit, ItemKind};
+use crate::pinball::game::{BallStarted, GameState};
+use crate::pinball::kicker::Kicker;
+use crate::pinball::level::spawn_level;
+use crate::pinball::scripts::registry::{ActiveTableScript, TableScriptRegistry};
+use crate::pinball::table::TableAssets;
+use crate::pinball::wall::Wall;
+use crate::screens::Screen;
+use crate::vpx::VpxAsset;
+use crate::{AppSystems, PausableSystems};
+use bevy::prelude::*;
+use rand::seq::IndexedRandom;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<DrainSettings>();
+    app.init_resource::<PendingBallReleases>();
+    app.add_observer(drain_ball);
+    app.add_observer(release_ball_for_turn);
+    app.add_systems(
+        Update,
+        release_balls
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        remove_plunger_wall.after(spawn_level),
+    );
+    app.add_systems(OnExit(Screen::Gameplay), clear_pending_releases);
+}
+
+/// How the default logic drains balls and releases new ones.
+#[derive(Resource, Debug, Clone)]
+pub struct DrainSettings {
+    /// Disable this when the table script handles draining itself.
+    pub enabled: bool,
+    pub drain_kicker: String,
+    pub release_kicker: String,
+    /// Time between a ball draining and the next ball being released.
+    pub release_delay_secs: f32,
+    pub drain_sound: TableSound,
+    pub release_sound: TableSound,
+}
+
+impl Default for DrainSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            drain_kicker: "Drain".to_string(),
+            release_kicker: "BallRelease".to_string(),
+            release_delay_secs: 0.5,
+            drain_sound: TableSound::FirstOf(vec!["drain".to_string(), "fx_drain".to_string()]),
+            release_sound: TableSound::FirstOf(vec![
+                "ballrelease".to_string(),
+                "fx_ballrel".to_string(),
+            ]),
+        }
+    }
+}
+
+fn remove_plunger_wall(
+    mut commands: Commands,
+    registry: Res<TableScriptRegistry>,
+    active: Res<ActiveTableScript>,
+    wall_query: Query<(Entity, &Wall)>,
+) {
+    // TODO the plunger lane wall keeps the ball in the lane and allows the plunger to pass
+    //   through. However we don't know how to allow that behavior yet so we skip it for now
+    //   https://github.com/avianphysics/avian/blob/main/crates/avian2d/examples/one_way_platform_2d.rs
+    //   Maybe they should be on different collision layers?
+    //   The best option would be replacing the single wall with a left and right part
+    //   that leaves a gap for the plunger in the center.
+    let Some(name) = active.0.and_then(|script| registry.plunger_wall(script)) else {
+        return;
+    };
+    if let Some((plunger_wall_entity, _wall)) = wall_query.iter().find(|(_, k)| k.name == name) {
+        commands.entity(plunger_wall_entity).despawn();
+    } else {
+        warn!(
+            "Plunger centering wall {} not found, could not remove it",
+            name
+        );
+    }
+}
+
+/// Picks one of the sounds the table has.
+#[derive(Debug, Clone)]
+pub enum TableSound {
+    /// The first sound that exists in the table.
+    FirstOf(Vec<String>),
+    /// A random sound out of those that exist in the table.
+    OneOf(Vec<String>),
+}
+
+impl TableSound {
+    fn pick(&self, vpx_asset: &VpxAsset) -> Option<Handle<AudioSource>> {
+        match self {
+            TableSound::FirstOf(names) => names.iter().find_map(|name| vpx_asset.sound(name)),
+            TableSound::OneOf(names) => {
+                let available: Vec<_> = names
+                    .iter()
+                    .filter_map(|name| vpx_asset.sound(name))
+                    .collect();
+                available.choose(&mut rand::rng()).copied()
+            }
+        }
+        .cloned()
+    }
+}
+
+/// Timers for balls that drained and still have to be released.
+#[derive(Resource, Debug, Default)]
+struct PendingBallReleases(Vec<Timer>);
+
+fn drain_ball(
+    hit: On<ItemHit>,
+    mut commands: Commands,
+    settings: Res<DrainSettings>,
+    mut pending: ResMut<PendingBallReleases>,
+    game: Res<GameState>,
+    table_assets: Res<TableAssets>,
+    assets_vpx: Res<Assets<VpxAsset>>,
+) {
+    if !settings.enabled
+        || hit.kind != ItemKind::Kicker
+        || !hit.item_name.eq_ignore_ascii_case(&settings.drain_kicker)
+    {
+        return;
+    }
+    let Some(vpx_asset) = assets_vpx.get(&table_assets.vpx) else {
+        return;
+    };
+    info!("Ball {} drained!", hit.ball_id);
+    if let Some(sound) = settings.drain_sound.pick(vpx_asset) {
+        commands
+            .entity(hit.entity)
+            .with_child(spatial_sound_effect(sound));
+    }
+    commands.entity(hit.ball).despawn();
+    if !game.in_progress() && !game.is_over() {
+        pending.0.push(Timer::from_seconds(
+            settings.release_delay_secs,
+            TimerMode::Once,
+        ));
+    }
+}
+
+/// Releases a ball for the next turn unless one is already on its way.
+fn release_ball_for_turn(
+    _started: On<BallStarted>,
+    settings: Res<DrainSettings>,
+    mut pending: ResMut<PendingBallReleases>,
+    ball_query: Query<(), With<Ball>>,
+) {
+    if ball_query.is_empty() && pending.0.is_empty() {
+        pending.0.push(Timer::from_seconds(
+            settings.release_delay_secs,
+            TimerMode::Once,
+        ));
+    }
+}
+
+fn release_balls(
+    time: Res<Time>,
+    mut commands: Commands,
+    settings: Res<DrainSettings>,
+    mut pending: ResMut<PendingBallReleases>,
+    kicker_query: Query<(Entity, &Kicker, &Transform)>,
+    table_assets: Res<TableAssets>,
+    assets_vpx: Res<Assets<VpxAsset>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let Some(vpx_asset) = assets_vpx.get(&table_assets.vpx) else {
+        return;
+    };
+    let mut due = 0;
+    pending.0.retain_mut(|timer| {
+        timer.tick(time.delta());
+        if timer.is_finished() {
+            due += 1;
+        }
+        !timer.is_finished()
+    });
+    if due == 0 {
+        return;
+    }
+    let Some((release_entity, _, release_transform)) = kicker_query
+        .iter()
+        .find(|(_, kicker, _)| kicker.name.eq_ignore_ascii_case(&settings.release_kicker))
+    else {
+        warn!(
+            "Ball release kicker {} not found, no new ball released",
+            settings.release_kicker
+        );
+        return;
+    };
+    for _ in 0..due {
+        if let Some(sound) = settings.release_sound.pick(vpx_asset) {
+            commands
+                .entity(release_entity)
+                .with_child(spatial_sound_effect(sound));
+        }
+        commands.spawn((
+            ball(
+                0,
+                &table_assets,
+                &mut meshes,
+                &mut materials,
+                &assets_vpx,
+                release_transform.translation.truncate(),
+            ),
+            DespawnOnExit(Screen::Gameplay),
+        ));
+    }
+}
+
+fn clear_pending_releases(mut pending: ResMut<PendingBallReleases>) {
+    pending.0.clear();
+}
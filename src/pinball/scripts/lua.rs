@@ -0,0 +1,610 @@
+//! Runs a Lua script that sits next to the table, e.g. `exampleTable.lua` for `exampleTable.vpx`.
+//!
+//! Table items are exposed as globals by their VPX name, with methods modelled after the VPX
+//! scripting API. Callbacks follow the VBScript naming convention, so a VBScript
+//! `Sub Drain_Hit()` becomes `function Drain_Hit()`.
+//!
+//! ```lua
+//! function Drain_Hit()
+//!     Drain:PlaySound("drain")
+//!     Drain:DestroyBall()
+//!     BallRelease:CreateBall()
+//!     BallRelease:Kick(90, 7)
+//! end
+//!
+//! function Bumper1_Hit()
+//!     AddScore(100)
+//!     DmdText("BUMPER\n100", "blink", 1000)
+//! end
+//! ```
+
+use crate::pinball::dmd::{
+    DEFAULT_FRAME_DURATION_MS, DEFAULT_TEXT_DURATION_MS, ShadeDepth, TextEffect,
+};
+use crate::pinball::events::{ItemHit, ItemSpin, ItemTimer, ItemUnhit};
+use crate::pinball::hud::StatusMessage;
+use crate::pinball::light::{Light, LightState};
+use crate::pinball::scripts::api::ScriptCommand;
+use crate::pinball::scripts::default_table::DrainSettings;
+use crate::pinball::scripts::registry::{
+    ActiveTableScript, RegisterTableScript, TableKey, TableScript, table_script_active,
+};
+use crate::pinball::table::TableAssets;
+use crate::screens::Screen;
+use crate::vpx::VpxAsset;
+use crate::{AppSystems, PausableSystems};
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::prelude::*;
+use mlua::{Function, Lua, LuaOptions, StdLib, UserData, UserDataFields, UserDataMethods};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use vpin::vpx::gameitem::GameItemEnum;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_asset::<LuaScript>();
+    app.register_asset_loader(LuaScriptLoader);
+    app.insert_non_send_resource(LuaRuntime::default());
+    app.init_resource::<PendingLuaCallbacks>();
+    app.add_observer(queue_hit_callback);
+    app.add_observer(queue_unhit_callback);
+    app.add_observer(queue_spin_callback);
+    app.add_observer(queue_timer_callback);
+    app.register_table_script(LuaTableScript);
+}
+
+const LUA_SCRIPT: &str = "lua";
+
+/// Runs the Lua sidecar of a table, which takes precedence over any other script.
+/// The default drain logic keeps running unless the script handles the drain kicker itself.
+struct LuaTableScript;
+
+impl TableScript for LuaTableScript {
+    fn name(&self) -> &'static str {
+        LUA_SCRIPT
+    }
+
+    fn keys(&self) -> Vec<TableKey> {
+        vec![TableKey::Sidecar("lua")]
+    }
+
+    fn handles_item_events(&self) -> bool {
+        true
+    }
+
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            load_lua_script
+                .in_set(AppSystems::Update)
+                .run_if(resource_changed::<ActiveTableScript>),
+        );
+        app.add_systems(
+            Update,
+            (start_lua_runtime, dispatch_lua_callbacks)
+                .chain()
+                .in_set(AppSystems::Update)
+                .in_set(PausableSystems)
+                .run_if(in_state(Screen::Gameplay))
+                .run_if(table_script_active(LUA_SCRIPT)),
+        );
+        app.add_systems(OnExit(Screen::Gameplay), stop_lua_runtime);
+    }
+}
+
+/// The Lua script for a table has the same path with a `lua` extension.
+fn sidecar_path(table_path: &Path) -> PathBuf {
+    table_path.with_extension("lua")
+}
+
+/// The source code of a Lua table script.
+#[derive(Asset, TypePath, Debug)]
+pub struct LuaScript {
+    source: String,
+}
+
+struct LuaScriptLoader;
+
+impl AssetLoader for LuaScriptLoader {
+    type Asset = LuaScript;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let source = String::from_utf8(bytes)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        Ok(LuaScript { source })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["lua"]
+    }
+}
+
+#[derive(Resource)]
+struct LuaScriptHandle(Handle<LuaScript>);
+
+/// Loads the sidecar when the Lua script becomes active and unloads it when it becomes inactive.
+fn load_lua_script(
+    mut commands: Commands,
+    active: Res<ActiveTableScript>,
+    table_assets: Res<TableAssets>,
+    asset_server: Res<AssetServer>,
+    mut runtime: NonSendMut<LuaRuntime>,
+) {
+    *runtime = LuaRuntime::default();
+    if active.0 == Some(LUA_SCRIPT) {
+        let handle = asset_server.load(sidecar_path(Path::new(&table_assets.file_name)));
+        commands.insert_resource(LuaScriptHandle(handle));
+    } else {
+        commands.remove_resource::<LuaScriptHandle>();
+    }
+}
+
+/// State shared between the Lua globals and the Bevy systems.
+#[derive(Default)]
+struct SharedState {
+    /// Commands issued by the script, waiting to be sent to the game-item API.
+    commands: Vec<ScriptCommand>,
+    /// Mirror of the light states so the script can read them.
+    light_states: HashMap<String, LightState>,
+}
+
+type Shared = Rc<RefCell<SharedState>>;
+
+/// The Lua interpreter. Lua is not thread-safe, so this is a non-send resource.
+#[derive(Default)]
+struct LuaRuntime {
+    lua: Option<Lua>,
+    shared: Shared,
+}
+
+impl LuaRuntime {
+    /// Calls a global Lua function if the script defines it, returns whether it was defined.
+    fn call(&self, function: &str) -> bool {
+        let Some(lua) = &self.lua else {
+            return false;
+        };
+        match lua.globals().get::<Option<Function>>(function) {
+            Ok(Some(f)) => {
+                if let Err(e) = f.call::<()>(()) {
+                    error!("Lua error in {}: {}", function, e);
+                }
+                true
+            }
+            Ok(None) => false,
+            Err(e) => {
+                error!("Lua global {} is not a function: {}", function, e);
+                false
+            }
+        }
+    }
+}
+
+/// A table item as seen from Lua.
+struct LuaItem {
+    name: String,
+    shared: Shared,
+}
+
+impl LuaItem {
+    fn push(&self, command: ScriptCommand) {
+        self.shared.borrow_mut().commands.push(command);
+    }
+}
+
+impl UserData for LuaItem {
+    fn add_fields<F: UserDataFields<Self>>(fields: &mut F) {
+        fields.add_field_method_get("Name", |_, this| Ok(this.name.clone()));
+        fields.add_field_method_get("State", |_, this| {
+            let shared = this.shared.borrow();
+            let state = shared
+                .light_states
+                .get(&this.name)
+                .copied()
+                .unwrap_or_default();
+            Ok(state as i64)
+        });
+        fields.add_field_method_set("State", |_, this, state: i64| {
+            let state = LightState::from_vpx(state);
+            this.shared
+                .borrow_mut()
+                .light_states
+                .insert(this.name.clone(), state);
+            this.push(ScriptCommand::SetLightState {
+                light: this.name.clone(),
+                state,
+            });
+            Ok(())
+        });
+        fields.add_field_method_set("IsDropped", |_, this, dropped: bool| {
+            this.push(ScriptCommand::SetWallDropped {
+                wall: this.name.clone(),
+                dropped,
+            });
+            Ok(())
+        });
+    }
+
+    fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method("CreateBall", |_, this, ()| {
+            this.push(ScriptCommand::CreateBall {
+                kicker: this.name.clone(),
+            });
+            Ok(())
+        });
+        methods.add_method("DestroyBall", |_, this, ()| {
+            this.push(ScriptCommand::DestroyBall {
+                kicker: this.name.clone(),
+            });
+            Ok(())
+        });
+        methods.add_method(
+            "Kick",
+            |_, this, (angle, speed, inclination): (f32, f32, Option<f32>)| {
+                this.push(ScriptCommand::Kick {
+                    kicker: this.name.clone(),
+                    angle,
+                    speed,
+                    inclination: inclination.unwrap_or(0.0),
+                });
+                Ok(())
+            },
+        );
+        methods.add_method("SetValue", |_, this, value: u64| {
+            this.push(ScriptCommand::SetReelValue {
+                reel: this.name.clone(),
+                value,
+            });
+            Ok(())
+        });
+        methods.add_method("AddValue", |_, this, value: i64| {
+            this.push(ScriptCommand::AddReelValue {
+                reel: this.name.clone(),
+                value,
+            });
+            Ok(())
+        });
+        methods.add_method("ResetToZero", |_, this, ()| {
+            this.push(ScriptCommand::SetReelValue {
+                reel: this.name.clone(),
+                value: 0,
+            });
+            Ok(())
+        });
+        methods.add_method("PlaySound", |_, this, sound: String| {
+            this.push(ScriptCommand::PlaySound {
+                sound,
+                item: Some(this.name.clone()),
+            });
+            Ok(())
+        });
+    }
+}
+
+fn item_name(item: &GameItemEnum) -> Option<&str> {
+    match item {
+        GameItemEnum::Wall(wall) => Some(&wall.name),
+        GameItemEnum::Kicker(kicker) => Some(&kicker.name),
+        GameItemEnum::Light(light) => Some(&light.name),
+        GameItemEnum::Bumper(bumper) => Some(&bumper.name),
+        GameItemEnum::Trigger(trigger) => Some(&trigger.name),
+        GameItemEnum::Rubber(rubber) => Some(&rubber.name),
+        GameItemEnum::Plunger(plunger) => Some(&plunger.name),
+        GameItemEnum::Reel(reel) => Some(&reel.name),
+        _ => None,
+    }
+}
+
+/// Creates the Lua state with all table items and runs the script once it has loaded.
+fn start_lua_runtime(
+    mut runtime: NonSendMut<LuaRuntime>,
+    script_handle: Option<Res<LuaScriptHandle>>,
+    scripts: Res<Assets<LuaScript>>,
+    table_assets: Res<TableAssets>,
+    assets_vpx: Res<Assets<VpxAsset>>,
+    mut drain_settings: ResMut<DrainSettings>,
+) {
+    if runtime.lua.is_some() {
+        return;
+    }
+    let Some(script_handle) = script_handle else {
+        return;
+    };
+    let (Some(script), Some(vpx_asset)) = (
+        scripts.get(&script_handle.0),
+        assets_vpx.get(&table_assets.vpx),
+    ) else {
+        return;
+    };
+
+    let lua = match sandboxed_lua() {
+        Ok(lua) => lua,
+        Err(e) => {
+            error!("Failed to create the Lua runtime: {}", e);
+            return;
+        }
+    };
+    let shared = Shared::default();
+    if let Err(e) = register_globals(&lua, &shared, vpx_asset) {
+        error!("Failed to set up Lua globals: {}", e);
+        return;
+    }
+    let script_name = sidecar_path(Path::new(&table_assets.file_name));
+    if let Err(e) = lua
+        .load(script.source.as_str())
+        .set_name(script_name.to_string_lossy())
+        .exec()
+    {
+        error!("Failed to run Lua script {}: {}", script_name.display(), e);
+        return;
+    }
+    info!("Running Lua script {}", script_name.display());
+    let drain_hit = format!("{}_Hit", drain_settings.drain_kicker);
+    if matches!(
+        lua.globals().get::<Option<Function>>(drain_hit.as_str()),
+        Ok(Some(_))
+    ) {
+        info!(
+            "Lua script defines {}, disabling the default drain logic",
+            drain_hit
+        );
+        drain_settings.enabled = false;
+    }
+    runtime.lua = Some(lua);
+    runtime.shared = shared;
+    let init = format!("{}_Init", vpx_asset.raw.gamedata.name);
+    runtime.call(&init);
+}
+
+fn register_globals(lua: &Lua, shared: &Shared, vpx_asset: &VpxAsset) -> mlua::Result<()> {
+    let globals = lua.globals();
+    for name in vpx_asset.raw.gameitems.iter().filter_map(item_name) {
+        globals.set(
+            name,
+            LuaItem {
+                name: name.to_string(),
+                shared: shared.clone(),
+            },
+        )?;
+    }
+    let play_sound_shared = shared.clone();
+    globals.set(
+        "PlaySound",
+        lua.create_function(move |_, sound: String| {
+            play_sound_shared
+                .borrow_mut()
+                .commands
+                .push(ScriptCommand::PlaySound { sound, item: None });
+            Ok(())
+        })?,
+    )?;
+    let add_score_shared = shared.clone();
+    globals.set(
+        "AddScore",
+        lua.create_function(move |_, points: i64| {
+            add_score_shared
+                .borrow_mut()
+                .commands
+                .push(ScriptCommand::AddScore { points });
+            Ok(())
+        })?,
+    )?;
+    let award_special_shared = shared.clone();
+    globals.set(
+        "AwardSpecial",
+        lua.create_function(move |_, ()| {
+            award_special_shared
+                .borrow_mut()
+                .commands
+                .push(ScriptCommand::AwardSpecial);
+            Ok(())
+        })?,
+    )?;
+    let dmd_text_shared = shared.clone();
+    globals.set(
+        "DmdText",
+        lua.create_function(
+            move |_, (text, effect, duration_ms): (String, Option<String>, Option<u64>)| {
+                let effect = effect.unwrap_or_default();
+                let effect = TextEffect::from_name(&effect).ok_or_else(|| {
+                    mlua::Error::runtime(format!("unknown DMD text effect '{effect}'"))
+                })?;
+                dmd_text_shared
+                    .borrow_mut()
+                    .commands
+                    .push(ScriptCommand::DmdText {
+                        text,
+                        effect,
+                        duration_ms: duration_ms.unwrap_or(DEFAULT_TEXT_DURATION_MS),
+                    });
+                Ok(())
+            },
+        )?,
+    )?;
+    let dmd_animation_shared = shared.clone();
+    globals.set(
+        "DmdAnimation",
+        lua.create_function(move |_, (images, frame_ms): (Vec<String>, Option<u64>)| {
+            dmd_animation_shared
+                .borrow_mut()
+                .commands
+                .push(ScriptCommand::DmdAnimation {
+                    images,
+                    frame_ms: frame_ms.unwrap_or(DEFAULT_FRAME_DURATION_MS),
+                });
+            Ok(())
+        })?,
+    )?;
+    let b2s_set_data_shared = shared.clone();
+    globals.set(
+        "B2SSetData",
+        lua.create_function(move |_, (id, value): (i32, i32)| {
+            b2s_set_data_shared
+                .borrow_mut()
+                .commands
+                .push(ScriptCommand::SetB2sData { id, value });
+            Ok(())
+        })?,
+    )?;
+    let dmd_clear_shared = shared.clone();
+    globals.set(
+        "DmdClear",
+        lua.create_function(move |_, ()| {
+            dmd_clear_shared
+                .borrow_mut()
+                .commands
+                .push(ScriptCommand::DmdClear);
+            Ok(())
+        })?,
+    )?;
+    let dmd_shade_depth_shared = shared.clone();
+    globals.set(
+        "DmdShadeDepth",
+        lua.create_function(move |_, levels: u32| {
+            let depth = ShadeDepth::from_levels(levels).ok_or_else(|| {
+                mlua::Error::runtime(format!("a DMD has 4 or 16 shades, not {levels}"))
+            })?;
+            dmd_shade_depth_shared
+                .borrow_mut()
+                .commands
+                .push(ScriptCommand::DmdShadeDepth(depth));
+            Ok(())
+        })?,
+    )?;
+    let show_status_shared = shared.clone();
+    globals.set(
+        "ShowStatus",
+        lua.create_function(move |_, name: String| {
+            let message = StatusMessage::from_name(&name)
+                .ok_or_else(|| mlua::Error::runtime(format!("unknown status message '{name}'")))?;
+            show_status_shared
+                .borrow_mut()
+                .commands
+                .push(ScriptCommand::ShowStatus(message));
+            Ok(())
+        })?,
+    )?;
+    Ok(())
+}
+
+/// A Lua state without `io`, `os`, `package` and the base functions that load files, so a
+/// downloaded table script can't read or write files or run programs.
+fn sandboxed_lua() -> mlua::Result<Lua> {
+    let lua = Lua::new_with(
+        StdLib::TABLE | StdLib::STRING | StdLib::MATH | StdLib::COROUTINE | StdLib::UTF8,
+        LuaOptions::default(),
+    )?;
+    let globals = lua.globals();
+    globals.set("dofile", mlua::Nil)?;
+    globals.set("loadfile", mlua::Nil)?;
+    Ok(lua)
+}
+
+fn stop_lua_runtime(mut runtime: NonSendMut<LuaRuntime>) {
+    *runtime = LuaRuntime::default();
+}
+
+/// Callbacks for item events, waiting to be called by [`dispatch_lua_callbacks`].
+#[derive(Resource, Default)]
+struct PendingLuaCallbacks(Vec<String>);
+
+fn queue_hit_callback(
+    hit: On<ItemHit>,
+    active: Res<ActiveTableScript>,
+    mut pending: ResMut<PendingLuaCallbacks>,
+) {
+    if active.0 == Some(LUA_SCRIPT) {
+        pending.0.push(format!("{}_Hit", hit.item_name));
+    }
+}
+
+fn queue_unhit_callback(
+    unhit: On<ItemUnhit>,
+    active: Res<ActiveTableScript>,
+    mut pending: ResMut<PendingLuaCallbacks>,
+) {
+    if active.0 == Some(LUA_SCRIPT) {
+        pending.0.push(format!("{}_Unhit", unhit.item_name));
+    }
+}
+
+fn queue_spin_callback(
+    spin: On<ItemSpin>,
+    active: Res<ActiveTableScript>,
+    mut pending: ResMut<PendingLuaCallbacks>,
+) {
+    if active.0 == Some(LUA_SCRIPT) {
+        pending.0.push(format!("{}_Spin", spin.item_name));
+    }
+}
+
+fn queue_timer_callback(
+    timer: On<ItemTimer>,
+    active: Res<ActiveTableScript>,
+    mut pending: ResMut<PendingLuaCallbacks>,
+) {
+    if active.0 == Some(LUA_SCRIPT) {
+        pending.0.push(format!("{}_Timer", timer.item_name));
+    }
+}
+
+/// Calls the queued item callbacks like `<Item>_Hit` and `<Item>_Unhit`.
+fn dispatch_lua_callbacks(
+    runtime: NonSend<LuaRuntime>,
+    mut pending: ResMut<PendingLuaCallbacks>,
+    light_query: Query<(&Light, &LightState)>,
+    mut script_commands: MessageWriter<ScriptCommand>,
+) {
+    if runtime.lua.is_none() {
+        pending.0.clear();
+        return;
+    }
+    runtime.shared.borrow_mut().light_states = light_query
+        .iter()
+        .map(|(light, state)| (light.name.clone(), *state))
+        .collect();
+
+    for callback in pending.0.drain(..) {
+        runtime.call(&callback);
+    }
+
+    script_commands.write_batch(runtime.shared.borrow_mut().commands.drain(..));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripts_cannot_reach_files_or_programs() {
+        let lua = sandboxed_lua().unwrap();
+        let reachable: bool = lua
+            .load(
+                "return (os and os.execute) ~= nil or io ~= nil or require ~= nil \
+                 or dofile ~= nil or loadfile ~= nil",
+            )
+            .eval()
+            .unwrap();
+        assert!(!reachable);
+    }
+
+    #[test]
+    fn scripts_keep_the_safe_libraries() {
+        let lua = sandboxed_lua().unwrap();
+        let value: String = lua
+            .load("return string.format('%d', math.max(1, table.unpack({2, 3})))")
+            .eval()
+            .unwrap();
+        assert_eq!(value, "3");
+    }
+}
@@ -1,45 +1,38 @@
 //! Visual Pinball tables use legacy VBScript for scripting.
 //! However, we don't want to implement a full VBScript interpreter in Rust.
-//! Instead, we want to use a still supported and widely used language like Lua.
-//! For now however we re-implement the script in Rust directly as a proof of concept.
+//! Simple table scripts are interpreted directly, see [`vbscript`].
+//! For anything else we want to use a still supported and widely used language like Lua.
+//! A table can ship a Lua script next to the VPX file, see [`lua`].
+//! Some tables have their script re-implemented in Rust directly as a proof of concept.
+//! Tables without a script still drain and release balls, see [`default_table`].
+//! The script for the loaded table is chosen by the [`registry`].
+//! Item events that no script handles still play the sounds of the original VBScript.
 
-use crate::pinball::TablePath;
-use crate::pinball::table::TableAssets;
-use crate::vpx::VpxAsset;
 use bevy::prelude::*;
+use registry::RegisterTableScript;
 
+pub mod api;
+pub mod default_table;
 mod example_table;
+#[cfg(not(target_family = "wasm"))]
+mod lua;
 mod north_pole;
+pub mod registry;
 mod tna;
+mod vbscript;
+mod vbscript_sounds;
 
 pub(super) fn plugin(app: &mut App) {
-    let table_path = app.world().resource::<TablePath>();
-    match table_path.path.file_name().unwrap().to_str().unwrap() {
-        "exampleTable.vpx" => {
-            app.add_plugins((example_table::plugin,));
-        }
-        "North Pole (Playmatic 1967) v600.vpx" => {
-            app.add_plugins((north_pole::plugin,));
-        }
-        "Total Nuclear Annihilation (Spooky 2017) VPW v2.3.vpx" => {
-            app.add_plugins((tna::plugin,));
-        }
-        other => {
-            warn!("No script available for table file: {}", other);
-        }
-    }
-}
-
-pub(super) fn load_sound(
-    table_assets: &Res<TableAssets>,
-    assets_vpx: &Res<Assets<VpxAsset>>,
-    name: &str,
-) -> Handle<AudioSource> {
-    assets_vpx
-        .get(&table_assets.vpx)
-        .unwrap()
-        .named_sounds
-        .get(name)
-        .unwrap_or_else(|| panic!("Sound {name} not found"))
-        .clone()
+    app.add_plugins((
+        api::plugin,
+        default_table::plugin,
+        registry::plugin,
+        vbscript::plugin,
+        vbscript_sounds::plugin,
+    ));
+    #[cfg(not(target_family = "wasm"))]
+    app.add_plugins(lua::plugin);
+    app.register_table_script(example_table::ExampleTableScript)
+        .register_table_script(north_pole::NorthPoleScript)
+        .register_table_script(tna::TnaScript);
 }
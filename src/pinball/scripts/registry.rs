@@ -0,0 +1,225 @@
+//! Chooses the script for the loaded table.
+//!
+//! Scripts register the tables they are written for as [`TableKey`]s. When the table changes the
+//! best matching script becomes the [`ActiveTableScript`], and every script system is expected to
+//! run only while its script is active, see [`table_script_active`].
+
+use crate::AppSystems;
+use crate::pinball::scripts::default_table::DrainSettings;
+use crate::pinball::table::TableAssets;
+use crate::vpx::VpxAsset;
+use bevy::prelude::*;
+use std::path::Path;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<TableScriptRegistry>();
+    app.init_resource::<ActiveTableScript>();
+    app.add_systems(
+        Update,
+        select_table_script
+            .in_set(AppSystems::Update)
+            .run_if(resource_exists::<TableAssets>),
+    );
+}
+
+/// A table script implemented in Rust or by a script runtime.
+pub trait TableScript {
+    /// A unique name for the script.
+    fn name(&self) -> &'static str;
+
+    /// The tables this script is written for.
+    fn keys(&self) -> Vec<TableKey>;
+
+    /// Adds the systems of the script, which should only run while the script is active.
+    fn build(&self, _app: &mut App) {}
+
+    /// Whether the script reacts to item events itself, instead of relying on the handlers found
+    /// in the VBScript of the table.
+    fn handles_item_events(&self) -> bool {
+        false
+    }
+
+    /// How the built-in drain logic should behave for this table.
+    fn drain_settings(&self) -> DrainSettings {
+        DrainSettings::default()
+    }
+
+    /// The wall that keeps the ball centered in the plunger lane, which the built-in logic
+    /// removes because the plunger can't pass through it.
+    fn plunger_wall(&self) -> Option<&'static str> {
+        None
+    }
+}
+
+/// Identifies a table a script is written for, from the most to the least specific.
+#[derive(Debug, Clone)]
+pub enum TableKey {
+    /// A file next to the table with the given extension, e.g. a Lua script.
+    Sidecar(&'static str),
+    /// The table name and version from the VPX table info, compared case-insensitively.
+    Info {
+        name: &'static str,
+        version: Option<&'static str>,
+    },
+    /// The file name of the table, for tables without useful table info.
+    FileName(&'static str),
+    /// Any table the predicate accepts, for generic script runtimes.
+    Custom(fn(&VpxAsset) -> bool),
+}
+
+impl TableKey {
+    pub fn info(name: &'static str) -> Self {
+        TableKey::Info {
+            name,
+            version: None,
+        }
+    }
+
+    /// How well this key matches the table, higher is better.
+    fn score(&self, vpx_asset: &VpxAsset, table_path: &Path) -> Option<u32> {
+        let identity = &vpx_asset.identity;
+        let eq =
+            |a: &Option<String>, b: &str| a.as_ref().is_some_and(|a| a.eq_ignore_ascii_case(b));
+        match self {
+            TableKey::Sidecar(extension) => sidecar_exists(table_path, extension).then_some(4),
+            TableKey::Info {
+                name,
+                version: Some(version),
+            } => (eq(&identity.name, name) && eq(&identity.version, version)).then_some(3),
+            TableKey::Info {
+                name,
+                version: None,
+            } => eq(&identity.name, name).then_some(2),
+            TableKey::FileName(file_name) => table_path
+                .file_name()
+                .is_some_and(|f| f.to_string_lossy() == *file_name)
+                .then_some(1),
+            TableKey::Custom(accepts) => accepts(vpx_asset).then_some(0),
+        }
+    }
+}
+
+/// Whether a file with the given extension sits next to the table.
+#[cfg(not(target_family = "wasm"))]
+pub(crate) fn sidecar_exists(table_path: &Path, extension: &str) -> bool {
+    bevy::asset::io::file::FileAssetReader::get_base_path()
+        .join("assets")
+        .join(table_path.with_extension(extension))
+        .exists()
+}
+
+#[cfg(target_family = "wasm")]
+pub(crate) fn sidecar_exists(_table_path: &Path, _extension: &str) -> bool {
+    false
+}
+
+/// All registered table scripts with the tables they are written for.
+#[derive(Resource, Default)]
+pub struct TableScriptRegistry {
+    scripts: Vec<RegisteredScript>,
+}
+
+struct RegisteredScript {
+    name: &'static str,
+    keys: Vec<TableKey>,
+    handles_item_events: bool,
+    drain_settings: DrainSettings,
+    plunger_wall: Option<&'static str>,
+}
+
+impl TableScriptRegistry {
+    /// Whether the given script reacts to item events itself, see [`TableScript::handles_item_events`].
+    pub fn handles_item_events(&self, name: &str) -> bool {
+        self.scripts
+            .iter()
+            .any(|script| script.name == name && script.handles_item_events)
+    }
+
+    /// The plunger lane wall of the given script, see [`TableScript::plunger_wall`].
+    pub fn plunger_wall(&self, name: &str) -> Option<&'static str> {
+        self.scripts
+            .iter()
+            .find(|script| script.name == name)
+            .and_then(|script| script.plunger_wall)
+    }
+}
+
+/// The name of the script for the current table, if any.
+#[derive(Resource, Debug, Default, Clone, PartialEq, Eq)]
+pub struct ActiveTableScript(pub Option<&'static str>);
+
+pub trait RegisterTableScript {
+    /// Builds the script and makes it available for the tables it is written for.
+    fn register_table_script(&mut self, script: impl TableScript) -> &mut Self;
+}
+
+impl RegisterTableScript for App {
+    fn register_table_script(&mut self, script: impl TableScript) -> &mut Self {
+        script.build(self);
+        self.world_mut()
+            .get_resource_or_init::<TableScriptRegistry>()
+            .scripts
+            .push(RegisteredScript {
+                name: script.name(),
+                keys: script.keys(),
+                handles_item_events: script.handles_item_events(),
+                drain_settings: script.drain_settings(),
+                plunger_wall: script.plunger_wall(),
+            });
+        self
+    }
+}
+
+/// Run condition that is active if the given script is the script for the current table.
+pub fn table_script_active(
+    name: &'static str,
+) -> impl FnMut(Res<ActiveTableScript>) -> bool + Clone {
+    move |active: Res<ActiveTableScript>| active.0 == Some(name)
+}
+
+/// Selects the script once the table is loaded, and again whenever another table is loaded.
+fn select_table_script(
+    registry: Res<TableScriptRegistry>,
+    mut active: ResMut<ActiveTableScript>,
+    mut drain_settings: ResMut<DrainSettings>,
+    table_assets: Res<TableAssets>,
+    assets_vpx: Res<Assets<VpxAsset>>,
+    mut selected_for: Local<Option<AssetId<VpxAsset>>>,
+) {
+    if *selected_for == Some(table_assets.vpx.id()) {
+        return;
+    }
+    let Some(vpx_asset) = assets_vpx.get(&table_assets.vpx) else {
+        return;
+    };
+    *selected_for = Some(table_assets.vpx.id());
+    let table_path = Path::new(&table_assets.file_name);
+    let best = registry
+        .scripts
+        .iter()
+        .filter_map(|script| {
+            script
+                .keys
+                .iter()
+                .filter_map(|key| key.score(vpx_asset, table_path))
+                .max()
+                .map(|score| (score, script))
+        })
+        .max_by_key(|(score, _)| *score)
+        .map(|(_, script)| script);
+    match best {
+        Some(script) => info!(
+            "Using script '{}' for table {}",
+            script.name, table_assets.file_name
+        ),
+        None => warn!(
+            "No script available for table {} ({:?})",
+            table_assets.file_name, vpx_asset.identity
+        ),
+    }
+    *drain_settings = best
+        .map(|script| script.drain_settings.clone())
+        .unwrap_or_default();
+    let best = best.map(|script| script.name);
+    active.set_if_neq(ActiveTableScript(best));
+}
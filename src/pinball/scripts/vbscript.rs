@@ -0,0 +1,401 @@
+//! Runs the VBScript embedded in the table, for tables that only use the subset of the language
+//! supported by the [`interpreter`](crate::vbscript::interpreter).
+//!
+//! This is the fallback for tables without a hand-written script. Tables that use unsupported
+//! constructs are logged with the offending lines and keep using the default table logic.
+
+use crate::pinball::dmd::{
+    DEFAULT_FRAME_DURATION_MS, DEFAULT_TEXT_DURATION_MS, ShadeDepth, TextEffect,
+};
+use crate::pinball::events::{GameItem, ItemHit, ItemKind, ItemSpin, ItemTimer, ItemUnhit};
+use crate::pinball::hud::StatusMessage;
+use crate::pinball::light::{Light, LightState};
+use crate::pinball::scripts::api::ScriptCommand;
+use crate::pinball::scripts::default_table::DrainSettings;
+use crate::pinball::scripts::registry::{
+    ActiveTableScript, RegisterTableScript, TableKey, TableScript, table_script_active,
+};
+use crate::pinball::table::TableAssets;
+use crate::screens::Screen;
+use crate::vbscript::interpreter::{Host, Interpreter, RuntimeError, Value};
+use crate::vbscript::parser;
+use crate::vpx::VpxAsset;
+use crate::{AppSystems, PausableSystems};
+use bevy::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_table_script(VbScriptTable);
+}
+
+const VBSCRIPT: &str = "vbscript";
+
+/// Interprets the table VBScript. Hand-written scripts for a table take precedence.
+struct VbScriptTable;
+
+impl TableScript for VbScriptTable {
+    fn name(&self) -> &'static str {
+        VBSCRIPT
+    }
+
+    fn keys(&self) -> Vec<TableKey> {
+        vec![TableKey::Custom(script_supported)]
+    }
+
+    fn handles_item_events(&self) -> bool {
+        true
+    }
+
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VbScriptRuntime>();
+        app.add_systems(
+            Update,
+            (
+                stop_vbscript.run_if(resource_changed::<ActiveTableScript>),
+                start_vbscript
+                    .in_set(PausableSystems)
+                    .run_if(in_state(Screen::Gameplay))
+                    .run_if(table_script_active(VBSCRIPT)),
+            )
+                .chain()
+                .in_set(AppSystems::Update),
+        );
+        app.add_systems(OnExit(Screen::Gameplay), stop_vbscript);
+        app.add_observer(|hit: On<ItemHit>, mut script: VbScript| {
+            script.call(&format!("{}_Hit", hit.item_name));
+        });
+        app.add_observer(|unhit: On<ItemUnhit>, mut script: VbScript| {
+            script.call(&format!("{}_Unhit", unhit.item_name));
+        });
+        app.add_observer(|spin: On<ItemSpin>, mut script: VbScript| {
+            script.call(&format!("{}_Spin", spin.item_name));
+        });
+        app.add_observer(|timer: On<ItemTimer>, mut script: VbScript| {
+            script.call(&format!("{}_Timer", timer.item_name));
+        });
+    }
+}
+
+/// Whether the table has a script that only uses supported VBScript.
+fn script_supported(vpx_asset: &VpxAsset) -> bool {
+    let code = &vpx_asset.raw.gamedata.code.string;
+    if code.trim().is_empty() {
+        return false;
+    }
+    match parser::parse(code) {
+        Ok(program) => {
+            let unsupported = program.unsupported();
+            if !unsupported.is_empty() {
+                info!(
+                    "Not interpreting the table VBScript, it uses {} unsupported constructs:",
+                    unsupported.len()
+                );
+                for (line, construct) in unsupported.iter().take(10) {
+                    info!("  line {}: {}", line, construct);
+                }
+            }
+            unsupported.is_empty()
+        }
+        Err(e) => {
+            info!(
+                "Not interpreting the table VBScript, line {}: {}",
+                e.line, e.message
+            );
+            false
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+struct VbScriptRuntime {
+    interpreter: Option<Interpreter>,
+    /// The kinds of all table items by their lowercase name.
+    items: HashMap<String, ItemKind>,
+    /// Procedures that failed, they are not called again to avoid flooding the log.
+    failed: HashSet<String>,
+}
+
+fn stop_vbscript(mut runtime: ResMut<VbScriptRuntime>) {
+    *runtime = VbScriptRuntime::default();
+}
+
+/// Runs the top level statements of the script and calls `<TableName>_Init`.
+fn start_vbscript(
+    mut script: VbScript,
+    item_query: Query<&GameItem>,
+    mut drain_settings: ResMut<DrainSettings>,
+    table_assets: Res<TableAssets>,
+    assets_vpx: Res<Assets<VpxAsset>>,
+) {
+    if script.runtime.interpreter.is_some() {
+        return;
+    }
+    let Some(vpx_asset) = assets_vpx.get(&table_assets.vpx) else {
+        return;
+    };
+    let program = match parser::parse(&vpx_asset.raw.gamedata.code.string) {
+        Ok(program) => program,
+        Err(e) => {
+            error!(
+                "Failed to parse the table VBScript, line {}: {}",
+                e.line, e.message
+            );
+            return;
+        }
+    };
+    let interpreter = Interpreter::new(program);
+    let drain_hit = format!("{}_Hit", drain_settings.drain_kicker);
+    if interpreter.has_procedure(&drain_hit) {
+        info!(
+            "Table VBScript defines {}, disabling the default drain logic",
+            drain_hit
+        );
+        drain_settings.enabled = false;
+    }
+    info!("Running the table VBScript of {}", table_assets.file_name);
+    script.runtime.interpreter = Some(interpreter);
+    script.runtime.items = item_query
+        .iter()
+        .map(|item| (item.name.to_lowercase(), item.kind))
+        .collect();
+    script.run(
+        |interpreter, host| interpreter.run(host),
+        "the table script",
+    );
+    script.call(&format!("{}_Init", vpx_asset.raw.gamedata.name));
+}
+
+/// Access to the running script from systems and observers.
+#[derive(bevy::ecs::system::SystemParam)]
+struct VbScript<'w, 's> {
+    runtime: ResMut<'w, VbScriptRuntime>,
+    light_query: Query<'w, 's, (&'static Light, &'static LightState)>,
+    script_commands: MessageWriter<'w, ScriptCommand>,
+}
+
+impl VbScript<'_, '_> {
+    /// Calls a procedure of the script if it defines it.
+    fn call(&mut self, procedure: &str) {
+        if self.runtime.failed.contains(procedure) {
+            return;
+        }
+        self.run(
+            |interpreter, host| interpreter.call(procedure, Vec::new(), host).map(|_| ()),
+            procedure,
+        );
+    }
+
+    fn run(
+        &mut self,
+        f: impl FnOnce(&mut Interpreter, &mut TableHost) -> Result<(), RuntimeError>,
+        what: &str,
+    ) {
+        let VbScriptRuntime {
+            interpreter: Some(interpreter),
+            items,
+            failed,
+        } = &mut *self.runtime
+        else {
+            return;
+        };
+        let mut host = TableHost {
+            items,
+            light_states: self
+                .light_query
+                .iter()
+                .map(|(light, state)| (light.name.to_lowercase(), *state))
+                .collect(),
+            commands: Vec::new(),
+        };
+        if let Err(e) = f(interpreter, &mut host) {
+            error!("VBScript error in {}, {}", what, e);
+            failed.insert(what.to_string());
+        }
+        self.script_commands.write_batch(host.commands);
+    }
+}
+
+/// Maps the VPX scripting API used by the script to [`ScriptCommand`]s.
+struct TableHost<'a> {
+    items: &'a HashMap<String, ItemKind>,
+    light_states: HashMap<String, LightState>,
+    commands: Vec<ScriptCommand>,
+}
+
+impl TableHost<'_> {
+    fn kind(&self, item: &str) -> Option<ItemKind> {
+        self.items.get(&item.to_lowercase()).copied()
+    }
+}
+
+fn arg(args: &[Value], index: usize) -> Value {
+    args.get(index).cloned().unwrap_or(Value::Empty)
+}
+
+impl Host for TableHost<'_> {
+    fn is_item(&self, name: &str) -> bool {
+        self.kind(name).is_some()
+    }
+
+    fn call(&mut self, name: &str, args: &[Value]) -> Option<Result<Value, String>> {
+        match name.to_lowercase().as_str() {
+            "playsound" => self.commands.push(ScriptCommand::PlaySound {
+                sound: arg(args, 0).to_string(),
+                item: None,
+            }),
+            "playsoundat" => self.commands.push(ScriptCommand::PlaySound {
+                sound: arg(args, 0).to_string(),
+                item: arg(args, 1).as_item().map(str::to_string),
+            }),
+            "addscore" => match arg(args, 0).to_number() {
+                Ok(points) => self.commands.push(ScriptCommand::AddScore {
+                    points: points as i64,
+                }),
+                Err(e) => return Some(Err(e)),
+            },
+            "awardspecial" => self.commands.push(ScriptCommand::AwardSpecial),
+            "dmdtext" => {
+                let effect = match arg(args, 1) {
+                    Value::Empty => TextEffect::Static,
+                    effect => match TextEffect::from_name(&effect.to_string()) {
+                        Some(effect) => effect,
+                        None => return Some(Err(format!("unknown DMD text effect '{effect}'"))),
+                    },
+                };
+                let duration_ms = match arg(args, 2) {
+                    Value::Empty => DEFAULT_TEXT_DURATION_MS,
+                    duration => match duration.to_number() {
+                        Ok(duration) => duration.max(0.0) as u64,
+                        Err(e) => return Some(Err(e)),
+                    },
+                };
+                self.commands.push(ScriptCommand::DmdText {
+                    text: arg(args, 0).to_string(),
+                    effect,
+                    duration_ms,
+                });
+            }
+            "dmdanimation" => {
+                let images = match arg(args, 0) {
+                    Value::Array(images) => images.iter().map(Value::to_string).collect(),
+                    image => vec![image.to_string()],
+                };
+                let frame_ms = match arg(args, 1) {
+                    Value::Empty => DEFAULT_FRAME_DURATION_MS,
+                    frame => match frame.to_number() {
+                        Ok(frame) => frame.max(0.0) as u64,
+                        Err(e) => return Some(Err(e)),
+                    },
+                };
+                self.commands
+                    .push(ScriptCommand::DmdAnimation { images, frame_ms });
+            }
+            "dmdclear" => self.commands.push(ScriptCommand::DmdClear),
+            "dmdshadedepth" => match arg(args, 0).to_number() {
+                Ok(levels) => match ShadeDepth::from_levels(levels as u32) {
+                    Some(depth) => self.commands.push(ScriptCommand::DmdShadeDepth(depth)),
+                    None => return Some(Err(format!("a DMD has 4 or 16 shades, not {levels}"))),
+                },
+                Err(e) => return Some(Err(e)),
+            },
+            "b2ssetdata" => match (arg(args, 0).to_number(), arg(args, 1).to_number()) {
+                (Ok(id), Ok(value)) => self.commands.push(ScriptCommand::SetB2sData {
+                    id: id as i32,
+                    value: value as i32,
+                }),
+                (Err(e), _) | (_, Err(e)) => return Some(Err(e)),
+            },
+            "showstatus" => {
+                let name = arg(args, 0).to_string();
+                match StatusMessage::from_name(&name) {
+                    Some(message) => self.commands.push(ScriptCommand::ShowStatus(message)),
+                    None => return Some(Err(format!("unknown status message '{name}'"))),
+                }
+            }
+            _ => return None,
+        }
+        Some(Ok(Value::Empty))
+    }
+
+    fn get_property(&mut self, item: &str, property: &str) -> Result<Value, String> {
+        match property.to_lowercase().as_str() {
+            "name" => Ok(Value::Str(item.to_string())),
+            "state" => self
+                .light_states
+                .get(&item.to_lowercase())
+                .map(|state| Value::Number(*state as i64 as f64))
+                .ok_or_else(|| format!("'{item}' is not a light")),
+            _ => Err(format!("reading '{item}.{property}' is not supported")),
+        }
+    }
+
+    fn set_property(&mut self, item: &str, property: &str, value: Value) -> Result<(), String> {
+        let is_timer = self.kind(item) == Some(ItemKind::Timer);
+        let command = match property.to_lowercase().as_str() {
+            "state" => {
+                let state = LightState::from_vpx(value.to_number()? as i64);
+                self.light_states.insert(item.to_lowercase(), state);
+                ScriptCommand::SetLightState {
+                    light: item.to_string(),
+                    state,
+                }
+            }
+            "isdropped" => ScriptCommand::SetWallDropped {
+                wall: item.to_string(),
+                dropped: value.to_bool()?,
+            },
+            "timerenabled" => timer_enabled(item, value.to_bool()?),
+            "enabled" if is_timer => timer_enabled(item, value.to_bool()?),
+            "timerinterval" => timer_interval(item, value.to_number()?),
+            "interval" if is_timer => timer_interval(item, value.to_number()?),
+            _ => return Err(format!("setting '{item}.{property}' is not supported")),
+        };
+        self.commands.push(command);
+        Ok(())
+    }
+
+    fn call_method(&mut self, item: &str, method: &str, args: &[Value]) -> Result<Value, String> {
+        let kicker = item.to_string();
+        let command = match method.to_lowercase().as_str() {
+            "createball" => ScriptCommand::CreateBall { kicker },
+            "destroyball" => ScriptCommand::DestroyBall { kicker },
+            "kick" => ScriptCommand::Kick {
+                kicker,
+                angle: arg(args, 0).to_number()? as f32,
+                speed: arg(args, 1).to_number()? as f32,
+                inclination: arg(args, 2).to_number()? as f32,
+            },
+            "setvalue" => ScriptCommand::SetReelValue {
+                reel: item.to_string(),
+                value: arg(args, 0).to_number()?.max(0.0) as u64,
+            },
+            "addvalue" => ScriptCommand::AddReelValue {
+                reel: item.to_string(),
+                value: arg(args, 0).to_number()? as i64,
+            },
+            "resettozero" => ScriptCommand::SetReelValue {
+                reel: item.to_string(),
+                value: 0,
+            },
+            _ => return Err(format!("'{item}.{method}' is not supported")),
+        };
+        self.commands.push(command);
+        Ok(Value::Empty)
+    }
+}
+
+fn timer_enabled(item: &str, enabled: bool) -> ScriptCommand {
+    ScriptCommand::SetTimer {
+        item: item.to_string(),
+        enabled: Some(enabled),
+        interval_ms: None,
+    }
+}
+
+fn timer_interval(item: &str, interval_ms: f64) -> ScriptCommand {
+    ScriptCommand::SetTimer {
+        item: item.to_string(),
+        enabled: None,
+        interval_ms: Some(interval_ms.max(0.0) as u64),
+    }
+}
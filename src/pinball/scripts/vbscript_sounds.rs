@@ -0,0 +1,67 @@
+//! Plays the sounds that the VBScript of the table plays for item events.
+//!
+//! This only applies when the active script doesn't handle item events itself, see
+//! [`TableScript::handles_item_events`](super::registry::TableScript::handles_item_events).
+//! Other actions of the handlers are left to the default table logic and hand-written scripts.
+
+use crate::pinball::events::{ItemHit, ItemTimer, ItemUnhit};
+use crate::pinball::scripts::api::ScriptCommand;
+use crate::pinball::scripts::default_table::DrainSettings;
+use crate::pinball::scripts::registry::{ActiveTableScript, TableScriptRegistry};
+use crate::pinball::table::TableAssets;
+use crate::vpx::VpxAsset;
+use bevy::prelude::*;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_observer(|hit: On<ItemHit>, sounds: ScriptSounds| {
+        sounds.play(&hit.item_name, "Hit");
+    });
+    app.add_observer(|unhit: On<ItemUnhit>, sounds: ScriptSounds| {
+        sounds.play(&unhit.item_name, "Unhit");
+    });
+    app.add_observer(|timer: On<ItemTimer>, sounds: ScriptSounds| {
+        sounds.play(&timer.item_name, "Timer");
+    });
+}
+
+#[derive(bevy::ecs::system::SystemParam)]
+struct ScriptSounds<'w> {
+    registry: Res<'w, TableScriptRegistry>,
+    active: Res<'w, ActiveTableScript>,
+    drain_settings: Res<'w, DrainSettings>,
+    table_assets: Res<'w, TableAssets>,
+    assets_vpx: Res<'w, Assets<VpxAsset>>,
+    script_commands: MessageWriter<'w, ScriptCommand>,
+}
+
+impl ScriptSounds<'_> {
+    fn play(mut self, item: &str, event: &str) {
+        if self
+            .active
+            .0
+            .is_some_and(|name| self.registry.handles_item_events(name))
+        {
+            return;
+        }
+        // the default drain logic plays its own sounds
+        if self.drain_settings.enabled
+            && item.eq_ignore_ascii_case(&self.drain_settings.drain_kicker)
+        {
+            return;
+        }
+        let Some(vpx_asset) = self.assets_vpx.get(&self.table_assets.vpx) else {
+            return;
+        };
+        // scripts often play sounds that are not part of the table, like those of a sound package
+        let sounds = vpx_asset
+            .event_handlers
+            .sounds(item, event)
+            .filter(|sound| vpx_asset.sound(sound).is_some());
+        for sound in sounds {
+            self.script_commands.write(ScriptCommand::PlaySound {
+                sound: sound.to_string(),
+                item: Some(item.to_string()),
+            });
+        }
+    }
+}
@@ -0,0 +1,229 @@
+//! Settings that are kept between runs, each kind in a small versioned text file next to the
+//! assets folder with one setting per line:
+//!
+//! ```text
+//! vpinball2d-view 1
+//! view-mode desktop
+//! follow-ball off
+//! ```
+//!
+//! Settings that are not known are skipped, so that a file written by a newer version still loads.
+//! A file that can't be read is left alone, the settings in it are not saved.
+
+use bevy::prelude::*;
+use std::marker::PhantomData;
+use thiserror::Error;
+
+/// Loads the settings on startup and saves them on exit.
+pub(crate) fn plugin<T: StoredSettings>(app: &mut App) {
+    let (settings, read_only) = load::<T>();
+    app.insert_resource(settings);
+    app.insert_resource(SettingsFile::<T> {
+        read_only,
+        marker: PhantomData,
+    });
+    app.add_systems(Last, save_on_exit::<T>);
+}
+
+/// A settings resource that is stored in a file of its own.
+pub trait StoredSettings: Resource + Default {
+    /// The first word of the file, naming the kind of settings.
+    const FILE_HEADER: &'static str;
+    const FILE_VERSION: u32;
+    const FILE_NAME: &'static str;
+
+    /// Reads a single setting, returns `Ok(false)` if the key is not known.
+    fn read(&mut self, key: &str, value: &str) -> Result<bool, String>;
+
+    /// The keys and values of all settings, in the order they are written.
+    fn write(&self) -> Vec<(&'static str, String)>;
+}
+
+#[derive(Error, Debug)]
+pub enum SettingsFileError {
+    #[error("not a {0} file")]
+    WrongHeader(&'static str),
+    #[error("unsupported version {0}")]
+    UnsupportedVersion(String),
+    #[error("line {line}: {message}")]
+    Invalid { line: usize, message: String },
+    #[error("could not read or write the settings: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Whether the file of the settings `T` could not be read, so that saving doesn't overwrite it.
+#[derive(Resource)]
+struct SettingsFile<T> {
+    read_only: bool,
+    marker: PhantomData<T>,
+}
+
+/// Reads an `on` or `off` setting.
+pub fn read_on_off(value: &str) -> Result<bool, String> {
+    match value {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => Err("expected on or off".to_string()),
+    }
+}
+
+pub fn on_off(on: bool) -> String {
+    if on { "on" } else { "off" }.to_string()
+}
+
+fn parse<T: StoredSettings>(text: &str) -> Result<T, SettingsFileError> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()));
+    let version = lines
+        .next()
+        .and_then(|(_, header)| header.strip_prefix(T::FILE_HEADER))
+        .ok_or(SettingsFileError::WrongHeader(T::FILE_HEADER))?
+        .trim();
+    if version != T::FILE_VERSION.to_string() {
+        return Err(SettingsFileError::UnsupportedVersion(version.to_string()));
+    }
+    let mut settings = T::default();
+    for (line, text) in lines.filter(|(_, text)| !text.is_empty()) {
+        let (key, value) = text.split_once(' ').unwrap_or((text, ""));
+        match settings.read(key, value.trim()) {
+            Ok(true) => {}
+            Ok(false) => warn!(
+                "Skipping unknown setting '{}' on line {} of {}",
+                key,
+                line,
+                T::FILE_NAME
+            ),
+            Err(message) => return Err(SettingsFileError::Invalid { line, message }),
+        }
+    }
+    Ok(settings)
+}
+
+fn to_text<T: StoredSettings>(settings: &T) -> String {
+    let mut text = format!("{} {}\n", T::FILE_HEADER, T::FILE_VERSION);
+    for (key, value) in settings.write() {
+        text.push_str(&format!("{key} {value}\n"));
+    }
+    text
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn path<T: StoredSettings>() -> std::path::PathBuf {
+    bevy::asset::io::file::FileAssetReader::get_base_path().join(T::FILE_NAME)
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn load<T: StoredSettings>() -> (T, bool) {
+    let path = path::<T>();
+    let result = match std::fs::read_to_string(&path) {
+        Ok(text) => parse(&text),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(e.into()),
+    };
+    match result {
+        Ok(settings) => (settings, false),
+        Err(e) => {
+            warn!(
+                "Ignoring the settings in {}, they won't be updated: {}",
+                path.display(),
+                e
+            );
+            (T::default(), true)
+        }
+    }
+}
+
+/// Settings are not stored on the web.
+#[cfg(target_family = "wasm")]
+fn load<T: StoredSettings>() -> (T, bool) {
+    (T::default(), true)
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn save<T: StoredSettings>(settings: &T) {
+    let path = path::<T>();
+    if let Err(e) = std::fs::write(&path, to_text(settings)) {
+        warn!("Failed to save the settings to {}: {}", path.display(), e);
+    }
+}
+
+#[cfg(target_family = "wasm")]
+fn save<T: StoredSettings>(_settings: &T) {}
+
+fn save_on_exit<T: StoredSettings>(
+    mut exit: MessageReader<AppExit>,
+    settings: Res<T>,
+    file: Res<SettingsFile<T>>,
+) {
+    if exit.read().count() > 0 && !file.read_only {
+        save(&*settings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Resource, Debug, Default, PartialEq)]
+    struct TestSettings {
+        enabled: bool,
+        name: String,
+    }
+
+    impl StoredSettings for TestSettings {
+        const FILE_HEADER: &'static str = "vpinball2d-test";
+        const FILE_VERSION: u32 = 2;
+        const FILE_NAME: &'static str = "test.txt";
+
+        fn read(&mut self, key: &str, value: &str) -> Result<bool, String> {
+            match key {
+                "enabled" => self.enabled = read_on_off(value)?,
+                "name" => self.name = value.to_string(),
+                _ => return Ok(false),
+            }
+            Ok(true)
+        }
+
+        fn write(&self) -> Vec<(&'static str, String)> {
+            vec![
+                ("enabled", on_off(self.enabled)),
+                ("name", self.name.clone()),
+            ]
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let settings = TestSettings {
+            enabled: true,
+            name: "North Pole".to_string(),
+        };
+        let text = to_text(&settings);
+        assert_eq!(text, "vpinball2d-test 2\nenabled on\nname North Pole\n");
+        assert_eq!(parse::<TestSettings>(&text).unwrap(), settings);
+    }
+
+    #[test]
+    fn skips_unknown_settings() {
+        let settings = parse::<TestSettings>("vpinball2d-test 2\nshiny on\nenabled on\n").unwrap();
+        assert!(settings.enabled);
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(matches!(
+            parse::<TestSettings>("vpinball2d-windows 2\n"),
+            Err(SettingsFileError::WrongHeader(_))
+        ));
+        assert!(matches!(
+            parse::<TestSettings>("vpinball2d-test 1\n"),
+            Err(SettingsFileError::UnsupportedVersion(_))
+        ));
+        assert!(matches!(
+            parse::<TestSettings>("vpinball2d-test 2\nenabled maybe\n"),
+            Err(SettingsFileError::Invalid { line: 2, .. })
+        ));
+    }
+}
@@ -2,6 +2,7 @@
 
 use crate::asset_tracking::LoadResource;
 use crate::pinball::TablePath;
+use crate::pinball::backglass_window::BackglassCamera;
 use crate::vpx::VpxAsset;
 use avian2d::prelude::*;
 use bevy::color::palettes::css;
@@ -32,7 +33,7 @@ pub(crate) fn table(
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<ColorMaterial>>,
     assets_vpx: &Res<Assets<VpxAsset>>,
-    camera_q: Query<(&Camera, &Projection), With<Camera2d>>,
+    camera_q: Query<(&Camera, &Projection), (With<Camera2d>, Without<BackglassCamera>)>,
 ) -> impl Bundle {
     let vpx_asset = assets_vpx.get(&table_assets.vpx).unwrap();
     let playfield_image = vpx_asset
@@ -116,6 +117,7 @@ pub(crate) fn table(
         children![
             (
                 Name::from("Backglass"),
+                TableBackglass,
                 Mesh2d(meshes.add(backglass_mesh)),
                 MeshMaterial2d(backglass_material),
                 Transform::from_xyz(0.0, 0.0, -20.0)
@@ -184,6 +186,10 @@ pub(crate) fn table(
 #[reflect(Component)]
 struct Table;
 
+/// The backglass image behind the table, hidden while the backglass has a window of its own.
+#[derive(Component, Debug)]
+pub(crate) struct TableBackglass;
+
 #[derive(Resource, Asset, Clone, Reflect)]
 #[reflect(Resource)]
 pub struct TableAssets {
@@ -1,3 +1,5 @@
+use crate::pinball::events::{GameItem, ItemKind};
+use crate::pinball::height::Z_IN_PLAYFIELD;
 use avian2d::prelude::{Collider, CollisionEventsEnabled, RigidBody, Sensor};
 use bevy::color::Color;
 use bevy::color::palettes::css;
@@ -34,11 +36,12 @@ pub(super) fn spawn_trigger(
         Trigger {
             name: trigger.name.clone(),
         },
+        GameItem::new(&trigger.name, ItemKind::Trigger),
         Name::from(format!("Trigger {}", trigger.name)),
         Transform::from_xyz(
             vpx_to_bevy_transform.translation.x + vpu_to_m(trigger.center.x),
             vpx_to_bevy_transform.translation.y - vpu_to_m(trigger.center.y),
-            10.0,
+            Z_IN_PLAYFIELD,
         ),
         Mesh2d(meshes.add(Annulus::new(radius - 0.001, radius))),
         MeshMaterial2d(materials.add(Color::from(TRIGGER_COLOR))),
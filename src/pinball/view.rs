@@ -0,0 +1,327 @@
+//! How the table is shown in the window: upright on a desktop monitor, turned a quarter for a
+//! cabinet with its portrait monitor lying on the side, or with the backglass above the
+//! playfield on a single screen. The view mode is one of the [`ViewSettings`], which are stored
+//! in a [settings file](super::settings_file).
+//!
+//! The camera can also zoom in and follow the balls, keeping all of them in view. It only moves
+//! once they leave a dead zone around the center, never shows more than the table, and zooms back
+//! out to the whole table when there is no ball in play.
+
+use crate::pinball::backglass::{BACKGLASS_HEIGHT, BACKGLASS_WIDTH};
+use crate::pinball::backglass_window::BackglassCamera;
+use crate::pinball::ball::Ball;
+use crate::pinball::settings_file::{self, StoredSettings, on_off, read_on_off};
+use crate::pinball::table::TableAssets;
+use crate::screens::Screen;
+use crate::vpx::VpxAsset;
+use crate::{AppSystems, PausableSystems};
+use bevy::camera::ScalingMode;
+use bevy::prelude::*;
+use std::f32::consts::FRAC_PI_2;
+use vpin::vpx::vpu_to_m;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_plugins(settings_file::plugin::<ViewSettings>);
+    app.add_systems(
+        Update,
+        fit_camera
+            .run_if(resource_changed::<ViewSettings>)
+            .in_set(AppSystems::Update)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    app.add_systems(
+        Update,
+        follow_balls
+            .after(fit_camera)
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// The part of the table the follow camera shows when zoomed in all the way.
+const FOLLOW_ZOOM: f32 = 0.5;
+/// How far the balls can move from the center before the camera follows, as part of the view.
+const FOLLOW_DEAD_ZONE: f32 = 0.15;
+/// The room kept around the balls when they spread out, as part of the whole table view.
+const FOLLOW_MARGIN: f32 = 0.25;
+/// How fast the camera catches up, higher is faster.
+const FOLLOW_SMOOTHING: f32 = 4.0;
+
+#[derive(Resource, Debug, Default)]
+pub struct ViewSettings {
+    pub view_mode: ViewMode,
+    /// Whether the camera zooms in and follows the balls.
+    pub follow_ball: bool,
+    /// Whether walls and bumpers cast soft shadows on the playfield.
+    pub drop_shadows: bool,
+}
+
+impl StoredSettings for ViewSettings {
+    const FILE_HEADER: &'static str = "vpinball2d-view";
+    const FILE_VERSION: u32 = 1;
+    const FILE_NAME: &'static str = "view.txt";
+
+    fn read(&mut self, key: &str, value: &str) -> Result<bool, String> {
+        match key {
+            "view-mode" => {
+                self.view_mode =
+                    ViewMode::from_name(value).ok_or_else(|| "unknown view mode".to_string())?;
+            }
+            "follow-ball" => self.follow_ball = read_on_off(value)?,
+            "drop-shadows" => self.drop_shadows = read_on_off(value)?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn write(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("view-mode", self.view_mode.name().to_string()),
+            ("follow-ball", on_off(self.follow_ball)),
+            ("drop-shadows", on_off(self.drop_shadows)),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ViewMode {
+    /// The playfield upright with the backglass beside it.
+    #[default]
+    Desktop,
+    /// The playfield turned a quarter clockwise, for a monitor turned a quarter counterclockwise.
+    Cabinet90,
+    /// The playfield turned a quarter counterclockwise, for a monitor turned a quarter clockwise.
+    Cabinet270,
+    /// The playfield upright with the backglass above it.
+    FullSingleScreen,
+}
+
+impl ViewMode {
+    const ALL: [ViewMode; 4] = [
+        ViewMode::Desktop,
+        ViewMode::Cabinet90,
+        ViewMode::Cabinet270,
+        ViewMode::FullSingleScreen,
+    ];
+
+    /// The name in the view settings file.
+    pub fn name(self) -> &'static str {
+        match self {
+            ViewMode::Desktop => "desktop",
+            ViewMode::Cabinet90 => "cabinet-90",
+            ViewMode::Cabinet270 => "cabinet-270",
+            ViewMode::FullSingleScreen => "full-single-screen",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+
+    /// The name shown in the settings menu.
+    pub fn label(self) -> &'static str {
+        match self {
+            ViewMode::Desktop => "Desktop",
+            ViewMode::Cabinet90 => "Cabinet 90°",
+            ViewMode::Cabinet270 => "Cabinet 270°",
+            ViewMode::FullSingleScreen => "Full Single Screen",
+        }
+    }
+
+    /// The next mode, wrapping around.
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|mode| *mode == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// The previous mode, wrapping around.
+    pub fn previous(self) -> Self {
+        let index = Self::ALL.iter().position(|mode| *mode == self).unwrap_or(0);
+        Self::ALL[(index + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+
+    /// Whether the playfield is turned on the screen.
+    pub fn rotated(self) -> bool {
+        matches!(self, ViewMode::Cabinet90 | ViewMode::Cabinet270)
+    }
+
+    /// Whether the camera can follow the balls. Not with the backglass above the playfield, as
+    /// that is laid out for the whole table.
+    pub fn can_follow(self) -> bool {
+        self != ViewMode::FullSingleScreen
+    }
+
+    /// The height of the backglass above the playfield, for a table of the given size.
+    fn backglass_height(self, table_size: Vec2) -> f32 {
+        match self {
+            ViewMode::FullSingleScreen => table_size.x * BACKGLASS_HEIGHT / BACKGLASS_WIDTH,
+            _ => 0.0,
+        }
+    }
+
+    /// The part of the world the camera has to show, in screen directions.
+    fn view_size(self, table_size: Vec2) -> Vec2 {
+        if self.rotated() {
+            table_size.yx()
+        } else {
+            table_size + Vec2::new(0.0, self.backglass_height(table_size))
+        }
+    }
+
+    /// The camera transform and scaling that fit a table of the given size, in meters.
+    pub fn camera(self, table_size: Vec2) -> (Transform, ScalingMode) {
+        let view_size = self.view_size(table_size);
+        let transform = match self {
+            ViewMode::Desktop => Transform::default(),
+            // turning the camera one way turns the picture the other way
+            ViewMode::Cabinet90 => Transform::from_rotation(Quat::from_rotation_z(FRAC_PI_2)),
+            ViewMode::Cabinet270 => Transform::from_rotation(Quat::from_rotation_z(-FRAC_PI_2)),
+            // the table stays at the origin, with the backglass above it
+            ViewMode::FullSingleScreen => {
+                Transform::from_xyz(0.0, self.backglass_height(table_size) / 2.0, 0.0)
+            }
+        };
+        let scaling = ScalingMode::AutoMin {
+            min_width: view_size.x,
+            min_height: view_size.y,
+        };
+        (transform, scaling)
+    }
+
+    /// The scale from table units to window pixels and the top left corner of the view.
+    fn fit(self, window_size: Vec2, table_size: Vec2) -> (f32, Vec2) {
+        let view_size = self.view_size(table_size);
+        let scale = (window_size / view_size).min_element();
+        (scale, (window_size - view_size * scale) / 2.0)
+    }
+
+    /// Where the playfield is in a window, in logical pixels. This uses the same fit as the
+    /// camera, the table size can be in any unit.
+    pub fn playfield_area(self, window_size: Vec2, table_size: Vec2) -> Rect {
+        let (scale, view_min) = self.fit(window_size, table_size);
+        let min = view_min + Vec2::new(0.0, self.backglass_height(table_size) * scale);
+        let size = if self.rotated() {
+            table_size.yx()
+        } else {
+            table_size
+        };
+        Rect::from_corners(min, min + size * scale)
+    }
+
+    /// Where the backglass is in a window, in logical pixels, when it is shown above the
+    /// playfield.
+    pub fn backglass_area(self, window_size: Vec2, table_size: Vec2) -> Option<Rect> {
+        let height = self.backglass_height(table_size);
+        (height > 0.0).then(|| {
+            let (scale, view_min) = self.fit(window_size, table_size);
+            Rect::from_corners(view_min, view_min + Vec2::new(table_size.x, height) * scale)
+        })
+    }
+}
+
+/// The size of the table in meters.
+fn table_size(vpx_asset: &VpxAsset) -> Vec2 {
+    let gamedata = &vpx_asset.raw.gamedata;
+    Vec2::new(
+        vpu_to_m(gamedata.right - gamedata.left),
+        vpu_to_m(gamedata.bottom - gamedata.top),
+    )
+}
+
+/// Fits the playfield camera to the table in the current view mode. While following the balls
+/// the position is left to [`follow_balls`].
+pub fn fit_camera(
+    mut cameras: Query<
+        (&mut Transform, &mut Projection),
+        (With<Camera2d>, Without<BackglassCamera>),
+    >,
+    settings: Res<ViewSettings>,
+    table_assets: Res<TableAssets>,
+    assets_vpx: Res<Assets<VpxAsset>>,
+) {
+    let Some(vpx_asset) = assets_vpx.get(&table_assets.vpx) else {
+        return;
+    };
+    let (camera_transform, scaling_mode) = settings.view_mode.camera(table_size(vpx_asset));
+    let following = settings.follow_ball && settings.view_mode.can_follow();
+    for (mut transform, mut projection) in &mut cameras {
+        if following {
+            transform.rotation = camera_transform.rotation;
+        } else {
+            transform.set_if_neq(camera_transform);
+        }
+        if let Projection::Orthographic(ortho) = &mut *projection {
+            ortho.scaling_mode = scaling_mode;
+        }
+    }
+}
+
+/// Zooms in on the balls and follows them, or eases back to the whole table.
+fn follow_balls(
+    time: Res<Time>,
+    settings: Res<ViewSettings>,
+    ball_query: Query<&GlobalTransform, With<Ball>>,
+    mut cameras: Query<
+        (&mut Transform, &mut Projection),
+        (With<Camera2d>, Without<BackglassCamera>),
+    >,
+    table_assets: Res<TableAssets>,
+    assets_vpx: Res<Assets<VpxAsset>>,
+) {
+    let Some(vpx_asset) = assets_vpx.get(&table_assets.vpx) else {
+        return;
+    };
+    let table_size = table_size(vpx_asset);
+    let (base, _) = settings.view_mode.camera(table_size);
+    let balls = ball_query
+        .iter()
+        .map(|transform| transform.translation().truncate())
+        .fold(None, |bounds: Option<Rect>, ball| {
+            Some(
+                bounds.map_or(Rect::from_center_size(ball, Vec2::ZERO), |bounds| {
+                    bounds.union_point(ball)
+                }),
+            )
+        })
+        .filter(|_| settings.follow_ball && settings.view_mode.can_follow());
+    let smoothing = 1.0 - (-FOLLOW_SMOOTHING * time.delta_secs()).exp();
+    for (mut transform, mut projection) in &mut cameras {
+        let Projection::Orthographic(ortho) = &mut *projection else {
+            continue;
+        };
+        let position = transform.translation.truncate();
+        let (target, target_scale) = match balls {
+            None => (base.translation.truncate(), 1.0),
+            Some(balls) => {
+                // the part of the world the whole view shows, turned like the camera
+                let view_size = ortho.area.size() / ortho.scale;
+                let full_view = (transform.rotation * view_size.extend(0.0))
+                    .truncate()
+                    .abs();
+                if full_view.min_element() <= 0.0 {
+                    // the projection has not been sized yet
+                    continue;
+                }
+                let scale = (balls.size() / full_view + FOLLOW_MARGIN)
+                    .max_element()
+                    .clamp(FOLLOW_ZOOM, 1.0);
+                let half_view = full_view * scale / 2.0;
+                let dead_zone = half_view * FOLLOW_DEAD_ZONE;
+                let center = balls.center();
+                let target =
+                    position + center - center.clamp(position - dead_zone, position + dead_zone);
+                // the view stays on the table, centered when it is larger than the table
+                let limit = (table_size / 2.0 - half_view).max(Vec2::ZERO);
+                (target.clamp(-limit, limit), scale)
+            }
+        };
+        if position.distance(target) > f32::EPSILON {
+            let position = position.lerp(target, smoothing);
+            transform.translation = position.extend(transform.translation.z);
+        }
+        if (ortho.scale - target_scale).abs() > f32::EPSILON {
+            ortho.scale = ortho.scale.lerp(target_scale, smoothing);
+        }
+    }
+}
@@ -1,14 +1,13 @@
-use crate::pinball::ball::BALL_RADIUS_M;
-use crate::vpx::VpxAsset;
+use crate::pinball::events::{GameItem, ItemKind};
+use crate::pinball::height::{ItemHeight, ShadowMaterial, drop_shadow, item_z};
+use crate::vpx::{MaterialKey, VpxAsset};
 use avian2d::math::Vector;
 
 use avian2d::prelude::*;
-use bevy::color::palettes::css;
 use bevy::ecs::relationship::RelatedSpawnerCommands;
-use bevy::math::Affine2;
 use bevy::prelude::*;
-use bevy::sprite_render::AlphaMode2d;
 use vpin::vpx::gameitem::wall;
+use vpin::vpx::vpu_to_m;
 
 #[derive(Component)]
 pub struct Wall {
@@ -18,7 +17,7 @@ pub struct Wall {
 pub(super) fn spawn_wall(
     parent: &mut RelatedSpawnerCommands<ChildOf>,
     meshes: &ResMut<Assets<Mesh>>,
-    materials: &mut ResMut<Assets<ColorMaterial>>,
+    shadow_material: &ShadowMaterial,
     vpx_asset: &VpxAsset,
     vpx_to_bevy_transform: Transform,
     wall: &wall::Wall,
@@ -27,61 +26,62 @@ pub(super) fn spawn_wall(
         .named_meshes
         .get(VpxAsset::wall_mesh_sub_path(&wall.name).as_str())
         .unwrap();
-    //let color = css::PINK;
-    let top_material = vpx_asset
-        .raw
-        .gamedata
-        .materials
-        .iter()
-        .flatten()
-        .find(|m| m.name == wall.top_material);
-    let color = if let Some(mat) = top_material {
-        Srgba::rgb_u8(mat.base_color.r, mat.base_color.g, mat.base_color.b)
+    // hidden walls are shown see-through, the loader made a material for every wall
+    let material_key = if !wall.is_top_bottom_visible && !wall.is_side_visible {
+        MaterialKey::translucent(&wall.top_material, &wall.image)
     } else {
-        css::PINK
-    };
-    let texture = vpx_asset.named_images.get(wall.image.as_str()).cloned();
-    let mut mat = ColorMaterial {
-        color: color.into(),
-        alpha_mode: AlphaMode2d::Opaque,
-        texture,
-        // TODO adjust UV scale properly, how doe vpinball do this?
-        uv_transform: Affine2::from_scale(Vec2::splat(0.01)),
+        MaterialKey::plain(&wall.top_material, &wall.image)
     };
-    if !wall.is_top_bottom_visible && !wall.is_side_visible {
-        mat.alpha_mode = AlphaMode2d::Blend;
-        mat.color = color.with_alpha(0.5).into();
-    }
-    let material = materials.add(mat);
+    let material = vpx_asset
+        .named_materials
+        .get(&material_key)
+        .unwrap()
+        .clone();
     let name_component = Name::from(format!("Wall {}", wall.name));
     let wall_component = Wall {
         name: wall.name.clone(),
     };
-    // A wall above the ball height is just visual
-    // A wall that is below the playfield can't collide with the ball
-    //   one example is the hole for the trigger wire where there is a bottom wall and the sides walls that reach to playfield
-    if wall.is_collidable && wall.height_bottom < BALL_RADIUS_M * 2.0 && wall.height_top > 0.0 {
+    let item_component = GameItem::new(&wall.name, ItemKind::Wall);
+    let top = vpu_to_m(wall.height_top);
+    let z = item_z(top);
+    let transform = Transform {
+        translation: vpx_to_bevy_transform.translation.with_z(z),
+        ..vpx_to_bevy_transform
+    };
+    // Balls only hit the wall where their height overlaps it, a wall below the playfield is never
+    //   hit, one example is the hole for the trigger wire where there is a bottom wall and the sides
+    //   walls that reach to playfield
+    let mut entity = if wall.is_collidable {
         let mesh = meshes.get(mesh_handle).unwrap();
         let collider = mesh_collider(mesh);
         parent.spawn((
             name_component,
             wall_component,
+            item_component,
             Mesh2d(mesh_handle.clone()),
             MeshMaterial2d(material),
-            vpx_to_bevy_transform,
+            transform,
             RigidBody::Static,
+            ItemHeight {
+                bottom: vpu_to_m(wall.height_bottom),
+                top,
+            },
             Restitution::from(wall.elasticity),
             Friction::from(wall.friction),
             collider,
-        ));
+        ))
     } else {
         parent.spawn((
             name_component,
             wall_component,
+            item_component,
             Mesh2d(mesh_handle.clone()),
             MeshMaterial2d(material),
-            vpx_to_bevy_transform,
-        ));
+            transform,
+        ))
+    };
+    if wall.is_top_bottom_visible && top > 0.0 {
+        entity.with_child(drop_shadow(mesh_handle.clone(), shadow_material, top, z));
     }
 }
 
@@ -1,11 +1,10 @@
 //! The screen state for the main gameplay.
 
-use crate::pinball::table::TableAssets;
-use crate::vpx::VpxAsset;
+use crate::input::{InputAction, action_just_pressed};
+use crate::pinball::view::fit_camera;
 use crate::{Pause, menus::Menu, pinball::level::spawn_level, screens::Screen};
 use avian2d::prelude::*;
 use bevy::{input::common_conditions::input_just_pressed, prelude::*};
-use vpin::vpx::vpu_to_m;
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(
@@ -16,19 +15,19 @@ pub(super) fn plugin(app: &mut App) {
 
     // we need to first spawn entities before we can run the script that starts on Screen::Gameplay
 
-    // Toggle pause on key press.
+    // Toggle pause on the pause action, escape only pauses as the menus handle it themselves.
     app.add_systems(
         Update,
         (
             (pause, spawn_pause_overlay, open_pause_menu).run_if(
-                in_state(Screen::Gameplay)
-                    .and(in_state(Menu::None))
-                    .and(input_just_pressed(KeyCode::KeyP).or(input_just_pressed(KeyCode::Escape))),
+                in_state(Screen::Gameplay).and(in_state(Menu::None)).and(
+                    action_just_pressed(InputAction::Pause).or(input_just_pressed(KeyCode::Escape)),
+                ),
             ),
             close_menu.run_if(
                 in_state(Screen::Gameplay)
                     .and(not(in_state(Menu::None)))
-                    .and(input_just_pressed(KeyCode::KeyP)),
+                    .and(action_just_pressed(InputAction::Pause)),
             ),
         ),
     );
@@ -74,21 +73,3 @@ fn open_pause_menu(mut next_menu: ResMut<NextState<Menu>>) {
 fn close_menu(mut next_menu: ResMut<NextState<Menu>>) {
     next_menu.set(Menu::None);
 }
-
-fn fit_camera(
-    mut cameras: Query<&mut Projection, With<Camera2d>>,
-    table_assets: Res<TableAssets>,
-    assets_vpx: Res<Assets<VpxAsset>>,
-) {
-    let vpx_asset = assets_vpx.get(&table_assets.vpx).unwrap();
-    let table_width_m = vpu_to_m(vpx_asset.raw.gamedata.right - vpx_asset.raw.gamedata.left);
-    let table_depth_m = vpu_to_m(vpx_asset.raw.gamedata.bottom - vpx_asset.raw.gamedata.top);
-    for mut projection in &mut cameras {
-        if let Projection::Orthographic(ortho) = &mut *projection {
-            ortho.scaling_mode = bevy::camera::ScalingMode::AutoMin {
-                min_height: table_depth_m,
-                min_width: table_width_m,
-            };
-        }
-    }
-}
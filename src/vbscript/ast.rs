@@ -0,0 +1,172 @@
+//! The syntax tree of the supported VBScript subset.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Str(String),
+    Bool(bool),
+    Empty,
+    Nothing,
+    Ident(String),
+    /// `object.name`
+    Member(Box<Expr>, String),
+    /// `name(args)`, which is either a call or an array index.
+    Call(Box<Expr>, Vec<Expr>),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Pow,
+    Mul,
+    Div,
+    IntDiv,
+    Mod,
+    Add,
+    Sub,
+    Concat,
+    Eq,
+    Ne,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    And,
+    Or,
+    Xor,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    pub line: usize,
+    pub kind: StmtKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StmtKind {
+    /// `Dim a, b(10)`, also used for `Private` and `Public` variables.
+    Dim(Vec<(String, Option<Vec<Expr>>)>),
+    /// `ReDim a(10)`, keeping the elements that fit with `ReDim Preserve`.
+    ReDim {
+        preserve: bool,
+        variables: Vec<(String, Vec<Expr>)>,
+    },
+    Const(Vec<(String, Expr)>),
+    /// `target = value`, or `Set target = value`.
+    Assign(Expr, Expr),
+    /// `name args` or `Call name(args)`.
+    Call(Expr, Vec<Expr>),
+    If {
+        branches: Vec<(Expr, Vec<Statement>)>,
+        otherwise: Vec<Statement>,
+    },
+    Select {
+        subject: Expr,
+        cases: Vec<(Vec<Expr>, Vec<Statement>)>,
+        otherwise: Vec<Statement>,
+    },
+    For {
+        variable: String,
+        from: Expr,
+        to: Expr,
+        step: Option<Expr>,
+        body: Vec<Statement>,
+    },
+    /// `Do While`, `Do Until`, `Loop While`, `Loop Until` and `While ... Wend`.
+    Loop {
+        condition: Option<LoopCondition>,
+        body: Vec<Statement>,
+    },
+    Exit(ExitKind),
+    /// `On Error Resume Next`, or `On Error GoTo 0` to stop resuming.
+    OnError {
+        resume_next: bool,
+    },
+    /// A statement that has no effect, like `Option Explicit`.
+    Nop,
+    /// A construct the interpreter does not support, reported when it is executed.
+    Unsupported(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoopCondition {
+    pub expr: Expr,
+    /// `Until` instead of `While`.
+    pub until: bool,
+    /// Checked before instead of after each iteration.
+    pub before: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitKind {
+    Sub,
+    Function,
+    For,
+    Do,
+}
+
+/// A `Sub` or `Function`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Procedure {
+    pub name: String,
+    pub params: Vec<String>,
+    pub body: Vec<Statement>,
+    pub line: usize,
+}
+
+/// A parsed script.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Program {
+    /// The top level statements, run once when the script starts.
+    pub statements: Vec<Statement>,
+    pub procedures: Vec<Procedure>,
+}
+
+impl Program {
+    /// All unsupported constructs with their line numbers.
+    pub fn unsupported(&self) -> Vec<(usize, &str)> {
+        let mut found = Vec::new();
+        collect_unsupported(&self.statements, &mut found);
+        for procedure in &self.procedures {
+            collect_unsupported(&procedure.body, &mut found);
+        }
+        found.sort_by_key(|(line, _)| *line);
+        found
+    }
+}
+
+fn collect_unsupported<'a>(statements: &'a [Statement], found: &mut Vec<(usize, &'a str)>) {
+    for statement in statements {
+        match &statement.kind {
+            StmtKind::Unsupported(message) => found.push((statement.line, message)),
+            StmtKind::If {
+                branches,
+                otherwise,
+            } => {
+                for (_, body) in branches {
+                    collect_unsupported(body, found);
+                }
+                collect_unsupported(otherwise, found);
+            }
+            StmtKind::Select {
+                cases, otherwise, ..
+            } => {
+                for (_, body) in cases {
+                    collect_unsupported(body, found);
+                }
+                collect_unsupported(otherwise, found);
+            }
+            StmtKind::For { body, .. } | StmtKind::Loop { body, .. } => {
+                collect_unsupported(body, found)
+            }
+            _ => {}
+        }
+    }
+}
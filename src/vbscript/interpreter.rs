@@ -0,0 +1,824 @@
+//! Runs a parsed [`Program`] against a [`Host`] that provides the table items.
+//!
+//! Arguments are always passed by value and arrays have one dimension. Runaway loops and
+//! recursion are stopped with an error instead of freezing the game, even after
+//! `On Error Resume Next`.
+
+use crate::vbscript::ast::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Maximum number of statements executed by a single call into the script.
+const MAX_STEPS: usize = 1_000_000;
+const MAX_CALL_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Empty,
+    Nothing,
+    Bool(bool),
+    Number(f64),
+    Str(String),
+    /// A table item, referenced by name.
+    Item(String),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    pub fn to_number(&self) -> Result<f64, String> {
+        match self {
+            Value::Empty => Ok(0.0),
+            // VBScript True is -1
+            Value::Bool(b) => Ok(if *b { -1.0 } else { 0.0 }),
+            Value::Number(n) => Ok(*n),
+            Value::Str(s) => s
+                .trim()
+                .parse()
+                .map_err(|_| format!("type mismatch: \"{s}\" is not a number")),
+            other => Err(format!("type mismatch: {other} is not a number")),
+        }
+    }
+
+    pub fn to_bool(&self) -> Result<bool, String> {
+        match self {
+            Value::Bool(b) => Ok(*b),
+            Value::Str(s) if s.eq_ignore_ascii_case("true") => Ok(true),
+            Value::Str(s) if s.eq_ignore_ascii_case("false") => Ok(false),
+            other => Ok(other.to_number()? != 0.0),
+        }
+    }
+
+    pub fn as_item(&self) -> Option<&str> {
+        match self {
+            Value::Item(name) => Some(name),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Empty => Ok(()),
+            Value::Nothing => write!(f, "Nothing"),
+            Value::Bool(true) => write!(f, "True"),
+            Value::Bool(false) => write!(f, "False"),
+            Value::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+            Value::Number(n) => write!(f, "{n}"),
+            Value::Str(s) => write!(f, "{s}"),
+            Value::Item(name) => write!(f, "{name}"),
+            Value::Array(_) => write!(f, "Array"),
+        }
+    }
+}
+
+/// Provides the table items and global procedures like `PlaySound` to a script.
+pub trait Host {
+    fn is_item(&self, name: &str) -> bool;
+    /// Calls a global procedure, `None` if the host doesn't know it.
+    fn call(&mut self, name: &str, args: &[Value]) -> Option<Result<Value, String>>;
+    fn get_property(&mut self, item: &str, property: &str) -> Result<Value, String>;
+    fn set_property(&mut self, item: &str, property: &str, value: Value) -> Result<(), String>;
+    fn call_method(&mut self, item: &str, method: &str, args: &[Value]) -> Result<Value, String>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+type RunResult<T> = Result<T, String>;
+
+enum Flow {
+    Normal,
+    Exit(ExitKind),
+}
+
+struct Frame {
+    /// The lowercase name of the procedure, which doubles as the return value variable.
+    procedure: String,
+    locals: HashMap<String, Value>,
+    /// Set by `On Error Resume Next` in the procedure.
+    resume_next: bool,
+}
+
+pub struct Interpreter {
+    statements: Vec<Statement>,
+    procedures: HashMap<String, Arc<Procedure>>,
+    globals: HashMap<String, Value>,
+    frames: Vec<Frame>,
+    /// Set by `On Error Resume Next` in the top level statements.
+    resume_next: bool,
+    steps: usize,
+    /// The step limit was reached, which stops the script even when it resumes on errors.
+    aborted: bool,
+    line: usize,
+}
+
+impl Interpreter {
+    pub fn new(program: Program) -> Self {
+        Self {
+            statements: program.statements,
+            procedures: program
+                .procedures
+                .into_iter()
+                .map(|procedure| (procedure.name.to_lowercase(), Arc::new(procedure)))
+                .collect(),
+            globals: HashMap::new(),
+            frames: Vec::new(),
+            resume_next: false,
+            steps: 0,
+            aborted: false,
+            line: 0,
+        }
+    }
+
+    /// Runs the top level statements of the script.
+    pub fn run(&mut self, host: &mut dyn Host) -> Result<(), RuntimeError> {
+        let statements = std::mem::take(&mut self.statements);
+        self.steps = 0;
+        self.aborted = false;
+        let result = self.exec_block(&statements, host).map(|_| ());
+        self.statements = statements;
+        result.map_err(|message| self.error(message))
+    }
+
+    pub fn has_procedure(&self, name: &str) -> bool {
+        self.procedures.contains_key(&name.to_lowercase())
+    }
+
+    /// Calls a procedure of the script, does nothing if it doesn't exist.
+    pub fn call(
+        &mut self,
+        name: &str,
+        args: Vec<Value>,
+        host: &mut dyn Host,
+    ) -> Result<Option<Value>, RuntimeError> {
+        let Some(procedure) = self.procedures.get(&name.to_lowercase()).cloned() else {
+            return Ok(None);
+        };
+        self.steps = 0;
+        self.aborted = false;
+        let result = self.call_procedure(&procedure, args, host);
+        self.frames.clear();
+        result.map(Some).map_err(|message| self.error(message))
+    }
+
+    fn error(&self, message: String) -> RuntimeError {
+        RuntimeError {
+            line: self.line,
+            message,
+        }
+    }
+
+    fn call_procedure(
+        &mut self,
+        procedure: &Procedure,
+        args: Vec<Value>,
+        host: &mut dyn Host,
+    ) -> RunResult<Value> {
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            return Err(format!("calls nested too deep in '{}'", procedure.name));
+        }
+        if args.len() > procedure.params.len() {
+            return Err(format!(
+                "'{}' takes {} arguments, got {}",
+                procedure.name,
+                procedure.params.len(),
+                args.len()
+            ));
+        }
+        let mut locals: HashMap<String, Value> = procedure
+            .params
+            .iter()
+            .map(|param| (param.to_lowercase(), Value::Empty))
+            .collect();
+        for (param, arg) in procedure.params.iter().zip(args) {
+            locals.insert(param.to_lowercase(), arg);
+        }
+        let name = procedure.name.to_lowercase();
+        self.frames.push(Frame {
+            procedure: name.clone(),
+            locals,
+            resume_next: false,
+        });
+        let caller_line = self.line;
+        self.line = procedure.line;
+        let result = self.exec_block(&procedure.body, host);
+        let frame = self.frames.pop().expect("frame pushed above");
+        result?;
+        self.line = caller_line;
+        Ok(frame.locals.get(&name).cloned().unwrap_or(Value::Empty))
+    }
+
+    fn exec_block(&mut self, statements: &[Statement], host: &mut dyn Host) -> RunResult<Flow> {
+        for statement in statements {
+            match self.exec(statement, host) {
+                Ok(Flow::Exit(kind)) => return Ok(Flow::Exit(kind)),
+                Ok(Flow::Normal) => {}
+                // continue with the statement after the one that failed
+                Err(_) if self.resumes_next() => {}
+                Err(message) => return Err(message),
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    /// Whether errors are ignored in the current scope, see [`StmtKind::OnError`].
+    fn resumes_next(&self) -> bool {
+        !self.aborted
+            && self
+                .frames
+                .last()
+                .map_or(self.resume_next, |frame| frame.resume_next)
+    }
+
+    /// Counts an executed statement or loop iteration against [`MAX_STEPS`].
+    fn step(&mut self) -> RunResult<()> {
+        self.steps += 1;
+        if self.steps > MAX_STEPS {
+            self.aborted = true;
+            return Err("script takes too long, is there an endless loop?".to_string());
+        }
+        Ok(())
+    }
+
+    fn exec(&mut self, statement: &Statement, host: &mut dyn Host) -> RunResult<Flow> {
+        self.line = statement.line;
+        self.step()?;
+        match &statement.kind {
+            StmtKind::Dim(variables) => {
+                for (name, bounds) in variables {
+                    let value = match bounds.as_deref() {
+                        None | Some([]) => Value::Empty,
+                        Some([upper]) => {
+                            let upper = self.eval(upper, host)?.to_number()?;
+                            Value::Array(vec![Value::Empty; array_len(upper)])
+                        }
+                        Some(_) => return Err("multidimensional arrays are not supported".into()),
+                    };
+                    self.declare(name, value);
+                }
+            }
+            StmtKind::ReDim {
+                preserve,
+                variables,
+            } => {
+                for (name, bounds) in variables {
+                    let [upper] = bounds.as_slice() else {
+                        return Err("multidimensional arrays are not supported".into());
+                    };
+                    let upper = self.eval(upper, host)?.to_number()?;
+                    let mut array = match self.lookup(name) {
+                        Some(Value::Array(array)) if *preserve => array.clone(),
+                        _ => Vec::new(),
+                    };
+                    array.resize(array_len(upper), Value::Empty);
+                    self.set_variable(name, Value::Array(array));
+                }
+            }
+            StmtKind::Const(constants) => {
+                for (name, expr) in constants {
+                    let value = self.eval(expr, host)?;
+                    self.declare(name, value);
+                }
+            }
+            StmtKind::Assign(target, expr) => {
+                let value = self.eval(expr, host)?;
+                self.assign(target, value, host)?;
+            }
+            StmtKind::Call(callee, args) => {
+                let args = self.eval_all(args, host)?;
+                self.invoke(callee, args, host)?;
+            }
+            StmtKind::If {
+                branches,
+                otherwise,
+            } => {
+                for (condition, body) in branches {
+                    if self.eval(condition, host)?.to_bool()? {
+                        return self.exec_block(body, host);
+                    }
+                }
+                return self.exec_block(otherwise, host);
+            }
+            StmtKind::Select {
+                subject,
+                cases,
+                otherwise,
+            } => {
+                let subject = self.eval(subject, host)?;
+                for (values, body) in cases {
+                    for value in values {
+                        let value = self.eval(value, host)?;
+                        if compare(&subject, &value)? == Some(std::cmp::Ordering::Equal) {
+                            return self.exec_block(body, host);
+                        }
+                    }
+                }
+                return self.exec_block(otherwise, host);
+            }
+            StmtKind::For {
+                variable,
+                from,
+                to,
+                step,
+                body,
+            } => {
+                let mut counter = self.eval(from, host)?.to_number()?;
+                let to = self.eval(to, host)?.to_number()?;
+                let step = match step {
+                    Some(step) => self.eval(step, host)?.to_number()?,
+                    None => 1.0,
+                };
+                while (step >= 0.0 && counter <= to) || (step < 0.0 && counter >= to) {
+                    self.set_variable(variable, Value::Number(counter));
+                    match self.exec_block(body, host)? {
+                        Flow::Exit(ExitKind::For) => break,
+                        Flow::Exit(kind) => return Ok(Flow::Exit(kind)),
+                        Flow::Normal => {}
+                    }
+                    self.step()?;
+                    counter = self.get_variable(variable).to_number()? + step;
+                }
+                self.set_variable(variable, Value::Number(counter));
+            }
+            StmtKind::Loop { condition, body } => loop {
+                if let Some(condition) = condition
+                    && condition.before
+                    && !self.loop_continues(condition, host)?
+                {
+                    break;
+                }
+                match self.exec_block(body, host)? {
+                    Flow::Exit(ExitKind::Do) => break,
+                    Flow::Exit(kind) => return Ok(Flow::Exit(kind)),
+                    Flow::Normal => {}
+                }
+                self.step()?;
+                if let Some(condition) = condition
+                    && !condition.before
+                    && !self.loop_continues(condition, host)?
+                {
+                    break;
+                }
+            },
+            StmtKind::Exit(kind) => return Ok(Flow::Exit(*kind)),
+            StmtKind::OnError { resume_next } => match self.frames.last_mut() {
+                Some(frame) => frame.resume_next = *resume_next,
+                None => self.resume_next = *resume_next,
+            },
+            StmtKind::Nop => {}
+            StmtKind::Unsupported(message) => return Err(format!("unsupported: {message}")),
+        }
+        Ok(Flow::Normal)
+    }
+
+    fn loop_continues(
+        &mut self,
+        condition: &LoopCondition,
+        host: &mut dyn Host,
+    ) -> RunResult<bool> {
+        let value = self.eval(&condition.expr, host)?.to_bool()?;
+        Ok(value != condition.until)
+    }
+
+    fn declare(&mut self, name: &str, value: Value) {
+        let name = name.to_lowercase();
+        match self.frames.last_mut() {
+            Some(frame) => frame.locals.insert(name, value),
+            None => self.globals.insert(name, value),
+        };
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Value> {
+        let name = name.to_lowercase();
+        self.frames
+            .last()
+            .and_then(|frame| frame.locals.get(&name))
+            .or_else(|| self.globals.get(&name))
+    }
+
+    fn get_variable(&self, name: &str) -> Value {
+        self.lookup(name).cloned().unwrap_or(Value::Empty)
+    }
+
+    /// Assigns a variable, declaring it in the current scope if it doesn't exist yet.
+    fn set_variable(&mut self, name: &str, value: Value) {
+        let name = name.to_lowercase();
+        if let Some(frame) = self.frames.last_mut()
+            && (frame.locals.contains_key(&name)
+                || frame.procedure == name
+                || !self.globals.contains_key(&name))
+        {
+            frame.locals.insert(name, value);
+        } else {
+            self.globals.insert(name, value);
+        }
+    }
+
+    fn assign(&mut self, target: &Expr, value: Value, host: &mut dyn Host) -> RunResult<()> {
+        match target {
+            Expr::Ident(name) => {
+                if self.lookup(name).is_none() && host.is_item(name) {
+                    return Err(format!("can't assign to table item '{name}'"));
+                }
+                self.set_variable(name, value);
+                Ok(())
+            }
+            Expr::Call(callee, indices) if matches!(**callee, Expr::Ident(_)) => {
+                let Expr::Ident(name) = &**callee else {
+                    unreachable!()
+                };
+                let [index] = indices.as_slice() else {
+                    return Err(format!("'{name}' needs exactly one index"));
+                };
+                let index = self.eval(index, host)?.to_number()?;
+                let mut array = match self.lookup(name) {
+                    Some(Value::Array(array)) => array.clone(),
+                    _ => return Err(format!("'{name}' is not an array")),
+                };
+                let element = array
+                    .get_mut(index as usize)
+                    .filter(|_| index >= 0.0)
+                    .ok_or_else(|| format!("index {index} out of range for '{name}'"))?;
+                *element = value;
+                self.set_variable(name, Value::Array(array));
+                Ok(())
+            }
+            Expr::Member(object, property) => {
+                let object = self.eval(object, host)?;
+                let Some(item) = object.as_item() else {
+                    return Err(format!("can't set '{property}' on {object}"));
+                };
+                host.set_property(item, property, value)
+            }
+            _ => Err("invalid assignment target".to_string()),
+        }
+    }
+
+    fn eval_all(&mut self, exprs: &[Expr], host: &mut dyn Host) -> RunResult<Vec<Value>> {
+        exprs.iter().map(|expr| self.eval(expr, host)).collect()
+    }
+
+    fn eval(&mut self, expr: &Expr, host: &mut dyn Host) -> RunResult<Value> {
+        match expr {
+            Expr::Number(n) => Ok(Value::Number(*n)),
+            Expr::Str(s) => Ok(Value::Str(s.clone())),
+            Expr::Bool(b) => Ok(Value::Bool(*b)),
+            Expr::Empty => Ok(Value::Empty),
+            Expr::Nothing => Ok(Value::Nothing),
+            Expr::Ident(name) => {
+                if let Some(value) = self.lookup(name) {
+                    return Ok(value.clone());
+                }
+                if let Some(value) = constant(name) {
+                    return Ok(value);
+                }
+                if host.is_item(name) {
+                    return Ok(Value::Item(name.clone()));
+                }
+                // functions can be called without parentheses, undeclared variables are Empty
+                match self.invoke(expr, Vec::new(), host) {
+                    Err(message) if message.starts_with("unknown procedure") => Ok(Value::Empty),
+                    result => result,
+                }
+            }
+            Expr::Member(object, property) => {
+                let object = self.eval(object, host)?;
+                match object.as_item() {
+                    Some(item) => host.get_property(item, property),
+                    None => Err(format!("can't get '{property}' of {object}")),
+                }
+            }
+            Expr::Call(callee, args) => {
+                let args = self.eval_all(args, host)?;
+                self.invoke(callee, args, host)
+            }
+            Expr::Unary(op, operand) => {
+                let value = self.eval(operand, host)?;
+                match op {
+                    UnaryOp::Neg => Ok(Value::Number(-value.to_number()?)),
+                    UnaryOp::Not => match value {
+                        Value::Bool(b) => Ok(Value::Bool(!b)),
+                        other => Ok(Value::Number(!(other.to_number()? as i64) as f64)),
+                    },
+                }
+            }
+            Expr::Binary(op, left, right) => {
+                let left = self.eval(left, host)?;
+                let right = self.eval(right, host)?;
+                binary(*op, &left, &right)
+            }
+        }
+    }
+
+    fn invoke(&mut self, callee: &Expr, args: Vec<Value>, host: &mut dyn Host) -> RunResult<Value> {
+        match callee {
+            Expr::Ident(name) => {
+                if let Some(Value::Array(array)) = self.lookup(name) {
+                    let [index] = args.as_slice() else {
+                        return Err(format!("'{name}' needs exactly one index"));
+                    };
+                    let index = index.to_number()?;
+                    return array
+                        .get(index as usize)
+                        .filter(|_| index >= 0.0)
+                        .cloned()
+                        .ok_or_else(|| format!("index {index} out of range for '{name}'"));
+                }
+                if let Some(procedure) = self.procedures.get(&name.to_lowercase()).cloned() {
+                    return self.call_procedure(&procedure, args, host);
+                }
+                if let Some(result) = builtin(name, &args) {
+                    return result;
+                }
+                if let Some(result) = host.call(name, &args) {
+                    return result;
+                }
+                Err(format!("unknown procedure '{name}'"))
+            }
+            Expr::Member(object, method) => {
+                let object = self.eval(object, host)?;
+                match object.as_item() {
+                    Some(item) => host.call_method(item, method, &args),
+                    None => Err(format!("can't call '{method}' on {object}")),
+                }
+            }
+            _ => Err("invalid call".to_string()),
+        }
+    }
+}
+
+/// The length of an array with the given upper bound, arrays start at 0 and `Dim a(-1)` is empty.
+fn array_len(upper: f64) -> usize {
+    (upper.max(-1.0) + 1.0) as usize
+}
+
+fn constant(name: &str) -> Option<Value> {
+    let value = match name.to_lowercase().as_str() {
+        "lightstateoff" => Value::Number(0.0),
+        "lightstateon" => Value::Number(1.0),
+        "lightstateblinking" => Value::Number(2.0),
+        "vbnewline" | "vbcrlf" => Value::Str("\r\n".to_string()),
+        "vbtrue" => Value::Number(-1.0),
+        "vbfalse" => Value::Number(0.0),
+        _ => return None,
+    };
+    Some(value)
+}
+
+fn builtin(name: &str, args: &[Value]) -> Option<RunResult<Value>> {
+    let arg = |index: usize| args.get(index).cloned().unwrap_or(Value::Empty);
+    let number = |index: usize| arg(index).to_number();
+    let text = |index: usize| arg(index).to_string();
+    let result = (|| {
+        Ok(match name.to_lowercase().as_str() {
+            "rnd" => Value::Number(rand::random::<f64>()),
+            "int" => Value::Number(number(0)?.floor()),
+            "fix" => Value::Number(number(0)?.trunc()),
+            "abs" => Value::Number(number(0)?.abs()),
+            "sgn" => Value::Number(number(0)?.signum()),
+            "sqr" => Value::Number(number(0)?.sqrt()),
+            "round" => Value::Number(number(0)?.round()),
+            "cint" | "clng" => Value::Number(number(0)?.round()),
+            "cdbl" | "csng" => Value::Number(number(0)?),
+            "cbool" => Value::Bool(arg(0).to_bool()?),
+            "cstr" => Value::Str(text(0)),
+            "len" => Value::Number(text(0).chars().count() as f64),
+            "ucase" => Value::Str(text(0).to_uppercase()),
+            "lcase" => Value::Str(text(0).to_lowercase()),
+            "left" => Value::Str(text(0).chars().take(number(1)? as usize).collect()),
+            "right" => {
+                let text = text(0);
+                let count = text.chars().count();
+                let skip = count.saturating_sub(number(1)? as usize);
+                Value::Str(text.chars().skip(skip).collect())
+            }
+            "mid" => {
+                let start = (number(1)? as usize).max(1) - 1;
+                let text = text(0);
+                let chars = text.chars().skip(start);
+                match args.get(2) {
+                    Some(len) => Value::Str(chars.take(len.to_number()? as usize).collect()),
+                    None => Value::Str(chars.collect()),
+                }
+            }
+            "chr" => Value::Str(
+                char::from_u32(number(0)? as u32)
+                    .map(String::from)
+                    .unwrap_or_default(),
+            ),
+            "isempty" => Value::Bool(arg(0) == Value::Empty),
+            "ubound" => match arg(0) {
+                Value::Array(array) => Value::Number(array.len() as f64 - 1.0),
+                other => return Err(format!("UBound needs an array, got {other}")),
+            },
+            _ => return Err(String::new()),
+        })
+    })();
+    match result {
+        Err(message) if message.is_empty() => None,
+        result => Some(result),
+    }
+}
+
+fn compare(left: &Value, right: &Value) -> RunResult<Option<std::cmp::Ordering>> {
+    Ok(match (left, right) {
+        (Value::Str(a), Value::Str(b)) => Some(a.cmp(b)),
+        (Value::Item(a), Value::Item(b)) => {
+            (a.eq_ignore_ascii_case(b)).then_some(std::cmp::Ordering::Equal)
+        }
+        (Value::Nothing, Value::Nothing) => Some(std::cmp::Ordering::Equal),
+        (Value::Nothing | Value::Item(_), _) | (_, Value::Nothing | Value::Item(_)) => None,
+        (Value::Str(_), Value::Empty) | (Value::Empty, Value::Str(_)) => {
+            Some(left.to_string().cmp(&right.to_string()))
+        }
+        _ => left.to_number()?.partial_cmp(&right.to_number()?),
+    })
+}
+
+fn binary(op: BinaryOp, left: &Value, right: &Value) -> RunResult<Value> {
+    use std::cmp::Ordering;
+    let logical = |f: fn(bool, bool) -> bool, g: fn(i64, i64) -> i64| -> RunResult<Value> {
+        match (left, right) {
+            (Value::Bool(a), Value::Bool(b)) => Ok(Value::Bool(f(*a, *b))),
+            _ => Ok(Value::Number(
+                g(left.to_number()? as i64, right.to_number()? as i64) as f64,
+            )),
+        }
+    };
+    Ok(match op {
+        BinaryOp::Add => match (left, right) {
+            (Value::Str(a), Value::Str(b)) => Value::Str(format!("{a}{b}")),
+            _ => Value::Number(left.to_number()? + right.to_number()?),
+        },
+        BinaryOp::Sub => Value::Number(left.to_number()? - right.to_number()?),
+        BinaryOp::Mul => Value::Number(left.to_number()? * right.to_number()?),
+        BinaryOp::Div => {
+            let divisor = right.to_number()?;
+            if divisor == 0.0 {
+                return Err("division by zero".to_string());
+            }
+            Value::Number(left.to_number()? / divisor)
+        }
+        BinaryOp::IntDiv | BinaryOp::Mod => {
+            let dividend = left.to_number()?.round() as i64;
+            let divisor = right.to_number()?.round() as i64;
+            if divisor == 0 {
+                return Err("division by zero".to_string());
+            }
+            let result = if op == BinaryOp::IntDiv {
+                dividend.checked_div(divisor)
+            } else {
+                dividend.checked_rem(divisor)
+            };
+            Value::Number(result.ok_or("overflow")? as f64)
+        }
+        BinaryOp::Pow => Value::Number(left.to_number()?.powf(right.to_number()?)),
+        BinaryOp::Concat => Value::Str(format!("{left}{right}")),
+        BinaryOp::Eq => Value::Bool(compare(left, right)? == Some(Ordering::Equal)),
+        BinaryOp::Ne => Value::Bool(compare(left, right)? != Some(Ordering::Equal)),
+        BinaryOp::Lt => Value::Bool(compare(left, right)? == Some(Ordering::Less)),
+        BinaryOp::Gt => Value::Bool(compare(left, right)? == Some(Ordering::Greater)),
+        BinaryOp::Le => Value::Bool(matches!(
+            compare(left, right)?,
+            Some(Ordering::Less | Ordering::Equal)
+        )),
+        BinaryOp::Ge => Value::Bool(matches!(
+            compare(left, right)?,
+            Some(Ordering::Greater | Ordering::Equal)
+        )),
+        BinaryOp::And => logical(|a, b| a && b, |a, b| a & b)?,
+        BinaryOp::Or => logical(|a, b| a || b, |a, b| a | b)?,
+        BinaryOp::Xor => logical(|a, b| a ^ b, |a, b| a ^ b)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vbscript::parser::parse;
+
+    /// A table without items that records the sounds played.
+    #[derive(Default)]
+    struct TestHost {
+        sounds: Vec<String>,
+    }
+
+    impl Host for TestHost {
+        fn is_item(&self, _name: &str) -> bool {
+            false
+        }
+
+        fn call(&mut self, name: &str, args: &[Value]) -> Option<Result<Value, String>> {
+            if !name.eq_ignore_ascii_case("PlaySound") {
+                return None;
+            }
+            self.sounds.push(args.first()?.to_string());
+            Some(Ok(Value::Empty))
+        }
+
+        fn get_property(&mut self, item: &str, _property: &str) -> Result<Value, String> {
+            Err(format!("no item {item}"))
+        }
+
+        fn set_property(
+            &mut self,
+            item: &str,
+            _property: &str,
+            _value: Value,
+        ) -> Result<(), String> {
+            Err(format!("no item {item}"))
+        }
+
+        fn call_method(
+            &mut self,
+            item: &str,
+            _method: &str,
+            _args: &[Value],
+        ) -> Result<Value, String> {
+            Err(format!("no item {item}"))
+        }
+    }
+
+    /// Runs the script and returns the value of its `Result` function.
+    fn result(source: &str) -> Result<Value, RuntimeError> {
+        let mut interpreter = Interpreter::new(parse(source).unwrap());
+        let mut host = TestHost::default();
+        interpreter.run(&mut host)?;
+        interpreter
+            .call("Result", Vec::new(), &mut host)
+            .map(|value| value.expect("the script has a Result function"))
+    }
+
+    #[test]
+    fn calls_procedures() {
+        let mut interpreter = Interpreter::new(
+            parse("Sub Bumper1_Hit\n  PlaySound \"fx_bumper\" & Double(2)\nEnd Sub\nFunction Double(x)\n  Double = x * 2\nEnd Function").unwrap(),
+        );
+        let mut host = TestHost::default();
+        interpreter.run(&mut host).unwrap();
+        interpreter
+            .call("bumper1_hit", Vec::new(), &mut host)
+            .unwrap();
+        assert_eq!(host.sounds, vec!["fx_bumper4"]);
+    }
+
+    #[test]
+    fn redim_resizes_arrays() {
+        let script = "Dim a(1)\na(0) = 5\nReDim Preserve a(3)\na(3) = 7\n\
+            Function Result\n  Result = a(0) & \",\" & a(3) & \",\" & UBound(a)\nEnd Function";
+        assert_eq!(result(script).unwrap(), Value::Str("5,7,3".to_string()));
+        let script = "Dim a(1)\na(0) = 5\nReDim a(2)\n\
+            Function Result\n  Result = IsEmpty(a(0)) And UBound(a) = 2\nEnd Function";
+        assert_eq!(result(script).unwrap(), Value::Bool(true));
+    }
+
+    #[test]
+    fn dim_minus_one_is_empty() {
+        let script = "Dim a(-1)\nFunction Result\n  Result = UBound(a)\nEnd Function";
+        assert_eq!(result(script).unwrap(), Value::Number(-1.0));
+    }
+
+    #[test]
+    fn resumes_after_errors() {
+        let script = "On Error Resume Next\nx = 1 / 0\ny = 2\n\
+            Function Result\n  Result = y\nEnd Function";
+        assert_eq!(result(script).unwrap(), Value::Number(2.0));
+
+        let script = "x = 1 / 0\nFunction Result\nEnd Function";
+        assert_eq!(result(script).unwrap_err().message, "division by zero");
+
+        // each procedure handles its own errors, unhandled ones go to the caller
+        let script = "Sub Fail\n  x = 1 / 0\n  y = 1\nEnd Sub\n\
+            Function Result\n  On Error Resume Next\n  Fail\n  Result = y + 2\nEnd Function";
+        assert_eq!(result(script).unwrap(), Value::Number(2.0));
+
+        let script =
+            "On Error Resume Next\nOn Error GoTo 0\nx = 1 / 0\nFunction Result\nEnd Function";
+        assert!(result(script).is_err());
+    }
+
+    #[test]
+    fn stops_endless_loops_despite_resume_next() {
+        let script = "On Error Resume Next\nDo\nLoop\nFunction Result\nEnd Function";
+        assert!(result(script).unwrap_err().message.contains("too long"));
+    }
+
+    #[test]
+    fn integer_division() {
+        let script = "Function Result\n  Result = (7 \\ 2) & \",\" & (-7 Mod 3)\nEnd Function";
+        assert_eq!(result(script).unwrap(), Value::Str("3,-1".to_string()));
+        let script = "Function Result\n  Result = -9223372036854775808 \\ -1\nEnd Function";
+        assert_eq!(result(script).unwrap_err().message, "overflow");
+    }
+}
@@ -0,0 +1,288 @@
+//! Splits VBScript source into tokens.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    /// An identifier or keyword, keywords are recognized by the parser.
+    Ident(String),
+    Number(f64),
+    Str(String),
+    LParen,
+    RParen,
+    Comma,
+    Dot,
+    Eq,
+    Ne,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Backslash,
+    Caret,
+    Ampersand,
+    Newline,
+    /// Separates statements on one line.
+    Colon,
+    Eof,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(name) => write!(f, "'{name}'"),
+            Token::Number(n) => write!(f, "{n}"),
+            Token::Str(s) => write!(f, "\"{s}\""),
+            Token::Newline => write!(f, "end of line"),
+            Token::Eof => write!(f, "end of script"),
+            other => write!(f, "{other:?}"),
+        }
+    }
+}
+
+/// A token and the line it starts on, counting from 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned {
+    pub token: Token,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexError {
+    pub line: usize,
+    pub message: String,
+}
+
+pub fn tokenize(source: &str) -> Result<Vec<Spanned>, LexError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+    let push = |tokens: &mut Vec<Spanned>, token, line| tokens.push(Spanned { token, line });
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\n' => {
+                push(&mut tokens, Token::Newline, line);
+                line += 1;
+                i += 1;
+            }
+            ':' => {
+                push(&mut tokens, Token::Colon, line);
+                i += 1;
+            }
+            c if c.is_whitespace() => i += 1,
+            '\'' => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            // line continuation
+            '_' if chars[i + 1..]
+                .iter()
+                .take_while(|c| **c != '\n')
+                .all(|c| c.is_whitespace()) =>
+            {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                i += 1;
+                line += 1;
+            }
+            '"' => {
+                let start_line = line;
+                let mut value = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        Some('"') if chars.get(i + 1) == Some(&'"') => {
+                            value.push('"');
+                            i += 2;
+                        }
+                        Some('"') => {
+                            i += 1;
+                            break;
+                        }
+                        Some('\n') | None => {
+                            return Err(LexError {
+                                line: start_line,
+                                message: "unterminated string".to_string(),
+                            });
+                        }
+                        Some(c) => {
+                            value.push(*c);
+                            i += 1;
+                        }
+                    }
+                }
+                push(&mut tokens, Token::Str(value), start_line);
+            }
+            '&' if matches!(chars.get(i + 1), Some('H' | 'h'))
+                && chars.get(i + 2).is_some_and(|c| c.is_ascii_hexdigit()) =>
+            {
+                let start = i + 2;
+                let mut end = start;
+                while end < chars.len() && chars[end].is_ascii_hexdigit() {
+                    end += 1;
+                }
+                let digits: String = chars[start..end].iter().collect();
+                let value = i64::from_str_radix(&digits, 16).map_err(|_| LexError {
+                    line,
+                    message: format!("invalid hex number &H{digits}"),
+                })?;
+                // an optional trailing & marks a Long
+                i = if chars.get(end) == Some(&'&') {
+                    end + 1
+                } else {
+                    end
+                };
+                push(&mut tokens, Token::Number(value as f64), line);
+            }
+            c if c.is_ascii_digit()
+                || (c == '.' && chars.get(i + 1).is_some_and(|c| c.is_ascii_digit())) =>
+            {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                if i < chars.len() && matches!(chars[i], 'e' | 'E') {
+                    i += 1;
+                    if i < chars.len() && matches!(chars[i], '+' | '-') {
+                        i += 1;
+                    }
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+                let text: String = chars[start..i].iter().collect();
+                let value = text.parse().map_err(|_| LexError {
+                    line,
+                    message: format!("invalid number {text}"),
+                })?;
+                push(&mut tokens, Token::Number(value), line);
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let name: String = chars[start..i].iter().collect();
+                if name.eq_ignore_ascii_case("rem") {
+                    while i < chars.len() && chars[i] != '\n' {
+                        i += 1;
+                    }
+                } else {
+                    push(&mut tokens, Token::Ident(name), line);
+                }
+            }
+            '[' => {
+                // escaped identifier like [End]
+                let start = i + 1;
+                let Some(len) = chars[start..].iter().position(|c| *c == ']') else {
+                    return Err(LexError {
+                        line,
+                        message: "unterminated [identifier]".to_string(),
+                    });
+                };
+                let name: String = chars[start..start + len].iter().collect();
+                push(&mut tokens, Token::Ident(name), line);
+                i = start + len + 1;
+            }
+            _ => {
+                let next = chars.get(i + 1).copied();
+                let (token, len) = match (c, next) {
+                    ('<', Some('>')) => (Token::Ne, 2),
+                    ('<', Some('=')) => (Token::Le, 2),
+                    ('>', Some('=')) => (Token::Ge, 2),
+                    ('(', _) => (Token::LParen, 1),
+                    (')', _) => (Token::RParen, 1),
+                    (',', _) => (Token::Comma, 1),
+                    ('.', _) => (Token::Dot, 1),
+                    ('=', _) => (Token::Eq, 1),
+                    ('<', _) => (Token::Lt, 1),
+                    ('>', _) => (Token::Gt, 1),
+                    ('+', _) => (Token::Plus, 1),
+                    ('-', _) => (Token::Minus, 1),
+                    ('*', _) => (Token::Star, 1),
+                    ('/', _) => (Token::Slash, 1),
+                    ('\\', _) => (Token::Backslash, 1),
+                    ('^', _) => (Token::Caret, 1),
+                    ('&', _) => (Token::Ampersand, 1),
+                    _ => {
+                        return Err(LexError {
+                            line,
+                            message: format!("unexpected character '{c}'"),
+                        });
+                    }
+                };
+                push(&mut tokens, token, line);
+                i += len;
+            }
+        }
+    }
+    push(&mut tokens, Token::Newline, line);
+    push(&mut tokens, Token::Eof, line);
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(source: &str) -> Vec<Token> {
+        tokenize(source)
+            .unwrap()
+            .into_iter()
+            .map(|spanned| spanned.token)
+            .collect()
+    }
+
+    #[test]
+    fn numbers_and_strings() {
+        assert_eq!(
+            tokens(r#"x = &HFF& + 1.5e2 & "say ""hi""""#),
+            vec![
+                Token::Ident("x".to_string()),
+                Token::Eq,
+                Token::Number(255.0),
+                Token::Plus,
+                Token::Number(150.0),
+                Token::Ampersand,
+                Token::Str("say \"hi\"".to_string()),
+                Token::Newline,
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_comments_and_continues_lines() {
+        let spanned = tokenize("a _\n  b ' comment\nRem all of it\nc <> d").unwrap();
+        let lines: Vec<(Token, usize)> = spanned.into_iter().map(|s| (s.token, s.line)).collect();
+        assert_eq!(
+            lines,
+            vec![
+                (Token::Ident("a".to_string()), 1),
+                (Token::Ident("b".to_string()), 2),
+                (Token::Newline, 2),
+                (Token::Newline, 3),
+                (Token::Ident("c".to_string()), 4),
+                (Token::Ne, 4),
+                (Token::Ident("d".to_string()), 4),
+                (Token::Newline, 4),
+                (Token::Eof, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_unterminated_strings() {
+        let error = tokenize("x = 1\ny = \"open\n").unwrap_err();
+        assert_eq!(error.line, 2);
+    }
+}
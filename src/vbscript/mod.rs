@@ -0,0 +1,13 @@
+//! Support for the VBScript code embedded in VPX tables.
+//!
+//! Simple scripts are run by the [`interpreter`], which supports a subset of the language and
+//! reports anything else as unsupported. For all other tables the [`scanner`] extracts what the
+//! event handlers do so that they still sound somewhat like the original.
+
+pub mod ast;
+pub mod interpreter;
+pub mod lexer;
+pub mod parser;
+pub mod scanner;
+
+pub use scanner::{EventHandlers, ScriptAction};
@@ -0,0 +1,813 @@
+//! Parses tokens into a [`Program`].
+//!
+//! Statements that can't be parsed don't fail the whole script. They become
+//! [`StmtKind::Unsupported`] so they can be reported, and parsing continues on the next line.
+
+use crate::vbscript::ast::*;
+use crate::vbscript::lexer::{LexError, Spanned, Token, tokenize};
+
+pub fn parse(source: &str) -> Result<Program, LexError> {
+    let tokens = tokenize(source)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    Ok(parser.program())
+}
+
+type ParseResult<T> = Result<T, String>;
+
+struct Parser {
+    tokens: Vec<Spanned>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].token
+    }
+
+    fn peek_at(&self, offset: usize) -> &Token {
+        let index = (self.pos + offset).min(self.tokens.len() - 1);
+        &self.tokens[index].token
+    }
+
+    fn line(&self) -> usize {
+        self.tokens[self.pos].line
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.pos].token.clone();
+        if self.pos < self.tokens.len() - 1 {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn is_keyword_at(&self, offset: usize, keyword: &str) -> bool {
+        matches!(self.peek_at(offset), Token::Ident(name) if name.eq_ignore_ascii_case(keyword))
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        self.is_keyword_at(0, keyword)
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.is_keyword(keyword);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> ParseResult<()> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(format!("expected '{keyword}', found {}", self.peek()))
+        }
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let found = self.peek() == token;
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    fn expect(&mut self, token: Token) -> ParseResult<()> {
+        if self.eat(&token) {
+            Ok(())
+        } else {
+            Err(format!("expected {token}, found {}", self.peek()))
+        }
+    }
+
+    fn identifier(&mut self) -> ParseResult<String> {
+        match self.advance() {
+            Token::Ident(name) => Ok(name),
+            other => Err(format!("expected a name, found {other}")),
+        }
+    }
+
+    fn at_statement_end(&self) -> bool {
+        matches!(self.peek(), Token::Newline | Token::Colon | Token::Eof) || self.is_keyword("else")
+    }
+
+    fn skip_separators(&mut self) {
+        while matches!(self.peek(), Token::Newline | Token::Colon) {
+            self.advance();
+        }
+    }
+
+    fn skip_line(&mut self) {
+        while !matches!(self.peek(), Token::Newline | Token::Eof) {
+            self.advance();
+        }
+    }
+
+    fn program(&mut self) -> Program {
+        let mut program = Program::default();
+        loop {
+            self.skip_separators();
+            if *self.peek() == Token::Eof {
+                return program;
+            }
+            let line = self.line();
+            if self.at_procedure() {
+                match self.procedure() {
+                    Ok(procedure) => program.procedures.push(procedure),
+                    Err(message) => {
+                        self.skip_line();
+                        program.statements.push(Statement {
+                            line,
+                            kind: StmtKind::Unsupported(message),
+                        });
+                    }
+                }
+            } else {
+                program.statements.push(self.statement_or_unsupported());
+            }
+        }
+    }
+
+    fn at_procedure(&self) -> bool {
+        let mut offset = 0;
+        while ["public", "private", "default"]
+            .iter()
+            .any(|keyword| self.is_keyword_at(offset, keyword))
+        {
+            offset += 1;
+        }
+        self.is_keyword_at(offset, "sub") || self.is_keyword_at(offset, "function")
+    }
+
+    fn procedure(&mut self) -> ParseResult<Procedure> {
+        while self.eat_keyword("public")
+            || self.eat_keyword("private")
+            || self.eat_keyword("default")
+        {}
+        let line = self.line();
+        let kind = if self.eat_keyword("sub") {
+            "sub"
+        } else {
+            self.expect_keyword("function")?;
+            "function"
+        };
+        let name = self.identifier()?;
+        let mut params = Vec::new();
+        if self.eat(&Token::LParen) {
+            while !self.eat(&Token::RParen) {
+                let _ = self.eat_keyword("byval") || self.eat_keyword("byref");
+                params.push(self.identifier()?);
+                // array parameters like a()
+                if self.eat(&Token::LParen) {
+                    self.expect(Token::RParen)?;
+                }
+                if !self.eat(&Token::Comma) && *self.peek() != Token::RParen {
+                    return Err(format!("expected ',' or ')', found {}", self.peek()));
+                }
+            }
+        }
+        let body = self.block(&[&["end", kind]])?;
+        self.expect_keyword("end")?;
+        self.expect_keyword(kind)?;
+        Ok(Procedure {
+            name,
+            params,
+            body,
+            line,
+        })
+    }
+
+    /// Parses statements until one of the terminators, which is not consumed.
+    fn block(&mut self, terminators: &[&[&str]]) -> ParseResult<Vec<Statement>> {
+        let mut statements = Vec::new();
+        loop {
+            self.skip_separators();
+            if *self.peek() == Token::Eof {
+                return Err(format!("missing '{}'", terminators[0].join(" ")));
+            }
+            let at = |parser: &Self, keywords: &[&str]| {
+                keywords
+                    .iter()
+                    .enumerate()
+                    .all(|(offset, keyword)| parser.is_keyword_at(offset, keyword))
+            };
+            if terminators.iter().any(|keywords| at(self, keywords)) {
+                return Ok(statements);
+            }
+            // never run into the next procedure
+            if at(self, &["end", "sub"]) || at(self, &["end", "function"]) || self.at_procedure() {
+                return Err(format!("missing '{}'", terminators[0].join(" ")));
+            }
+            statements.push(self.statement_or_unsupported());
+        }
+    }
+
+    fn statement_or_unsupported(&mut self) -> Statement {
+        let line = self.line();
+        let result = self.statement().and_then(|kind| {
+            if self.at_statement_end() {
+                Ok(kind)
+            } else {
+                Err(format!("expected end of statement, found {}", self.peek()))
+            }
+        });
+        let kind = result.unwrap_or_else(|message| {
+            self.skip_line();
+            StmtKind::Unsupported(message)
+        });
+        Statement { line, kind }
+    }
+
+    fn statement(&mut self) -> ParseResult<StmtKind> {
+        let Token::Ident(keyword) = self.peek().clone() else {
+            return Err(format!("unexpected {}", self.peek()));
+        };
+        match keyword.to_lowercase().as_str() {
+            "option" | "randomize" => {
+                while !self.at_statement_end() {
+                    self.advance();
+                }
+                Ok(StmtKind::Nop)
+            }
+            "on" => {
+                self.advance();
+                self.expect_keyword("error")?;
+                if self.eat_keyword("resume") {
+                    self.expect_keyword("next")?;
+                    return Ok(StmtKind::OnError { resume_next: true });
+                }
+                self.expect_keyword("goto")?;
+                self.expect(Token::Number(0.0))?;
+                Ok(StmtKind::OnError { resume_next: false })
+            }
+            "redim" => {
+                self.advance();
+                let preserve = self.eat_keyword("preserve");
+                let mut variables = Vec::new();
+                loop {
+                    let name = self.identifier()?;
+                    self.expect(Token::LParen)?;
+                    variables.push((name, self.arguments()?));
+                    if !self.eat(&Token::Comma) {
+                        return Ok(StmtKind::ReDim {
+                            preserve,
+                            variables,
+                        });
+                    }
+                }
+            }
+            "dim" | "public" | "private" => {
+                self.advance();
+                if self.eat_keyword("const") {
+                    return self.constants();
+                }
+                self.declarations()
+            }
+            "const" => {
+                self.advance();
+                self.constants()
+            }
+            "set" => {
+                self.advance();
+                self.assignment_or_call()
+            }
+            "call" => {
+                self.advance();
+                match self.postfix()? {
+                    Expr::Call(callee, args) => Ok(StmtKind::Call(*callee, args)),
+                    callee => Ok(StmtKind::Call(callee, Vec::new())),
+                }
+            }
+            "if" => self.if_statement(),
+            "select" => self.select_statement(),
+            "for" => self.for_statement(),
+            "do" => self.do_statement(),
+            "while" => {
+                self.advance();
+                let expr = self.expr()?;
+                let body = self.block(&[&["wend"]])?;
+                self.advance();
+                Ok(StmtKind::Loop {
+                    condition: Some(LoopCondition {
+                        expr,
+                        until: false,
+                        before: true,
+                    }),
+                    body,
+                })
+            }
+            "exit" => {
+                self.advance();
+                let kind = match self.identifier()?.to_lowercase().as_str() {
+                    "sub" => ExitKind::Sub,
+                    "function" => ExitKind::Function,
+                    "for" => ExitKind::For,
+                    "do" => ExitKind::Do,
+                    other => return Err(format!("'Exit {other}' is not supported")),
+                };
+                Ok(StmtKind::Exit(kind))
+            }
+            "with" | "class" | "goto" | "execute" | "executeglobal" | "end" | "else" | "elseif"
+            | "next" | "loop" | "wend" | "case" | "sub" | "function" | "property" | "erase" => {
+                Err(format!("'{keyword}' is not supported here"))
+            }
+            _ => self.assignment_or_call(),
+        }
+    }
+
+    fn declarations(&mut self) -> ParseResult<StmtKind> {
+        let mut variables = Vec::new();
+        loop {
+            let name = self.identifier()?;
+            let bounds = if self.eat(&Token::LParen) {
+                Some(self.arguments()?)
+            } else {
+                None
+            };
+            variables.push((name, bounds));
+            if !self.eat(&Token::Comma) {
+                return Ok(StmtKind::Dim(variables));
+            }
+        }
+    }
+
+    fn constants(&mut self) -> ParseResult<StmtKind> {
+        let mut constants = Vec::new();
+        loop {
+            let name = self.identifier()?;
+            self.expect(Token::Eq)?;
+            constants.push((name, self.expr()?));
+            if !self.eat(&Token::Comma) {
+                return Ok(StmtKind::Const(constants));
+            }
+        }
+    }
+
+    fn assignment_or_call(&mut self) -> ParseResult<StmtKind> {
+        let target = self.postfix()?;
+        if self.eat(&Token::Eq) {
+            return Ok(StmtKind::Assign(target, self.expr()?));
+        }
+        if self.at_statement_end() {
+            return Ok(match target {
+                Expr::Call(callee, args) => StmtKind::Call(*callee, args),
+                callee => StmtKind::Call(callee, Vec::new()),
+            });
+        }
+        // `Foo (a), b` passes a parenthesized first argument
+        if let Expr::Call(callee, mut args) = target.clone()
+            && args.len() == 1
+            && self.eat(&Token::Comma)
+        {
+            args.extend(self.statement_arguments()?);
+            return Ok(StmtKind::Call(*callee, args));
+        }
+        Ok(StmtKind::Call(target, self.statement_arguments()?))
+    }
+
+    /// Arguments of a call statement without parentheses, where empty arguments are allowed.
+    fn statement_arguments(&mut self) -> ParseResult<Vec<Expr>> {
+        let mut args = Vec::new();
+        loop {
+            if matches!(self.peek(), Token::Comma) || self.at_statement_end() {
+                args.push(Expr::Empty);
+            } else {
+                args.push(self.expr()?);
+            }
+            if !self.eat(&Token::Comma) {
+                return Ok(args);
+            }
+        }
+    }
+
+    /// Arguments after an opening parenthesis, including the closing one.
+    fn arguments(&mut self) -> ParseResult<Vec<Expr>> {
+        let mut args = Vec::new();
+        if self.eat(&Token::RParen) {
+            return Ok(args);
+        }
+        loop {
+            if matches!(self.peek(), Token::Comma | Token::RParen) {
+                args.push(Expr::Empty);
+            } else {
+                args.push(self.expr()?);
+            }
+            if self.eat(&Token::RParen) {
+                return Ok(args);
+            }
+            self.expect(Token::Comma)?;
+        }
+    }
+
+    fn if_statement(&mut self) -> ParseResult<StmtKind> {
+        self.expect_keyword("if")?;
+        let condition = self.expr()?;
+        self.expect_keyword("then")?;
+        if !matches!(self.peek(), Token::Newline | Token::Colon) {
+            return self.single_line_if(condition);
+        }
+        let mut branches = Vec::new();
+        let mut otherwise = Vec::new();
+        let terminators: &[&[&str]] = &[&["elseif"], &["else"], &["end", "if"]];
+        let mut body = self.block(terminators)?;
+        let mut condition = condition;
+        loop {
+            if self.eat_keyword("elseif") {
+                branches.push((condition, body));
+                condition = self.expr()?;
+                self.expect_keyword("then")?;
+                body = self.block(terminators)?;
+            } else if self.eat_keyword("else") {
+                branches.push((condition, body));
+                otherwise = self.block(&[&["end", "if"]])?;
+                break;
+            } else {
+                branches.push((condition, body));
+                break;
+            }
+        }
+        self.expect_keyword("end")?;
+        self.expect_keyword("if")?;
+        Ok(StmtKind::If {
+            branches,
+            otherwise,
+        })
+    }
+
+    /// `If a Then b : c Else d`, which ends at the end of the line.
+    fn single_line_if(&mut self, condition: Expr) -> ParseResult<StmtKind> {
+        let mut body = Vec::new();
+        let mut otherwise = Vec::new();
+        let mut in_else = false;
+        loop {
+            let line = self.line();
+            let kind = self.statement()?;
+            let statement = Statement { line, kind };
+            if in_else {
+                otherwise.push(statement);
+            } else {
+                body.push(statement);
+            }
+            if self.eat(&Token::Colon) {
+                continue;
+            }
+            if !in_else && self.eat_keyword("else") {
+                in_else = true;
+                continue;
+            }
+            // a redundant End If is allowed
+            if self.is_keyword("end") && self.is_keyword_at(1, "if") {
+                self.advance();
+                self.advance();
+            }
+            if matches!(self.peek(), Token::Newline | Token::Eof) {
+                break;
+            }
+            return Err(format!("expected end of statement, found {}", self.peek()));
+        }
+        Ok(StmtKind::If {
+            branches: vec![(condition, body)],
+            otherwise,
+        })
+    }
+
+    fn select_statement(&mut self) -> ParseResult<StmtKind> {
+        self.expect_keyword("select")?;
+        self.expect_keyword("case")?;
+        let subject = self.expr()?;
+        let mut cases = Vec::new();
+        let mut otherwise = Vec::new();
+        self.skip_separators();
+        let terminators: &[&[&str]] = &[&["case"], &["end", "select"]];
+        while self.eat_keyword("case") {
+            if self.eat_keyword("else") {
+                otherwise = self.block(&[&["end", "select"]])?;
+                break;
+            }
+            if self.is_keyword("is") {
+                return Err("'Case Is' is not supported".to_string());
+            }
+            let mut values = vec![self.expr()?];
+            while self.eat(&Token::Comma) {
+                values.push(self.expr()?);
+            }
+            cases.push((values, self.block(terminators)?));
+        }
+        self.expect_keyword("end")?;
+        self.expect_keyword("select")?;
+        Ok(StmtKind::Select {
+            subject,
+            cases,
+            otherwise,
+        })
+    }
+
+    fn for_statement(&mut self) -> ParseResult<StmtKind> {
+        self.expect_keyword("for")?;
+        if self.is_keyword("each") {
+            return Err("'For Each' is not supported".to_string());
+        }
+        let variable = self.identifier()?;
+        self.expect(Token::Eq)?;
+        let from = self.expr()?;
+        self.expect_keyword("to")?;
+        let to = self.expr()?;
+        let step = if self.eat_keyword("step") {
+            Some(self.expr()?)
+        } else {
+            None
+        };
+        let body = self.block(&[&["next"]])?;
+        self.expect_keyword("next")?;
+        if let Token::Ident(_) = self.peek() {
+            self.advance();
+        }
+        Ok(StmtKind::For {
+            variable,
+            from,
+            to,
+            step,
+            body,
+        })
+    }
+
+    fn do_statement(&mut self) -> ParseResult<StmtKind> {
+        self.expect_keyword("do")?;
+        let mut condition = self.loop_condition(true)?;
+        let body = self.block(&[&["loop"]])?;
+        self.expect_keyword("loop")?;
+        if let Some(after) = self.loop_condition(false)? {
+            if condition.is_some() {
+                return Err("a loop can't have two conditions".to_string());
+            }
+            condition = Some(after);
+        }
+        Ok(StmtKind::Loop { condition, body })
+    }
+
+    fn loop_condition(&mut self, before: bool) -> ParseResult<Option<LoopCondition>> {
+        let until = if self.eat_keyword("while") {
+            false
+        } else if self.eat_keyword("until") {
+            true
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(LoopCondition {
+            expr: self.expr()?,
+            until,
+            before,
+        }))
+    }
+
+    fn expr(&mut self) -> ParseResult<Expr> {
+        let mut left = self.and_expr()?;
+        loop {
+            let op = if self.eat_keyword("or") {
+                BinaryOp::Or
+            } else if self.eat_keyword("xor") {
+                BinaryOp::Xor
+            } else {
+                return Ok(left);
+            };
+            left = Expr::Binary(op, Box::new(left), Box::new(self.and_expr()?));
+        }
+    }
+
+    fn and_expr(&mut self) -> ParseResult<Expr> {
+        let mut left = self.not_expr()?;
+        while self.eat_keyword("and") {
+            left = Expr::Binary(BinaryOp::And, Box::new(left), Box::new(self.not_expr()?));
+        }
+        Ok(left)
+    }
+
+    fn not_expr(&mut self) -> ParseResult<Expr> {
+        if self.eat_keyword("not") {
+            return Ok(Expr::Unary(UnaryOp::Not, Box::new(self.not_expr()?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> ParseResult<Expr> {
+        let mut left = self.concat()?;
+        loop {
+            let op = match self.peek() {
+                Token::Eq => BinaryOp::Eq,
+                Token::Ne => BinaryOp::Ne,
+                Token::Lt => BinaryOp::Lt,
+                Token::Gt => BinaryOp::Gt,
+                Token::Le => BinaryOp::Le,
+                Token::Ge => BinaryOp::Ge,
+                _ if self.is_keyword("is") => BinaryOp::Eq,
+                _ => return Ok(left),
+            };
+            self.advance();
+            left = Expr::Binary(op, Box::new(left), Box::new(self.concat()?));
+        }
+    }
+
+    fn concat(&mut self) -> ParseResult<Expr> {
+        let mut left = self.additive()?;
+        while self.eat(&Token::Ampersand) {
+            left = Expr::Binary(BinaryOp::Concat, Box::new(left), Box::new(self.additive()?));
+        }
+        Ok(left)
+    }
+
+    fn additive(&mut self) -> ParseResult<Expr> {
+        let mut left = self.modulo()?;
+        loop {
+            let op = match self.peek() {
+                Token::Plus => BinaryOp::Add,
+                Token::Minus => BinaryOp::Sub,
+                _ => return Ok(left),
+            };
+            self.advance();
+            left = Expr::Binary(op, Box::new(left), Box::new(self.modulo()?));
+        }
+    }
+
+    fn modulo(&mut self) -> ParseResult<Expr> {
+        let mut left = self.int_div()?;
+        while self.eat_keyword("mod") {
+            left = Expr::Binary(BinaryOp::Mod, Box::new(left), Box::new(self.int_div()?));
+        }
+        Ok(left)
+    }
+
+    fn int_div(&mut self) -> ParseResult<Expr> {
+        let mut left = self.multiplicative()?;
+        while self.eat(&Token::Backslash) {
+            left = Expr::Binary(
+                BinaryOp::IntDiv,
+                Box::new(left),
+                Box::new(self.multiplicative()?),
+            );
+        }
+        Ok(left)
+    }
+
+    fn multiplicative(&mut self) -> ParseResult<Expr> {
+        let mut left = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Token::Star => BinaryOp::Mul,
+                Token::Slash => BinaryOp::Div,
+                _ => return Ok(left),
+            };
+            self.advance();
+            left = Expr::Binary(op, Box::new(left), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> ParseResult<Expr> {
+        if self.eat(&Token::Minus) {
+            return Ok(Expr::Unary(UnaryOp::Neg, Box::new(self.unary()?)));
+        }
+        if self.eat(&Token::Plus) {
+            return self.unary();
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> ParseResult<Expr> {
+        let mut left = self.primary()?;
+        while self.eat(&Token::Caret) {
+            left = Expr::Binary(BinaryOp::Pow, Box::new(left), Box::new(self.primary()?));
+        }
+        Ok(left)
+    }
+
+    fn primary(&mut self) -> ParseResult<Expr> {
+        match self.peek().clone() {
+            Token::Number(n) => {
+                self.advance();
+                Ok(Expr::Number(n))
+            }
+            Token::Str(s) => {
+                self.advance();
+                Ok(Expr::Str(s))
+            }
+            Token::LParen => {
+                self.advance();
+                let expr = self.expr()?;
+                self.expect(Token::RParen)?;
+                Ok(expr)
+            }
+            Token::Ident(name) => match name.to_lowercase().as_str() {
+                "true" => {
+                    self.advance();
+                    Ok(Expr::Bool(true))
+                }
+                "false" => {
+                    self.advance();
+                    Ok(Expr::Bool(false))
+                }
+                "empty" | "null" => {
+                    self.advance();
+                    Ok(Expr::Empty)
+                }
+                "nothing" => {
+                    self.advance();
+                    Ok(Expr::Nothing)
+                }
+                "new" => Err("'New' is not supported".to_string()),
+                _ => self.postfix(),
+            },
+            Token::Dot => Err("'With' blocks are not supported".to_string()),
+            other => Err(format!("unexpected {other}")),
+        }
+    }
+
+    /// A name followed by member accesses and calls, like `Light1.State` or `Foo(1)`.
+    fn postfix(&mut self) -> ParseResult<Expr> {
+        let mut expr = Expr::Ident(self.identifier()?);
+        loop {
+            if self.eat(&Token::Dot) {
+                expr = Expr::Member(Box::new(expr), self.identifier()?);
+            } else if self.eat(&Token::LParen) {
+                expr = Expr::Call(Box::new(expr), self.arguments()?);
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statements(source: &str) -> Vec<StmtKind> {
+        parse(source)
+            .unwrap()
+            .statements
+            .into_iter()
+            .map(|statement| statement.kind)
+            .collect()
+    }
+
+    #[test]
+    fn redim_is_not_a_declaration() {
+        assert_eq!(
+            statements("ReDim Preserve a(n + 1), b(2)"),
+            vec![StmtKind::ReDim {
+                preserve: true,
+                variables: vec![
+                    (
+                        "a".to_string(),
+                        vec![Expr::Binary(
+                            BinaryOp::Add,
+                            Box::new(Expr::Ident("n".to_string())),
+                            Box::new(Expr::Number(1.0)),
+                        )],
+                    ),
+                    ("b".to_string(), vec![Expr::Number(2.0)]),
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn on_error() {
+        assert_eq!(
+            statements("On Error Resume Next\nOn Error GoTo 0"),
+            vec![
+                StmtKind::OnError { resume_next: true },
+                StmtKind::OnError { resume_next: false },
+            ]
+        );
+    }
+
+    #[test]
+    fn operator_precedence() {
+        // VBScript binds \ tighter than Mod and Mod tighter than +
+        let statements = statements("x = 1 + 7 Mod 5 \\ 2");
+        let [StmtKind::Assign(_, value)] = statements.as_slice() else {
+            panic!("expected an assignment");
+        };
+        let number = |n| Box::new(Expr::Number(n));
+        assert_eq!(
+            *value,
+            Expr::Binary(
+                BinaryOp::Add,
+                number(1.0),
+                Box::new(Expr::Binary(
+                    BinaryOp::Mod,
+                    number(7.0),
+                    Box::new(Expr::Binary(BinaryOp::IntDiv, number(5.0), number(2.0))),
+                )),
+            )
+        );
+    }
+
+    #[test]
+    fn keeps_parsing_after_unsupported_statements() {
+        let program = parse("With Light1\nEnd With\nSub Foo\n  x = 1\nEnd Sub").unwrap();
+        assert_eq!(program.unsupported().len(), 2);
+        assert_eq!(program.procedures.len(), 1);
+        assert_eq!(program.procedures[0].name, "Foo");
+    }
+}
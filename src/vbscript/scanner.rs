@@ -0,0 +1,242 @@
+//! Scans VBScript for item event handlers like `Sub Drain_Hit()` and the sounds they play,
+//! without interpreting the script.
+//!
+//! The scan ignores control flow, so a sound inside an `If` counts as always played.
+
+use std::collections::HashMap;
+
+/// A simple action performed by an event handler.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptAction {
+    /// `PlaySound "name"` or one of its variants like `PlaySoundAt "name", Item`.
+    PlaySound(String),
+}
+
+/// The actions of every `Sub <Item>_<Event>` in a script, looked up case-insensitively.
+#[derive(Debug, Clone, Default)]
+pub struct EventHandlers {
+    handlers: HashMap<(String, String), Vec<ScriptAction>>,
+}
+
+impl EventHandlers {
+    /// The actions performed for an event, e.g. `actions("Drain", "Hit")`.
+    pub fn actions(&self, item: &str, event: &str) -> &[ScriptAction] {
+        self.handlers
+            .get(&(item.to_lowercase(), event.to_lowercase()))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// The sounds played for an event.
+    pub fn sounds<'a>(&'a self, item: &str, event: &str) -> impl Iterator<Item = &'a str> {
+        self.actions(item, event)
+            .iter()
+            .map(|ScriptAction::PlaySound(sound)| sound.as_str())
+    }
+}
+
+/// Scans a complete table script.
+pub fn scan(code: &str) -> EventHandlers {
+    let mut handlers = EventHandlers::default();
+    let mut current: Option<(String, String)> = None;
+    for line in logical_lines(code) {
+        for statement in split_statements(&line) {
+            let words: Vec<&str> = statement.split_whitespace().collect();
+            let lower: Vec<String> = words.iter().map(|w| w.to_lowercase()).collect();
+            match lower.as_slice() {
+                [end, sub, ..] if end == "end" && sub == "sub" => current = None,
+                [visibility, sub, ..]
+                    if (visibility == "public" || visibility == "private") && sub == "sub" =>
+                {
+                    current = sub_event(words[2..].join(" ").as_str());
+                }
+                [sub, ..] if sub == "sub" => {
+                    current = sub_event(words[1..].join(" ").as_str());
+                }
+                _ => {
+                    if let Some(key) = &current
+                        && let Some(action) = action(statement)
+                    {
+                        handlers
+                            .handlers
+                            .entry(key.clone())
+                            .or_default()
+                            .push(action);
+                    }
+                }
+            }
+            // register handlers without actions as well
+            if let Some(key) = &current {
+                handlers.handlers.entry(key.clone()).or_default();
+            }
+        }
+    }
+    handlers
+}
+
+/// Joins lines continued with ` _` and strips comments.
+fn logical_lines(code: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut pending = String::new();
+    for line in code.lines() {
+        let line = strip_comment(line);
+        let trimmed = line.trim_end();
+        if let Some(continued) = trimmed.strip_suffix(" _") {
+            pending.push_str(continued);
+            pending.push(' ');
+        } else {
+            pending.push_str(trimmed);
+            lines.push(std::mem::take(&mut pending));
+        }
+    }
+    if !pending.is_empty() {
+        lines.push(pending);
+    }
+    lines
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '\'' if !in_string => return &line[..index],
+            _ => {}
+        }
+    }
+    let trimmed = line.trim_start();
+    match strip_prefix_ignore_case(trimmed, "rem") {
+        Some(rest) if rest.is_empty() || rest.starts_with(char::is_whitespace) => "",
+        _ => line,
+    }
+}
+
+/// Splits a line on `:` outside of string literals.
+fn split_statements(line: &str) -> Vec<&str> {
+    let mut statements = Vec::new();
+    let mut in_string = false;
+    let mut start = 0;
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            ':' if !in_string => {
+                statements.push(line[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    statements.push(line[start..].trim());
+    statements.retain(|s| !s.is_empty());
+    statements
+}
+
+/// Splits `Drain_Hit()` into the lowercase item and event names.
+fn sub_event(declaration: &str) -> Option<(String, String)> {
+    let name = declaration
+        .split(|c: char| c == '(' || c.is_whitespace())
+        .next()?;
+    let (item, event) = name.rsplit_once('_')?;
+    if item.is_empty() || event.is_empty() {
+        return None;
+    }
+    Some((item.to_lowercase(), event.to_lowercase()))
+}
+
+fn action(statement: &str) -> Option<ScriptAction> {
+    let statement = strip_prefix_ignore_case(statement, "call ").unwrap_or(statement);
+    let (head, args) = statement
+        .split_once(|c: char| c == '(' || c.is_whitespace())
+        .unwrap_or((statement, ""));
+    if head.to_lowercase().starts_with("playsound") {
+        return first_string_argument(args).map(ScriptAction::PlaySound);
+    }
+    None
+}
+
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    s.get(..prefix.len())
+        .filter(|head| head.eq_ignore_ascii_case(prefix))
+        .map(|_| &s[prefix.len()..])
+}
+
+/// The first argument if it is nothing but a string literal, a sound name built at runtime like
+/// `"fx_" & name` is unknown to the scan.
+fn first_string_argument(args: &str) -> Option<String> {
+    let rest = args.trim_start().trim_start_matches('(').trim_start();
+    let rest = rest.strip_prefix('"')?;
+    let end = rest.find('"')?;
+    let after = rest[end + 1..].trim_start();
+    (after.is_empty() || after.starts_with([',', ')'])).then(|| rest[..end].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sounds(code: &str, item: &str, event: &str) -> Vec<String> {
+        scan(code).sounds(item, event).map(str::to_string).collect()
+    }
+
+    #[test]
+    fn handlers_ignore_case() {
+        let code = "SUB drain_HIT()\n    PlaySound \"drain\"\nEnd Sub\n";
+        assert_eq!(sounds(code, "Drain", "Hit"), ["drain"]);
+        assert_eq!(sounds(code, "drain", "hit"), ["drain"]);
+        assert!(sounds(code, "Drain", "Unhit").is_empty());
+    }
+
+    #[test]
+    fn sounds_with_and_without_parentheses() {
+        let code = r#"
+Private Sub Bumper1_Hit
+    PlaySound "fx_bumper1"
+    PlaySoundAt("fx_bumper2", Bumper1)
+    Call PlaySound("fx_bumper3", 0, 1) : AddScore 100
+End Sub
+"#;
+        assert_eq!(
+            sounds(code, "Bumper1", "Hit"),
+            ["fx_bumper1", "fx_bumper2", "fx_bumper3"]
+        );
+    }
+
+    #[test]
+    fn sounds_built_at_runtime_are_skipped() {
+        let code = r#"
+Sub Wall1_Hit()
+    PlaySound "fx_" & Wall1.Name
+    PlaySound("rubber_" & n), 0
+    PlaySound "fx_" & _
+        "wall"
+    PlaySound "known"
+End Sub
+"#;
+        assert_eq!(sounds(code, "Wall1", "Hit"), ["known"]);
+    }
+
+    #[test]
+    fn commented_out_calls_are_skipped() {
+        let code = r#"
+Sub Kicker1_Hit()
+    ' PlaySound "quote"
+    Rem PlaySound "rem"
+    PlaySound "kicker" ' it's "loud"
+End Sub
+"#;
+        assert_eq!(sounds(code, "Kicker1", "Hit"), ["kicker"]);
+    }
+
+    #[test]
+    fn sounds_outside_handlers_are_skipped() {
+        let code = r#"
+PlaySound "startup"
+Sub Table1_Init
+End Sub
+PlaySound "loose"
+"#;
+        let handlers = scan(code);
+        assert!(handlers.actions("Table1", "Init").is_empty());
+        assert_eq!(handlers.handlers.len(), 1);
+    }
+}
@@ -1,5 +1,7 @@
 //! Representation of assets present in a vpx file
 
+use crate::vbscript::EventHandlers;
+use crate::vpx::material2d::VpxMaterial2d;
 use bevy::prelude::*;
 use std::collections::HashMap;
 use vpin::vpx::VPX;
@@ -22,11 +24,75 @@ pub struct VpxAsset {
     pub meshes: Vec<Handle<Mesh>>,
     /// Named meshes loaded from the vpx file.
     pub named_meshes: HashMap<Box<str>, Handle<Mesh>>,
+    /// Materials made from the vpx materials and the images the table items combine them with,
+    /// shared by all items using them.
+    pub named_materials: HashMap<MaterialKey, Handle<VpxMaterial2d>>,
+    /// The item event handlers found in the table script.
+    pub event_handlers: EventHandlers,
+    /// Identifies the table independent of its file name.
+    pub identity: TableIdentity,
     /// The raw VPX data structure.
     pub raw: VPX,
 }
 
+/// Identifies a table by its metadata and content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableIdentity {
+    /// The table name from the VPX table info.
+    pub name: Option<String>,
+    /// The table version from the VPX table info.
+    pub version: Option<String>,
+    /// FNV-1a hash of the table script, which tells tables without table info apart. Hashing the
+    /// complete file would take long for tables with large images and sounds.
+    pub hash: u64,
+}
+
+impl TableIdentity {
+    pub fn new(vpx: &VPX) -> Self {
+        let non_empty = |value: &Option<String>| {
+            value
+                .as_ref()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        Self {
+            name: non_empty(&vpx.info.table_name),
+            version: non_empty(&vpx.info.table_version),
+            hash: fnv1a_64(vpx.gamedata.code.string.as_bytes()),
+        }
+    }
+}
+
+/// A simple and stable hash, unlike the std hashers which may change between Rust versions.
+fn fnv1a_64(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    })
+}
+
 impl VpxAsset {
+    /// Looks up a sound by name, falling back to a case-insensitive match like VPX does.
+    pub fn sound(&self, name: &str) -> Option<&Handle<AudioSource>> {
+        self.named_sounds.get(name).or_else(|| {
+            self.named_sounds
+                .iter()
+                .find(|(sound_name, _)| sound_name.eq_ignore_ascii_case(name))
+                .map(|(_, handle)| handle)
+        })
+    }
+
+    /// Looks up an image by name, falling back to a case-insensitive match like VPX does.
+    pub fn image(&self, name: &str) -> Option<&Handle<Image>> {
+        self.named_images.get(name).or_else(|| {
+            self.named_images
+                .iter()
+                .find(|(image_name, _)| image_name.eq_ignore_ascii_case(name))
+                .map(|(_, handle)| handle)
+        })
+    }
+
     pub fn wall_mesh_sub_path(name: &str) -> String {
         format!("meshes/wall/{name}")
     }
@@ -34,3 +100,54 @@ impl VpxAsset {
         format!("meshes/rubber/{name}")
     }
 }
+
+/// Identifies a shared material in [`VpxAsset::named_materials`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MaterialKey {
+    /// The name of the vpx material.
+    pub material: String,
+    /// The image the material is textured with, empty for none.
+    pub image: String,
+    pub variant: MaterialVariant,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MaterialVariant {
+    Plain,
+    /// For round items, like bumpers.
+    Round,
+    /// See-through, used to show items that are hidden in vpx.
+    Translucent,
+}
+
+impl MaterialKey {
+    /// The material for a vpx material, textured with an image unless the image name is empty.
+    pub fn plain(material: &str, image: &str) -> Self {
+        Self::new(material, image, MaterialVariant::Plain)
+    }
+
+    pub fn round(material: &str) -> Self {
+        Self::new(material, "", MaterialVariant::Round)
+    }
+
+    pub fn translucent(material: &str, image: &str) -> Self {
+        Self::new(material, image, MaterialVariant::Translucent)
+    }
+
+    fn new(material: &str, image: &str, variant: MaterialVariant) -> Self {
+        Self {
+            material: material.to_string(),
+            image: image.to_string(),
+            variant,
+        }
+    }
+
+    /// The label of the material asset. The names are quoted, so that names containing `/` can't
+    /// make two keys look the same.
+    pub fn label(&self) -> String {
+        format!(
+            "materials/{:?}/{:?}/{:?}",
+            self.variant, self.material, self.image
+        )
+    }
+}
@@ -0,0 +1,315 @@
+//! Loads `directb2s` backglass files, which tables ship next to the VPX file.
+//!
+//! A directb2s file is XML with the images base64 encoded in the attributes. Only the parts we
+//! show are read: the backglass image, the illumination bulbs and the score displays.
+//!
+//! ```xml
+//! <DirectB2SData Version="1.2">
+//!   <Name Value="North Pole" />
+//!   <Illumination>
+//!     <Bulb ID="1" Parent="Backglass" Name="GameOver" B2SID="5" InitialState="0"
+//!           LocX="10" LocY="20" Width="40" Height="40" LightColor="255.255.255" Image="iVBO..." />
+//!   </Illumination>
+//!   <Scores>
+//!     <Score ID="1" Parent="Backglass" B2SPlayerNo="1" Digits="7" ReelLitColor="255.128.0"
+//!            LocX="100" LocY="600" Width="300" Height="50" />
+//!   </Scores>
+//!   <Images>
+//!     <BackglassImage Value="iVBO..." />
+//!   </Images>
+//! </DirectB2SData>
+//! ```
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use bevy::asset::io::{Reader, VecReader};
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::image::{CompressedImageFormats, ImageLoader, ImageLoaderError};
+use bevy::prelude::*;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum DirectB2sError {
+    #[error("Could not load asset: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid XML: {0}")]
+    Xml(#[from] roxmltree::Error),
+    #[error("Not a directb2s file")]
+    NotDirectB2s,
+    #[error("Could not load image: {0}")]
+    ImageLoaderError(#[from] ImageLoaderError),
+}
+
+/// A backglass from a directb2s file. Positions are in pixels of the backglass image.
+#[derive(Asset, TypePath, Debug)]
+pub struct DirectB2sAsset {
+    pub name: Option<String>,
+    pub backglass: Option<Handle<Image>>,
+    /// The size of the backglass image.
+    pub size: Vec2,
+    pub bulbs: Vec<B2sBulb>,
+    pub scores: Vec<B2sScore>,
+}
+
+/// An illumination layer that lights up part of the backglass.
+#[derive(Debug, Clone)]
+pub struct B2sBulb {
+    pub name: String,
+    /// The id scripts switch the bulb with, see `B2SSetData`.
+    pub b2s_id: Option<i32>,
+    /// The value of the id that lights the bulb, 0 lights it for any value but 0.
+    pub b2s_value: i32,
+    pub initially_on: bool,
+    pub rect: Rect,
+    /// Bulbs without an image light up their area in their color.
+    pub image: Option<Handle<Image>>,
+    pub color: Color,
+}
+
+/// Where the score of a player is shown.
+#[derive(Debug, Clone)]
+pub struct B2sScore {
+    /// Index of the player, starting at 0.
+    pub player: usize,
+    pub digits: usize,
+    pub rect: Rect,
+    pub color: Color,
+}
+
+pub struct DirectB2sLoader;
+
+impl AssetLoader for DirectB2sLoader {
+    type Asset = DirectB2sAsset;
+    type Settings = ();
+    type Error = DirectB2sError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        info!("Loading directb2s {}", load_context.path().display());
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let text = String::from_utf8_lossy(&bytes);
+        // the XML document is not kept across the image loading below
+        let parsed = parse(&text)?;
+
+        let (backglass, size) = match parsed.backglass {
+            Some(data) => match load_image("backglass".to_string(), load_context, data).await {
+                Ok((handle, size)) => (Some(handle), size),
+                Err(e) => {
+                    warn!("Failed to load the backglass image: {}", e);
+                    (None, Vec2::ZERO)
+                }
+            },
+            None => (None, Vec2::ZERO),
+        };
+        let mut bulbs = Vec::new();
+        for (index, (mut bulb, data)) in parsed.bulbs.into_iter().enumerate() {
+            if let Some(data) = data {
+                match load_image(format!("bulbs/{index}"), load_context, data).await {
+                    Ok((handle, _)) => bulb.image = Some(handle),
+                    Err(e) => warn!("Failed to load the image of bulb {}: {}", bulb.name, e),
+                }
+            }
+            bulbs.push(bulb);
+        }
+        Ok(DirectB2sAsset {
+            name: parsed.name,
+            backglass,
+            size,
+            bulbs,
+            scores: parsed.scores,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["directb2s"]
+    }
+}
+
+/// The file contents, with the images still encoded.
+struct ParsedB2s {
+    name: Option<String>,
+    backglass: Option<Vec<u8>>,
+    bulbs: Vec<(B2sBulb, Option<Vec<u8>>)>,
+    scores: Vec<B2sScore>,
+}
+
+fn parse(text: &str) -> Result<ParsedB2s, DirectB2sError> {
+    let document = roxmltree::Document::parse(text)?;
+    let root = document.root_element();
+    if !root.has_tag_name("DirectB2SData") {
+        return Err(DirectB2sError::NotDirectB2s);
+    }
+    let element = |name: &str| root.descendants().find(|node| node.has_tag_name(name));
+    let name = element("Name")
+        .and_then(|node| node.attribute("Value"))
+        .map(str::to_string);
+    let backglass = element("BackglassImage")
+        .and_then(|node| node.attribute("Value"))
+        .and_then(decode_base64);
+
+    // bulbs and scores can also sit on the DMD part of the backglass, which we don't show
+    let on_backglass =
+        |node: &roxmltree::Node| node.attribute("Parent").is_none_or(|p| p == "Backglass");
+    let bulbs = root
+        .descendants()
+        .filter(|node| node.has_tag_name("Bulb") && on_backglass(node))
+        .map(|node| {
+            let bulb = B2sBulb {
+                name: node.attribute("Name").unwrap_or_default().to_string(),
+                b2s_id: number(&node, "B2SID").filter(|id| *id > 0),
+                b2s_value: number(&node, "B2SValue").unwrap_or(0),
+                initially_on: number(&node, "InitialState") == Some(1),
+                rect: rect(&node),
+                image: None,
+                color: color(&node, "LightColor").unwrap_or(Color::WHITE),
+            };
+            let image = node.attribute("Image").and_then(decode_base64);
+            (bulb, image)
+        })
+        .collect();
+    let scores = root
+        .descendants()
+        .filter(|node| node.has_tag_name("Score") && on_backglass(node))
+        .filter_map(|node| {
+            Some(B2sScore {
+                player: usize::try_from(number(&node, "B2SPlayerNo")?)
+                    .ok()?
+                    .checked_sub(1)?,
+                digits: usize::try_from(number(&node, "Digits")?).ok()?,
+                rect: rect(&node),
+                color: color(&node, "ReelLitColor").unwrap_or(Color::WHITE),
+            })
+        })
+        .collect();
+    Ok(ParsedB2s {
+        name,
+        backglass,
+        bulbs,
+        scores,
+    })
+}
+
+fn number(node: &roxmltree::Node, attribute: &str) -> Option<i32> {
+    node.attribute(attribute)?.trim().parse().ok()
+}
+
+fn rect(node: &roxmltree::Node) -> Rect {
+    let value = |attribute| number(node, attribute).unwrap_or(0) as f32;
+    Rect::from_corners(
+        Vec2::new(value("LocX"), value("LocY")),
+        Vec2::new(
+            value("LocX") + value("Width"),
+            value("LocY") + value("Height"),
+        ),
+    )
+}
+
+/// Colors are written as `r.g.b`.
+fn color(node: &roxmltree::Node, attribute: &str) -> Option<Color> {
+    let mut parts = node.attribute(attribute)?.split('.').map(str::parse::<u8>);
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(r)), Some(Ok(g)), Some(Ok(b))) => Some(Color::srgb_u8(r, g, b)),
+        _ => None,
+    }
+}
+
+/// Images are base64 encoded, sometimes with line breaks.
+fn decode_base64(value: &str) -> Option<Vec<u8>> {
+    let value: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+    if value.is_empty() {
+        return None;
+    }
+    STANDARD
+        .decode(value)
+        .inspect_err(|e| warn!("Invalid base64 image data: {}", e))
+        .ok()
+}
+
+async fn load_image(
+    label: String,
+    load_context: &mut LoadContext<'_>,
+    bytes: Vec<u8>,
+) -> Result<(Handle<Image>, Vec2), DirectB2sError> {
+    let mut reader = VecReader::new(bytes);
+    let image_loader = ImageLoader::new(CompressedImageFormats::all());
+    let settings = bevy::image::ImageLoaderSettings {
+        format: bevy::image::ImageFormatSetting::Guess,
+        ..default()
+    };
+    let mut labeled = load_context.begin_labeled_asset();
+    let image = image_loader
+        .load(&mut reader, &settings, &mut labeled)
+        .await?;
+    let size = image.size_f32();
+    let loaded = labeled.finish(image);
+    Ok((load_context.add_loaded_labeled_asset(label, loaded), size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A backglass image, a bulb on the backglass, a bulb on the DMD part and a score. The images
+    /// are `hello` and `bulb` in base64.
+    const B2S: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<DirectB2SData Version="1.2">
+  <Name Value="North Pole" />
+  <Illumination>
+    <Bulb ID="1" Parent="Backglass" Name="GameOver" B2SID="5" B2SValue="2" InitialState="1"
+          LocX="10" LocY="20" Width="40" Height="30" LightColor="255.128.0" Image="YnVs&#10;Yg==" />
+    <Bulb ID="2" Parent="DMD" Name="Credits" B2SID="6" LocX="0" LocY="0" Width="5" Height="5" />
+  </Illumination>
+  <Scores>
+    <Score ID="1" Parent="Backglass" B2SPlayerNo="2" Digits="7" ReelLitColor="0.255.0"
+           LocX="100" LocY="600" Width="300" Height="50" />
+  </Scores>
+  <Images>
+    <BackglassImage Value="aGVsbG8=" />
+  </Images>
+</DirectB2SData>"#;
+
+    #[test]
+    fn parses_backglass_bulbs_and_scores() {
+        let parsed = parse(B2S).unwrap();
+        assert_eq!(parsed.name.as_deref(), Some("North Pole"));
+        assert_eq!(parsed.backglass.as_deref(), Some(&b"hello"[..]));
+
+        let [(bulb, image)] = parsed.bulbs.as_slice() else {
+            panic!("expected only the backglass bulb, got {:?}", parsed.bulbs);
+        };
+        assert_eq!(bulb.name, "GameOver");
+        assert_eq!(bulb.b2s_id, Some(5));
+        assert_eq!(bulb.b2s_value, 2);
+        assert!(bulb.initially_on);
+        assert_eq!(
+            bulb.rect,
+            Rect::from_corners(Vec2::new(10.0, 20.0), Vec2::new(50.0, 50.0))
+        );
+        assert_eq!(bulb.color, Color::srgb_u8(255, 128, 0));
+        assert_eq!(image.as_deref(), Some(&b"bulb"[..]));
+
+        let [score] = parsed.scores.as_slice() else {
+            panic!("expected one score, got {:?}", parsed.scores);
+        };
+        assert_eq!(score.player, 1);
+        assert_eq!(score.digits, 7);
+        assert_eq!(
+            score.rect,
+            Rect::from_corners(Vec2::new(100.0, 600.0), Vec2::new(400.0, 650.0))
+        );
+        assert_eq!(score.color, Color::srgb_u8(0, 255, 0));
+    }
+
+    #[test]
+    fn rejects_other_xml() {
+        assert!(matches!(
+            parse("<DirectB2SDataX />"),
+            Err(DirectB2sError::NotDirectB2s)
+        ));
+    }
+}
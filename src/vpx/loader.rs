@@ -1,6 +1,9 @@
-use crate::vpx::VpxAsset;
+use crate::vbscript;
+use crate::vpx::material2d::VpxMaterial2d;
 use crate::vpx::triangulate::triangulate_polygon;
+use crate::vpx::{MaterialKey, TableIdentity, VpxAsset};
 use bevy::asset::{LoadDirectError, RenderAssetUsages};
+use bevy::color::palettes::css;
 use bevy::image::{CompressedImageFormats, ImageLoader, ImageLoaderError};
 use bevy::mesh::{Indices, PrimitiveTopology};
 use bevy::{
@@ -141,22 +144,22 @@ impl VpxLoader {
 
         let mut mesh_handles = Vec::new();
         let mut named_mesh_handles = HashMap::new();
-        // TODO where does the 100.0 factor come from?
-        let table_size = Vec2::new(
-            (vpx.gamedata.right - vpx.gamedata.left) / 100.0,
-            (vpx.gamedata.bottom - vpx.gamedata.top) / 100.0,
+        // images on items are stretched over the whole table, like in vpx
+        let table_rect = Rect::new(
+            vpx.gamedata.left,
+            vpx.gamedata.top,
+            vpx.gamedata.right,
+            vpx.gamedata.bottom,
         );
         if settings.load_meshes {
             for item in &vpx.gameitems {
                 match item {
                     GameItemEnum::Wall(wall) => {
-                        let top_height = vpu_to_m(wall.height_top);
                         let path = VpxAsset::wall_mesh_sub_path(&wall.name);
                         let handle = load_mesh_2d_from_drag_points(
-                            table_size,
+                            table_rect,
                             path.clone(),
                             &wall.drag_points,
-                            top_height,
                             load_context,
                         );
                         named_mesh_handles.insert(path.into_boxed_str(), handle.clone());
@@ -165,13 +168,11 @@ impl VpxLoader {
                     GameItemEnum::Rubber(rubber) => {
                         // a rubber is presented by a ring shape formed by the rubber.drag_points
                         // with the thickness rubber.thickness
-                        let top_height = vpu_to_m(rubber.height + rubber.thickness as f32 / 2.0);
                         let path = VpxAsset::rubber_mesh_sub_path(&rubber.name);
                         let handle = load_mesh_2d_from_drag_points(
-                            table_size,
+                            table_rect,
                             path.clone(),
                             &rubber.drag_points,
-                            top_height,
                             load_context,
                         );
                         named_mesh_handles.insert(path.into_boxed_str(), handle.clone());
@@ -182,6 +183,61 @@ impl VpxLoader {
             }
         }
 
+        // items share their materials, tables easily have hundreds of walls using a few materials
+        let mut named_material_handles = HashMap::new();
+        for material in vpx.gamedata.materials.iter().flatten() {
+            let key = MaterialKey::plain(&material.name, "");
+            let handle =
+                load_context.add_labeled_asset(key.label(), VpxMaterial2d::from_vpx(material));
+            named_material_handles.insert(key, handle);
+        }
+        let find_material = |name: &str| {
+            vpx.gamedata
+                .materials
+                .iter()
+                .flatten()
+                .find(|m| m.name.eq_ignore_ascii_case(name))
+        };
+        for item in &vpx.gameitems {
+            if let GameItemEnum::Bumper(bumper) = item {
+                for name in [&bumper.cap_material, &bumper.base_material] {
+                    let key = MaterialKey::round(name);
+                    if named_material_handles.contains_key(&key) {
+                        continue;
+                    }
+                    // bumpers without a material keep their default colors
+                    if let Some(material) = find_material(name) {
+                        let material = VpxMaterial2d {
+                            round: true,
+                            ..VpxMaterial2d::from_vpx(material)
+                        };
+                        let handle = load_context.add_labeled_asset(key.label(), material);
+                        named_material_handles.insert(key, handle);
+                    }
+                }
+            }
+            if let GameItemEnum::Wall(wall) = item {
+                let hidden = !wall.is_top_bottom_visible && !wall.is_side_visible;
+                let key = if hidden {
+                    MaterialKey::translucent(&wall.top_material, &wall.image)
+                } else {
+                    MaterialKey::plain(&wall.top_material, &wall.image)
+                };
+                if named_material_handles.contains_key(&key) {
+                    continue;
+                }
+                let mut material = find_material(&wall.top_material)
+                    .map(VpxMaterial2d::from_vpx)
+                    .unwrap_or_else(|| VpxMaterial2d::from(Color::from(css::PINK)));
+                material.texture = named_image_handles.get(wall.image.as_str()).cloned();
+                if hidden {
+                    material = material.translucent(0.5);
+                }
+                let handle = load_context.add_labeled_asset(key.label(), material);
+                named_material_handles.insert(key, handle);
+            }
+        }
+
         let custom_asset = VpxAsset {
             images: image_handles,
             named_images: named_image_handles,
@@ -189,6 +245,9 @@ impl VpxLoader {
             named_sounds: named_sound_handles,
             meshes: mesh_handles,
             named_meshes: named_mesh_handles,
+            named_materials: named_material_handles,
+            event_handlers: vbscript::scanner::scan(&vpx.gamedata.code.string),
+            identity: TableIdentity::new(&vpx),
             raw: vpx,
         };
 
@@ -252,33 +311,31 @@ async fn load_sound(
     Ok(handle)
 }
 
-/// Generates a flat 2D polygon mesh from the given drag points at the specified top height.
+/// Generates a flat 2D polygon mesh from the given drag points.
+///
+/// The texture coordinates follow vpx: an image is stretched over the whole table so that the item
+/// shows its slice of it. Drag points with a manual texture coordinate use it horizontally.
 fn load_mesh_2d_from_drag_points(
-    table_size: Vec2,
+    table_rect: Rect,
     label: String,
     drag_points: &Vec<DragPoint>,
-    top_height: f32,
     load_context: &mut LoadContext<'_>,
 ) -> Handle<Mesh> {
-    // Generate vertices for top face (all with the same height)
+    // Generate vertices for the top face, the height is left to the transform of the item
     let num_points = drag_points.len();
     let mut positions = Vec::with_capacity(num_points);
     let mut normals = Vec::with_capacity(num_points);
     let mut uvs = Vec::with_capacity(num_points);
 
     for point in drag_points {
-        // Position (x, top_height, y) -> Bevy uses y-up
-        positions.push([vpu_to_m(point.x), -vpu_to_m(point.y), top_height]);
+        // Bevy uses y-up
+        positions.push([vpu_to_m(point.x), -vpu_to_m(point.y), 0.0]);
         // Normal points up for the top face
         normals.push([0.0, 0.0, 1.0]);
-        if point.has_auto_texture {
-            uvs.push([point.x / table_size.x, point.y / table_size.y]);
-        } else {
-            warn!(
-                "Handle non-auto texture coordinates for mesh generation, should we use tex_coord?"
-            );
-            uvs.push([point.x, point.y]);
-        }
+        // VPX maps the top of a wall in table space, the manual `tex_coord` of a drag point is
+        // only used along the sides, which are not rendered yet
+        let uv = (Vec2::new(point.x, point.y) - table_rect.min) / table_rect.size();
+        uvs.push([uv.x, uv.y]);
     }
 
     // Triangulate the polygon using ear clipping (works for any polygon)
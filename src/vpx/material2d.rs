@@ -0,0 +1,159 @@
+//! A 2D material for VPX materials, which [`ColorMaterial`] can't express: tinted see-through
+//! plastics, a glossy layer reflecting the light and metals that tint their reflection.
+//!
+//! Seen from above a flat top shows little more than its color and a faint sheen. Round items like
+//! bumper caps fake their slope from the distance to their center, which gives them a highlight on
+//! the side facing the light and edges with the VPX edge opacity.
+
+use bevy::asset::embedded_asset;
+use bevy::math::Affine2;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_resource::{AsBindGroup, AsBindGroupShaderType, ShaderType};
+use bevy::render::texture::GpuImage;
+use bevy::shader::ShaderRef;
+use bevy::sprite_render::{AlphaMode2d, Material2d, Material2dPlugin};
+use vpin::vpx::material::{Material as VpxMaterial, MaterialType};
+
+const SHADER_PATH: &str = "embedded://vpinball2d/vpx/material2d.wgsl";
+
+pub(super) fn plugin(app: &mut App) {
+    embedded_asset!(app, "material2d.wgsl");
+    app.add_plugins(Material2dPlugin::<VpxMaterial2d>::default());
+}
+
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone)]
+#[reflect(Default, Debug, Clone)]
+#[uniform(0, VpxMaterial2dUniform)]
+pub struct VpxMaterial2d {
+    /// The color of the material, tinting the image.
+    pub base_color: Color,
+    /// The color of the light reflected by the glossy layer.
+    pub glossy_color: Color,
+    pub opacity: f32,
+    /// The opacity at the edges of round items.
+    pub edge_alpha: f32,
+    /// Metals reflect the light in their own color.
+    pub metal: bool,
+    /// Whether the item is round, see the module documentation.
+    pub round: bool,
+    pub uv_transform: Affine2,
+    #[texture(1)]
+    #[sampler(2)]
+    pub texture: Option<Handle<Image>>,
+    pub alpha_mode: AlphaMode2d,
+}
+
+impl Default for VpxMaterial2d {
+    fn default() -> Self {
+        Self {
+            base_color: Color::WHITE,
+            glossy_color: Color::BLACK,
+            opacity: 1.0,
+            edge_alpha: 1.0,
+            metal: false,
+            round: false,
+            uv_transform: Affine2::IDENTITY,
+            texture: None,
+            alpha_mode: AlphaMode2d::Opaque,
+        }
+    }
+}
+
+impl From<Color> for VpxMaterial2d {
+    fn from(base_color: Color) -> Self {
+        Self {
+            base_color,
+            ..default()
+        }
+    }
+}
+
+impl VpxMaterial2d {
+    /// Converts a VPX material, it is see-through when its opacity is active.
+    pub fn from_vpx(material: &VpxMaterial) -> Self {
+        let opacity = if material.opacity_active {
+            material.opacity.clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        let edge_alpha = if material.opacity_active {
+            material.edge_alpha.clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        Self {
+            base_color: Color::srgb_u8(
+                material.base_color.r,
+                material.base_color.g,
+                material.base_color.b,
+            ),
+            glossy_color: Color::srgb_u8(
+                material.glossy_color.r,
+                material.glossy_color.g,
+                material.glossy_color.b,
+            ),
+            opacity,
+            edge_alpha,
+            metal: matches!(material.type_, MaterialType::Metal),
+            alpha_mode: if opacity < 1.0 || edge_alpha < 1.0 {
+                AlphaMode2d::Blend
+            } else {
+                AlphaMode2d::Opaque
+            },
+            ..default()
+        }
+    }
+
+    /// Shows the material see-through, without changing its look otherwise.
+    pub fn translucent(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self.edge_alpha = self.edge_alpha.min(opacity);
+        self.alpha_mode = AlphaMode2d::Blend;
+        self
+    }
+}
+
+/// The material as the shader sees it, see `material2d.wgsl`.
+#[derive(Clone, Default, ShaderType)]
+pub struct VpxMaterial2dUniform {
+    base_color: Vec4,
+    glossy_color: Vec4,
+    uv_transform: Mat3,
+    opacity: f32,
+    edge_alpha: f32,
+    flags: u32,
+}
+
+const FLAG_METAL: u32 = 1;
+const FLAG_ROUND: u32 = 2;
+
+impl AsBindGroupShaderType<VpxMaterial2dUniform> for VpxMaterial2d {
+    fn as_bind_group_shader_type(&self, _images: &RenderAssets<GpuImage>) -> VpxMaterial2dUniform {
+        let mut flags = 0;
+        if self.metal {
+            flags |= FLAG_METAL;
+        }
+        if self.round {
+            flags |= FLAG_ROUND;
+        }
+        VpxMaterial2dUniform {
+            base_color: LinearRgba::from(self.base_color).to_vec4(),
+            glossy_color: LinearRgba::from(self.glossy_color).to_vec4(),
+            uv_transform: self.uv_transform.into(),
+            opacity: self.opacity,
+            edge_alpha: self.edge_alpha,
+            flags,
+        }
+    }
+}
+
+impl Material2d for VpxMaterial2d {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode2d {
+        self.alpha_mode
+    }
+}
@@ -0,0 +1,53 @@
+// The look of a VPX material seen from above, see material2d.rs.
+
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+struct VpxMaterial2d {
+    base_color: vec4<f32>,
+    glossy_color: vec4<f32>,
+    uv_transform: mat3x3<f32>,
+    opacity: f32,
+    edge_alpha: f32,
+    flags: u32,
+};
+
+const FLAG_METAL: u32 = 1u;
+const FLAG_ROUND: u32 = 2u;
+// lit from the top left, like the drop shadows
+const LIGHT_DIRECTION: vec2<f32> = vec2<f32>(-0.70710677, 0.70710677);
+// the part of the glossy color a flat top reflects
+const FLAT_SHEEN: f32 = 0.1;
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(0) var<uniform> material: VpxMaterial2d;
+@group(#{MATERIAL_BIND_GROUP}) @binding(1) var texture: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(2) var texture_sampler: sampler;
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    let uv = (material.uv_transform * vec3<f32>(mesh.uv, 1.0)).xy;
+    let image = textureSample(texture, texture_sampler, uv);
+    var color = image.rgb * material.base_color.rgb;
+    var alpha = image.a * material.opacity;
+
+    // a round item slopes down from its center, uv y points down
+    var slope = vec2<f32>(0.0);
+    var rim = 0.0;
+    if (material.flags & FLAG_ROUND) != 0u {
+        slope = (mesh.uv - vec2<f32>(0.5)) * vec2<f32>(2.0, -2.0);
+        rim = smoothstep(0.6, 1.0, length(slope));
+        alpha = mix(alpha, image.a * material.edge_alpha, rim);
+    }
+
+    // the glossy layer reflects the light where the item faces it
+    let facing = clamp(dot(slope, LIGHT_DIRECTION), 0.0, 1.0);
+    let highlight = FLAT_SHEEN + pow(facing, 3.0) * (1.0 - FLAT_SHEEN);
+    var gloss = material.glossy_color.rgb * highlight;
+    if (material.flags & FLAG_METAL) != 0u {
+        // metals reflect in their own color and hardly show their base color
+        gloss = gloss * material.base_color.rgb;
+        color = color * 0.5;
+    }
+    // the reflection stays visible on see-through plastics
+    let reflected = max(gloss.r, max(gloss.g, gloss.b));
+    return vec4<f32>(color + gloss, max(alpha, reflected * (1.0 - rim)));
+}
@@ -1,8 +1,11 @@
 use bevy::prelude::*;
+use directb2s::DirectB2sLoader;
 use loader::VpxLoader;
 
 pub mod assets;
+pub mod directb2s;
 mod loader;
+pub mod material2d;
 // TODO make this private again after the code has been moved
 pub mod triangulate;
 
@@ -14,8 +17,12 @@ impl Plugin for VpxPlugin {
     fn build(&self, app: &mut App) {
         app.init_asset::<VpxAsset>()
             .preregister_asset_loader::<VpxLoader>(&["vpx"]);
+        app.init_asset::<directb2s::DirectB2sAsset>()
+            .preregister_asset_loader::<DirectB2sLoader>(&["directb2s"]);
+        material2d::plugin(app);
     }
     fn finish(&self, app: &mut App) {
         app.register_asset_loader(VpxLoader {});
+        app.register_asset_loader(DirectB2sLoader);
     }
 }